@@ -0,0 +1,435 @@
+// Automation for ASCII text adventures, the kind where the droid reports each room as
+//
+//   == Hull Breach ==
+//   You got in through a hole in the floor here.
+//
+//   Doors here lead:
+//   - north
+//   - east
+//
+//   Items here:
+//   - mug
+//
+//   Command?
+//
+// and takes `north`/`south`/`east`/`west`, `take <item>`, `drop <item>` and `inv`. A
+// `Session` wraps any machine running one and keeps the commands it was sent, so a session
+// can be saved and played back. `World::explore` maps every room while picking up whatever
+// isn't a known trap, and `crack` then finds which of the items carried get the droid past
+// the pressure-sensitive floor behind the security checkpoint.
+use std::collections::{BTreeMap, VecDeque};
+use std::io::{self, BufRead, Write};
+use crate::{ascii, IntCodeComputer, State};
+
+// items that end the game or hang it when taken
+pub const TRAPS: [&str; 5] = ["escape pod", "giant electromagnet", "infinite loop", "molten lava", "photons"];
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Room {
+    pub name: String,
+    pub description: String,
+    pub doors: Vec<String>,
+    pub items: Vec<String>,
+}
+
+// Every room described in `text`, in order. Walking into the checkpoint with the wrong weight
+// describes two: the floor, then the checkpoint the droid was thrown back to.
+pub fn parse_rooms(text: &str) -> Vec<Room> {
+    let mut rooms = Vec::new();
+    let mut list: Option<bool> = None;
+    for line in text.lines().map(str::trim) {
+        if line.starts_with("== ") && line.ends_with(" ==") && line.len() >= 6 {
+            rooms.push(Room { name: line[3..line.len() - 3].to_string(), ..Room::default() });
+            list = None;
+            continue;
+        }
+        let room = match rooms.last_mut() {
+            Some(room) => room,
+            None => continue,
+        };
+        match (line, list) {
+            ("Doors here lead:", _) => list = Some(true),
+            ("Items here:", _) => list = Some(false),
+            ("", _) => list = None,
+            (_, Some(doors)) if line.starts_with("- ") => {
+                let entry = line[2..].to_string();
+                match doors {
+                    true => room.doors.push(entry),
+                    false => room.items.push(entry),
+                }
+            },
+            (_, None) if room.doors.is_empty() && room.items.is_empty() && line != "Command?" => {
+                if !room.description.is_empty() {
+                    room.description.push('\n');
+                }
+                room.description.push_str(line);
+            },
+            _ => (),
+        }
+    }
+    rooms
+}
+
+// what `inv` lists, or `None` if `text` isn't an answer to it
+pub fn parse_inventory(text: &str) -> Option<Vec<String>> {
+    if text.contains("You aren't carrying any items.") {
+        return Some(Vec::new());
+    }
+    let start = text.find("Items in your inventory:")?;
+    Some(text[start..].lines()
+        .skip(1)
+        .map(str::trim)
+        .take_while(|line| line.starts_with("- "))
+        .map(|line| line[2..].to_string())
+        .collect())
+}
+
+// the code the game hands out at the end, the first run of digits after "typing"
+pub fn keypad_code(text: &str) -> Option<String> {
+    let after = &text[text.find("typing")?..];
+    let start = after.find(|c: char| c.is_ascii_digit())?;
+    let digits: String = after[start..].chars().take_while(char::is_ascii_digit).collect();
+    Some(digits)
+}
+
+pub fn reverse(door: &str) -> Option<&'static str> {
+    match door {
+        "north" => Some("south"),
+        "south" => Some("north"),
+        "east" => Some("west"),
+        "west" => Some("east"),
+        _ => None,
+    }
+}
+
+// Everything the program printed in answer to one command. `Waiting` is the normal case,
+// the program back at its prompt; `Running` means it ran out of cycles without getting there.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Reply {
+    pub text: String,
+    pub state: State,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum AdventureError {
+    // the program didn't come back to its prompt
+    Stopped { command: String, reply: Reply },
+    // a command got an answer that doesn't fit what it should do
+    Unexpected { command: String, reply: Reply },
+    // exploring never found a door that throws the droid back
+    NoCheckpoint,
+    // the map doesn't connect the two rooms
+    NoRoute { from: String, to: String },
+    // no combination of the items carried was the right weight
+    NoCombination,
+    // too many items to try every combination of
+    TooManyItems { count: usize },
+}
+
+#[derive(Debug, Clone)]
+pub struct Session<C> {
+    comp: C,
+    commands: Vec<String>,
+    max_cycles: Option<u64>,
+    last: Option<Reply>,
+    // where the droid is, as far as the replies so far tell
+    room: Option<Room>,
+}
+
+impl<C: IntCodeComputer> Session<C> {
+    pub fn new(comp: C) -> Session<C> {
+        Session { comp, commands: Vec::new(), max_cycles: None, last: None, room: None }
+    }
+
+    // give up on a command after this many instructions, for the traps that never return
+    pub fn with_max_cycles(mut self, max_cycles: u64) -> Session<C> {
+        self.max_cycles = Some(max_cycles);
+        self
+    }
+
+    // Runs the program up to its first prompt. Everything else starts the session too, and
+    // once it's started this just hands back the latest reply.
+    pub fn start(&mut self) -> Reply {
+        match &self.last {
+            Some(reply) => reply.clone(),
+            None => self.exchange(None),
+        }
+    }
+
+    pub fn send(&mut self, command: &str) -> Reply {
+        self.start();
+        self.commands.push(command.to_string());
+        self.exchange(Some(command))
+    }
+
+    fn exchange(&mut self, command: Option<&str>) -> Reply {
+        let seen = self.comp.out().len();
+        if let Some(command) = command {
+            ascii::push_line(&mut self.comp, command);
+        }
+        let state = self.run();
+        let text = ascii::decode(&self.comp.out()[seen..]);
+        if let Some(room) = parse_rooms(&text).pop() {
+            self.room = Some(room);
+        }
+        let reply = Reply { text, state };
+        self.last = Some(reply.clone());
+        reply
+    }
+
+    fn run(&mut self) -> State {
+        let max_cycles = match self.max_cycles {
+            Some(max_cycles) => max_cycles,
+            None => return self.comp.run(),
+        };
+        let start = self.comp.cycles();
+        while self.comp.cycles() - start < max_cycles {
+            match self.comp.step() {
+                State::Running => continue,
+                state => return state,
+            }
+        }
+        State::Running
+    }
+
+    // `send`, for commands that have to leave the program at its prompt
+    fn expect(&mut self, command: &str) -> Result<Reply, AdventureError> {
+        let reply = self.send(command);
+        match reply.state {
+            State::Waiting => Ok(reply),
+            _ => Err(AdventureError::Stopped { command: command.to_string(), reply }),
+        }
+    }
+
+    pub fn room(&self) -> Option<&Room> {
+        self.room.as_ref()
+    }
+
+    pub fn last(&self) -> Option<&Reply> {
+        self.last.as_ref()
+    }
+
+    pub fn inventory(&mut self) -> Result<Vec<String>, AdventureError> {
+        let reply = self.expect("inv")?;
+        match parse_inventory(&reply.text) {
+            Some(items) => Ok(items),
+            None => Err(AdventureError::Unexpected { command: "inv".to_string(), reply }),
+        }
+    }
+
+    // every command sent so far, oldest first
+    pub fn transcript(&self) -> &[String] {
+        &self.commands
+    }
+
+    // the transcript, one command per line
+    pub fn save(&self) -> String {
+        self.commands.iter().map(|command| format!("{}\n", command)).collect()
+    }
+
+    // Sends every command in a saved transcript, returning the reply to the last one. Blank
+    // lines are skipped. Only the last command may leave the program stopped, so a saved win
+    // plays back as one.
+    pub fn load(&mut self, saved: &str) -> Result<Reply, AdventureError> {
+        let mut reply = self.start();
+        let mut commands = saved.lines().map(str::trim).filter(|line| !line.is_empty()).peekable();
+        while let Some(command) = commands.next() {
+            reply = match commands.peek() {
+                Some(_) => self.expect(command)?,
+                None => self.send(command),
+            };
+        }
+        Ok(reply)
+    }
+
+    pub fn comp(&self) -> &C {
+        &self.comp
+    }
+
+    pub fn into_inner(self) -> C {
+        self.comp
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct World {
+    // rooms as they were first seen, items and all
+    pub rooms: BTreeMap<String, Room>,
+    // (room, door) to the room it leads to
+    pub doors: BTreeMap<(String, String), String>,
+    // the room in front of the pressure-sensitive floor and the door onto it
+    pub checkpoint: Option<(String, String)>,
+}
+
+impl World {
+    // Walks every door depth first, taking every item that isn't in `avoid`, and comes back
+    // to the room it started in.
+    pub fn explore<C: IntCodeComputer>(session: &mut Session<C>, avoid: &[&str]) -> Result<World, AdventureError> {
+        let reply = session.start();
+        let room = match session.room() {
+            Some(room) => room.clone(),
+            None => return Err(AdventureError::Unexpected { command: String::new(), reply }),
+        };
+        let mut world = World::default();
+        world.rooms.insert(room.name.clone(), room.clone());
+        world.visit(session, avoid, room)?;
+        Ok(world)
+    }
+
+    fn visit<C: IntCodeComputer>(&mut self, session: &mut Session<C>, avoid: &[&str], room: Room) -> Result<(), AdventureError> {
+        for item in room.items.iter().filter(|item| !avoid.contains(&item.as_str())) {
+            session.expect(&format!("take {}", item))?;
+        }
+        for door in room.doors.iter() {
+            let key = (room.name.clone(), door.clone());
+            if self.doors.contains_key(&key) {
+                continue;
+            }
+            let reply = session.expect(door)?;
+            let rooms = parse_rooms(&reply.text);
+            let (entered, now) = match (rooms.first(), rooms.last()) {
+                (Some(entered), Some(now)) => (entered.clone(), now.clone()),
+                _ => return Err(AdventureError::Unexpected { command: door.clone(), reply }),
+            };
+            self.doors.insert(key, entered.name.clone());
+            let first_visit = !self.rooms.contains_key(&entered.name);
+            self.rooms.entry(entered.name.clone()).or_insert_with(|| entered.clone());
+            if now.name != entered.name {
+                self.checkpoint = Some((room.name.clone(), door.clone()));
+                continue;
+            }
+            let back = match reverse(door) {
+                Some(back) => back,
+                None => return Err(AdventureError::Unexpected { command: door.clone(), reply }),
+            };
+            self.doors.insert((entered.name.clone(), back.to_string()), room.name.clone());
+            if first_visit {
+                self.visit(session, avoid, entered)?;
+            }
+            session.expect(back)?;
+        }
+        Ok(())
+    }
+
+    // the doors to take from one room to get to another, never through the checkpoint
+    pub fn route(&self, from: &str, to: &str) -> Option<Vec<String>> {
+        let mut came: BTreeMap<&str, (&str, &str)> = BTreeMap::new();
+        let mut queue = VecDeque::new();
+        queue.push_back(from);
+        while let Some(room) = queue.pop_front() {
+            if room == to {
+                let mut route = Vec::new();
+                let mut at = to;
+                while at != from {
+                    let (prev, door) = came[at];
+                    route.push(door.to_string());
+                    at = prev;
+                }
+                route.reverse();
+                return Some(route);
+            }
+            for ((src, door), dest) in self.doors.iter().filter(|((src, _), _)| src == room) {
+                let through = Some((src.clone(), door.clone()));
+                if dest != from && !came.contains_key(dest.as_str()) && through != self.checkpoint {
+                    came.insert(dest, (room, door));
+                    queue.push_back(dest);
+                }
+            }
+        }
+        None
+    }
+
+    // takes the droid from wherever it is to `to`
+    pub fn goto<C: IntCodeComputer>(&self, session: &mut Session<C>, to: &str) -> Result<(), AdventureError> {
+        let from = session.room().map(|room| room.name.clone()).unwrap_or_default();
+        let route = self.route(&from, to)
+            .ok_or_else(|| AdventureError::NoRoute { from: from.clone(), to: to.to_string() })?;
+        for door in route {
+            session.expect(&door)?;
+        }
+        Ok(())
+    }
+}
+
+// Goes to the checkpoint and tries combinations of the items carried until the floor lets
+// the droid through, returning the reply from the other side. Each verdict prunes the rest:
+// anything inside a set that was too light is too light, and anything around a set that was
+// too heavy is too heavy.
+pub fn crack<C: IntCodeComputer>(session: &mut Session<C>, world: &World) -> Result<Reply, AdventureError> {
+    let (room, door) = world.checkpoint.clone().ok_or(AdventureError::NoCheckpoint)?;
+    world.goto(session, &room)?;
+    let items = session.inventory()?;
+    if items.len() >= 64 {
+        return Err(AdventureError::TooManyItems { count: items.len() });
+    }
+    let all = (1u64 << items.len()) - 1;
+    let mut held = all;
+    let (mut light, mut heavy): (Vec<u64>, Vec<u64>) = (Vec::new(), Vec::new());
+    for mask in 0..=all {
+        if light.iter().any(|l| mask & !l == 0) || heavy.iter().any(|h| h & !mask == 0) {
+            continue;
+        }
+        for (i, item) in items.iter().enumerate() {
+            match ((held >> i) & 1, (mask >> i) & 1) {
+                (1, 0) => session.expect(&format!("drop {}", item))?,
+                (0, 1) => session.expect(&format!("take {}", item))?,
+                _ => continue,
+            };
+        }
+        held = mask;
+        let reply = session.send(&door);
+        let thrown_back = parse_rooms(&reply.text).last().map(|now| now.name == room).unwrap_or(false);
+        match (&reply.state, thrown_back) {
+            (State::Waiting, true) => (),
+            (State::Waiting, false) | (State::Halted, _) => return Ok(reply),
+            _ => return Err(AdventureError::Stopped { command: door, reply }),
+        }
+        // the droids on the ship are heavier than us, so we're too light
+        if reply.text.contains("heavier than the detected value") {
+            light.push(mask);
+        } else if reply.text.contains("lighter than the detected value") {
+            heavy.push(mask);
+        }
+    }
+    Err(AdventureError::NoCombination)
+}
+
+// Lets a person play through a session. Besides the game's own commands, `n`, `s`, `e`
+// and `w` move, `!history` lists the commands so far and `!save <path>` writes them out for
+// `Session::load`. Returns when the input runs out or the program stops.
+pub fn play<C, R, O>(session: &mut Session<C>, input: R, mut output: O) -> io::Result<()>
+where C: IntCodeComputer, R: BufRead, O: Write {
+    let reply = session.start();
+    write!(output, "{}", reply.text)?;
+    if reply.state != State::Waiting {
+        return Ok(());
+    }
+    for line in input.lines() {
+        let line = line?;
+        let command = match line.trim() {
+            "n" => "north",
+            "s" => "south",
+            "e" => "east",
+            "w" => "west",
+            other => other,
+        };
+        if command == "!history" {
+            for (i, command) in session.transcript().iter().enumerate() {
+                writeln!(output, "{:>4}  {}", i + 1, command)?;
+            }
+            continue;
+        }
+        if let Some(path) = command.strip_prefix("!save ") {
+            std::fs::write(path.trim(), session.save())?;
+            writeln!(output, "saved {} commands to {}", session.transcript().len(), path.trim())?;
+            continue;
+        }
+        let reply = session.send(command);
+        write!(output, "{}", reply.text)?;
+        if reply.state != State::Waiting {
+            writeln!(output, "[{:?}]", reply.state)?;
+            return Ok(());
+        }
+    }
+    Ok(())
+}
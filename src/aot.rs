@@ -0,0 +1,557 @@
+// Ahead-of-time compilation of an image to Rust source, meant to be called from a build
+// script:
+//
+//   // build.rs
+//   let out = Path::new(&env::var("OUT_DIR").unwrap()).join("day9.rs");
+//   intcode_rs::aot::compile_file("res/09.txt", out, "Day9").unwrap();
+//
+//   // src/main.rs
+//   include!(concat!(env!("OUT_DIR"), "/day9.rs"));
+//   let mut comp = Day9::new(vec![1]);
+//
+// The generated type wraps a `Runtime` and implements `IntCodeComputer<i64>`. Every basic
+// block the control-flow graph reaches becomes a match arm of native Rust, with operands and
+// jump targets baked in; when the program counter lands anywhere else, one instruction goes
+// through the embedded `ProcIntCode` and native code picks up again at the next block start.
+// Immediates that are the address of an instruction, or of the cell after an unconditional
+// jump, also start blocks, which covers return addresses pushed before a call.
+//
+// Operand cells the program rewrites through position-mode stores (Intcode's stand-in for
+// pointers) are compiled as loads instead of constants. Any other write that changes a
+// compiled cell marks the runtime stale, and from then on everything is interpreted.
+//
+// Arithmetic wraps, as under the default `Overflow::Wrapping`, and there's no protection or
+// sandbox; machines that need those should stay on the interpreter.
+use fxhash::FxHashSet;
+use std::collections::BTreeSet;
+use std::fmt::Write;
+use std::fs;
+use std::io;
+use std::path::Path;
+use crate::{Fault, IntCodeComputer, State};
+use crate::cfg::{Cfg, EdgeKind};
+use crate::disasm::Line;
+use crate::instruction::{Instruction, Op, ParamMode};
+use crate::procedural_comp::ProcIntCode;
+use crate::syscall::Frame;
+
+// the compiled half of a program: runs blocks until it stops, can't go on, or the program
+// counter isn't at a block start, in which case it returns `State::Running`
+pub type Native = fn(&mut Runtime) -> Result<State, Fault>;
+
+#[derive(Debug)]
+pub struct Runtime {
+    comp: ProcIntCode,
+    // which cells the native code has baked in, by address
+    code: Vec<bool>,
+    native: Native,
+    stale: bool,
+}
+
+// The accessors are what generated code calls; they skip all the checks `ProcIntCode` makes,
+// since there's nothing configured to check.
+impl Runtime {
+    pub fn new(image: Vec<i64>, inputs: Vec<i64>, code: &[(i64, i64)], native: Native) -> Runtime {
+        let mut compiled = vec![false; image.len()];
+        for (start, end) in code.iter() {
+            for addr in *start..*end {
+                compiled[addr as usize] = true;
+            }
+        }
+        Runtime { comp: ProcIntCode::new(image, inputs), code: compiled, native, stale: false }
+    }
+
+    pub fn register_syscall<F>(&mut self, service: i64, handler: F)
+    where F: Fn(&mut Frame<i64>) -> Result<i64, String> + Send + Sync + 'static {
+        self.comp.register_syscall(service, handler);
+    }
+
+    // the program has rewritten compiled code, so only the interpreter runs it now
+    pub fn stale(&self) -> bool {
+        self.stale
+    }
+
+    pub fn pc(&self) -> i64 {
+        self.comp.pc
+    }
+
+    pub fn goto(&mut self, pc: i64) {
+        self.comp.pc = pc;
+    }
+
+    pub fn get(&self, addr: i64) -> i64 {
+        self.comp.mem.get(addr)
+    }
+
+    pub fn put(&mut self, addr: i64, val: i64) {
+        if self.compiled(addr) && self.get(addr) != val {
+            self.stale = true;
+        }
+        self.comp.mem.set(addr, val);
+    }
+
+    // the address of [rb+offset] for the instruction at `pc`
+    pub fn rel(&self, pc: i64, offset: i64) -> Result<i64, Fault> {
+        self.comp.rb.checked_add(offset).ok_or(Fault::Address { pc })
+    }
+
+    pub fn shift(&mut self, pc: i64, offset: i64) -> Result<(), Fault> {
+        self.comp.rb = self.rel(pc, offset)?;
+        Ok(())
+    }
+
+    pub fn input(&mut self) -> Option<i64> {
+        match self.comp.inputs.is_empty() {
+            true => None,
+            false => Some(self.comp.inputs.remove(0)),
+        }
+    }
+
+    pub fn output(&mut self, val: i64) {
+        self.comp.outputs.push(val);
+    }
+
+    // counts one finished instruction
+    pub fn retire(&mut self) {
+        self.comp.cycles += 1;
+    }
+
+    fn compiled(&self, addr: i64) -> bool {
+        addr >= 0 && self.code.get(addr as usize).copied().unwrap_or(false)
+    }
+
+    // one instruction on the interpreter, watching its writes for changes to compiled code
+    fn interpret(&mut self) -> State {
+        self.comp.mem.begin_journal();
+        let state = self.comp.step();
+        let (writes, _) = self.comp.mem.end_journal();
+        for (addr, old) in writes {
+            if self.compiled(addr) && old.unwrap_or_else(|| self.comp.mem.original(addr)) != self.get(addr) {
+                self.stale = true;
+            }
+        }
+        state
+    }
+}
+
+impl IntCodeComputer for Runtime {
+    fn run(&mut self) -> State {
+        if let Some(fault) = &self.comp.fault {
+            return State::Faulted(fault.clone());
+        }
+        loop {
+            if !self.stale {
+                match (self.native)(self) {
+                    Ok(State::Running) => (),
+                    Ok(state) => return state,
+                    Err(fault) => {
+                        if let Fault::Address { pc } = fault {
+                            self.comp.pc = pc;
+                        }
+                        self.comp.fault = Some(fault.clone());
+                        return State::Faulted(fault);
+                    },
+                }
+            }
+            match self.interpret() {
+                State::Running => continue,
+                state => return state,
+            }
+        }
+    }
+
+    fn out(&self) -> &Vec<i64> {
+        &self.comp.outputs
+    }
+
+    fn push(&mut self, val: i64) {
+        self.comp.push(val);
+    }
+
+    fn mem(&self, at: i64) -> i64 {
+        self.get(at)
+    }
+
+    fn state(&self) -> State {
+        self.comp.state()
+    }
+
+    // always interpreted, since compiled blocks can't stop halfway
+    fn step(&mut self) -> State {
+        self.interpret()
+    }
+
+    fn cycles(&self) -> u64 {
+        self.comp.cycles()
+    }
+
+    fn queued(&self) -> usize {
+        self.comp.queued()
+    }
+
+    fn pc(&self) -> i64 {
+        self.comp.pc
+    }
+
+    fn set_pc(&mut self, pc: i64) {
+        self.goto(pc);
+    }
+
+    fn rb(&self) -> i64 {
+        self.comp.rb
+    }
+
+    fn set_rb(&mut self, rb: i64) {
+        self.comp.rb = rb;
+    }
+
+    // poking compiled code hands it back to the interpreter, same as the program doing it
+    fn poke(&mut self, at: i64, val: i64) {
+        self.put(at, val);
+    }
+
+    fn inputs(&self) -> &[i64] {
+        self.comp.inputs()
+    }
+
+    fn image_size(&self) -> i64 {
+        self.comp.image_size()
+    }
+
+    fn touched(&self) -> Vec<i64> {
+        self.comp.touched()
+    }
+}
+
+struct Compiler<'a> {
+    image: &'a [i64],
+    cfg: Cfg,
+    // operand cells written through position-mode stores, read at runtime
+    patched: FxHashSet<i64>,
+    // cells baked into the native code
+    code: BTreeSet<i64>,
+}
+
+impl<'a> Compiler<'a> {
+    fn new(image: &'a [i64]) -> Compiler<'a> {
+        let cfg = Compiler::discover(image);
+        let mut operands = FxHashSet::default();
+        for (addr, instr) in Compiler::instrs(&cfg) {
+            operands.extend(addr + 1..addr + instr.size());
+        }
+        let mut patched = FxHashSet::default();
+        for (_, instr) in Compiler::instrs(&cfg) {
+            let dest = match instr.op {
+                Op::Add | Op::Mul | Op::LessThan | Op::Equals => &instr.operands[2],
+                Op::Read => &instr.operands[0],
+                _ => continue,
+            };
+            if dest.mode != ParamMode::Relative && operands.contains(&dest.val) {
+                patched.insert(dest.val);
+            }
+        }
+        let code = Compiler::instrs(&cfg)
+            .flat_map(|(addr, instr)| addr..addr + instr.size())
+            .filter(|addr| !patched.contains(addr))
+            .collect();
+        Compiler { image, cfg, patched, code }
+    }
+
+    // The control-flow graph, with every immediate that points at an instruction as an extra
+    // entry. The cell after an unconditional jump counts too, since that's where a call
+    // returns to, and new code found that way is searched again.
+    fn discover(image: &[i64]) -> Cfg {
+        let mut entries = vec![0];
+        loop {
+            let cfg = Cfg::build_from(image, &entries);
+            let mut targets: FxHashSet<i64> = cfg.instructions().into_iter().collect();
+            for block in cfg.blocks() {
+                let jumps = block.successors.iter().all(|edge| edge.kind == EdgeKind::Taken);
+                if jumps && !block.successors.is_empty() {
+                    targets.insert(block.end);
+                }
+            }
+            let mut found = entries.clone();
+            for (_, instr) in Compiler::instrs(&cfg) {
+                for operand in instr.operands() {
+                    if operand.mode == ParamMode::Immediate && targets.contains(&operand.val) {
+                        found.push(operand.val);
+                    }
+                }
+            }
+            found.sort_unstable();
+            found.dedup();
+            if found == entries {
+                return cfg;
+            }
+            entries = found;
+        }
+    }
+
+    fn instrs(cfg: &Cfg) -> impl Iterator<Item = (i64, Instruction)> + '_ {
+        cfg.blocks()
+            .flat_map(|block| block.lines.iter())
+            .filter_map(|line| Some((line.addr(), line.instr()?)))
+    }
+
+    // the operand's word as written in the image, or a load if the program rewrites it
+    fn raw(&self, addr: i64, instr: &Instruction, i: usize) -> String {
+        let cell = addr + 1 + i as i64;
+        match self.patched.contains(&cell) {
+            true => format!("rt.get({})", cell),
+            false => instr.operands[i].val.to_string(),
+        }
+    }
+
+    fn constant(&self, addr: i64, instr: &Instruction, i: usize) -> Option<i64> {
+        let operand = &instr.operands[i];
+        match operand.mode == ParamMode::Immediate && !self.patched.contains(&(addr + 1 + i as i64)) {
+            true => Some(operand.val),
+            false => None,
+        }
+    }
+
+    fn read(&self, addr: i64, instr: &Instruction, i: usize) -> String {
+        let raw = self.raw(addr, instr, i);
+        match instr.operands[i].mode {
+            ParamMode::Immediate => raw,
+            ParamMode::Relative => format!("rt.get(rt.rel({}, {})?)", addr, raw),
+            ParamMode::Position => format!("rt.get({})", raw),
+        }
+    }
+
+    fn dest(&self, addr: i64, instr: &Instruction, i: usize) -> String {
+        let raw = self.raw(addr, instr, i);
+        match instr.operands[i].mode {
+            ParamMode::Relative => format!("rt.rel({}, {})?", addr, raw),
+            _ => raw,
+        }
+    }
+
+    // whether a store through this operand could land on compiled code
+    fn may_hit_code(&self, addr: i64, instr: &Instruction, i: usize) -> bool {
+        let operand = &instr.operands[i];
+        match (operand.mode, self.patched.contains(&(addr + 1 + i as i64))) {
+            (ParamMode::Relative, _) | (_, true) => true,
+            _ => self.code.contains(&operand.val),
+        }
+    }
+
+    // the value an `add`, `mul`, `lt` or `eq` stores
+    fn value(&self, addr: i64, instr: &Instruction) -> String {
+        if let (Some(a), Some(b)) = (self.constant(addr, instr, 0), self.constant(addr, instr, 1)) {
+            let val = match instr.op {
+                Op::Add => a.wrapping_add(b),
+                Op::Mul => a.wrapping_mul(b),
+                Op::LessThan => (a < b) as i64,
+                _ => (a == b) as i64,
+            };
+            return val.to_string();
+        }
+        let (a, b) = (self.read(addr, instr, 0), self.read(addr, instr, 1));
+        match instr.op {
+            Op::Add => format!("i64::wrapping_add({}, {})", a, b),
+            Op::Mul => format!("i64::wrapping_mul({}, {})", a, b),
+            // comparing a load with itself is a constant, and clippy won't have it otherwise
+            Op::LessThan if a == b => "0".to_string(),
+            Op::Equals if a == b => "1".to_string(),
+            Op::LessThan => format!("i64::from({} < {})", a, b),
+            _ => format!("i64::from({} == {})", a, b),
+        }
+    }
+
+    // Code for one instruction, one line per entry. Returns true if it ends the block.
+    fn instr(&self, addr: i64, instr: &Instruction, text: &str, out: &mut Vec<String>) -> bool {
+        let next = addr + instr.size();
+        out.push(format!("// {}: {}", addr, text));
+        match instr.op {
+            Op::Add | Op::Mul | Op::LessThan | Op::Equals => {
+                out.push(format!("rt.put({}, {});", self.dest(addr, instr, 2), self.value(addr, instr)));
+                out.push("rt.retire();".to_string());
+                self.bail(addr, instr, 2, out);
+            },
+            Op::Read => {
+                out.push("match rt.input() {".to_string());
+                out.push(format!("    Some(val) => rt.put({}, val),", self.dest(addr, instr, 0)));
+                out.push("    None => {".to_string());
+                out.push(format!("        rt.goto({});", addr));
+                out.push("        return Ok(State::Waiting);".to_string());
+                out.push("    },".to_string());
+                out.push("}".to_string());
+                out.push("rt.retire();".to_string());
+                self.bail(addr, instr, 0, out);
+            },
+            Op::Write => {
+                out.push(format!("rt.output({});", self.read(addr, instr, 0)));
+                out.push("rt.retire();".to_string());
+            },
+            Op::JumpIfTrue | Op::JumpIfFalse => {
+                let taken = self.constant(addr, instr, 0).map(|cond| (cond != 0) == (instr.op == Op::JumpIfTrue));
+                let target = self.read(addr, instr, 1);
+                out.push("rt.retire();".to_string());
+                match taken {
+                    Some(true) => out.push(format!("rt.goto({});", target)),
+                    Some(false) => out.push(format!("rt.goto({});", next)),
+                    None => {
+                        let test = match instr.op {
+                            Op::JumpIfTrue => "!=",
+                            _ => "==",
+                        };
+                        out.push(format!("if {} {} 0 {{", self.read(addr, instr, 0), test));
+                        out.push(format!("    rt.goto({});", target));
+                        out.push("} else {".to_string());
+                        out.push(format!("    rt.goto({});", next));
+                        out.push("}".to_string());
+                    },
+                }
+                return true;
+            },
+            Op::UpdateRb => {
+                out.push(format!("rt.shift({}, {})?;", addr, self.read(addr, instr, 0)));
+                out.push("rt.retire();".to_string());
+            },
+            // host calls go through the interpreter, which has the handlers
+            Op::Syscall => {
+                out.push(format!("rt.goto({});", addr));
+                out.push("return Ok(State::Running);".to_string());
+                return true;
+            },
+            Op::Halt => {
+                out.push(format!("rt.goto({});", addr));
+                out.push("return Ok(State::Halted);".to_string());
+                return true;
+            },
+        }
+        false
+    }
+
+    // after a store that might have rewritten compiled code, leave if it did
+    fn bail(&self, addr: i64, instr: &Instruction, dest: usize, out: &mut Vec<String>) {
+        if self.may_hit_code(addr, instr, dest) {
+            out.push("if rt.stale() {".to_string());
+            out.push(format!("    rt.goto({});", addr + instr.size()));
+            out.push("    return Ok(State::Running);".to_string());
+            out.push("}".to_string());
+        }
+    }
+
+    fn emit(&self, name: &str) -> String {
+        let mut out = String::new();
+        let indent = |depth: usize| "    ".repeat(depth);
+        writeln!(out, "// Generated by intcode_rs::aot from a {}-cell image. Don't edit by hand.", self.image.len()).unwrap();
+        writeln!(out, "pub struct {} {{", name).unwrap();
+        writeln!(out, "    rt: ::intcode_rs::aot::Runtime,").unwrap();
+        writeln!(out, "}}").unwrap();
+        writeln!(out).unwrap();
+
+        // not every program wants every accessor
+        writeln!(out, "#[allow(dead_code)]").unwrap();
+        writeln!(out, "impl {} {{", name).unwrap();
+        writeln!(out, "    const IMAGE: [i64; {}] = [", self.image.len()).unwrap();
+        for row in self.image.chunks(16) {
+            let row: Vec<String> = row.iter().map(i64::to_string).collect();
+            writeln!(out, "        {},", row.join(", ")).unwrap();
+        }
+        writeln!(out, "    ];").unwrap();
+        let mut ranges: Vec<(i64, i64)> = Vec::new();
+        for addr in self.code.iter() {
+            match ranges.last_mut() {
+                Some((_, end)) if *end == *addr => *end += 1,
+                _ => ranges.push((*addr, addr + 1)),
+            }
+        }
+        writeln!(out, "    const CODE: [(i64, i64); {}] = [", ranges.len()).unwrap();
+        for row in ranges.chunks(8) {
+            let row: Vec<String> = row.iter().map(|(start, end)| format!("({}, {})", start, end)).collect();
+            writeln!(out, "        {},", row.join(", ")).unwrap();
+        }
+        writeln!(out, "    ];").unwrap();
+        writeln!(out).unwrap();
+        writeln!(out, "    pub fn new(inputs: Vec<i64>) -> {} {{", name).unwrap();
+        writeln!(out, "        let rt = ::intcode_rs::aot::Runtime::new({}::IMAGE.to_vec(), inputs, &{}::CODE, {}::native);", name, name, name).unwrap();
+        writeln!(out, "        {} {{ rt }}", name).unwrap();
+        writeln!(out, "    }}").unwrap();
+        writeln!(out).unwrap();
+        writeln!(out, "    pub fn runtime(&self) -> &::intcode_rs::aot::Runtime {{").unwrap();
+        writeln!(out, "        &self.rt").unwrap();
+        writeln!(out, "    }}").unwrap();
+        writeln!(out).unwrap();
+        writeln!(out, "    pub fn runtime_mut(&mut self) -> &mut ::intcode_rs::aot::Runtime {{").unwrap();
+        writeln!(out, "        &mut self.rt").unwrap();
+        writeln!(out, "    }}").unwrap();
+        writeln!(out).unwrap();
+        writeln!(out, "    fn native(rt: &mut ::intcode_rs::aot::Runtime) -> Result<::intcode_rs::State, ::intcode_rs::Fault> {{").unwrap();
+        writeln!(out, "        use ::intcode_rs::State;").unwrap();
+        writeln!(out, "        loop {{").unwrap();
+        writeln!(out, "            match rt.pc() {{").unwrap();
+        for block in self.cfg.blocks() {
+            writeln!(out, "                {} => {{", block.start).unwrap();
+            let mut body = Vec::new();
+            let mut ended = false;
+            for line in block.lines.iter() {
+                if let (Line::Instr { addr, text, .. }, Some(instr)) = (line, line.instr()) {
+                    ended = self.instr(*addr, &instr, text, &mut body);
+                }
+            }
+            if !ended {
+                body.push(format!("rt.goto({});", block.end));
+            }
+            for line in body {
+                writeln!(out, "{}{}", indent(5), line).unwrap();
+            }
+            writeln!(out, "                }},").unwrap();
+        }
+        writeln!(out, "                _ => return Ok(State::Running),").unwrap();
+        writeln!(out, "            }}").unwrap();
+        writeln!(out, "        }}").unwrap();
+        writeln!(out, "    }}").unwrap();
+        writeln!(out, "}}").unwrap();
+        writeln!(out).unwrap();
+
+        writeln!(out, "impl ::intcode_rs::IntCodeComputer for {} {{", name).unwrap();
+        // called through the trait so the generated file doesn't need it imported
+        let methods = [
+            ("run(&mut self) -> ::intcode_rs::State", "run(&mut self.rt)"),
+            ("out(&self) -> &Vec<i64>", "out(&self.rt)"),
+            ("push(&mut self, val: i64)", "push(&mut self.rt, val)"),
+            ("mem(&self, at: i64) -> i64", "mem(&self.rt, at)"),
+            ("state(&self) -> ::intcode_rs::State", "state(&self.rt)"),
+            ("step(&mut self) -> ::intcode_rs::State", "step(&mut self.rt)"),
+            ("cycles(&self) -> u64", "cycles(&self.rt)"),
+            ("queued(&self) -> usize", "queued(&self.rt)"),
+            ("pc(&self) -> i64", "pc(&self.rt)"),
+            ("set_pc(&mut self, pc: i64)", "set_pc(&mut self.rt, pc)"),
+            ("rb(&self) -> i64", "rb(&self.rt)"),
+            ("set_rb(&mut self, rb: i64)", "set_rb(&mut self.rt, rb)"),
+            ("poke(&mut self, at: i64, val: i64)", "poke(&mut self.rt, at, val)"),
+            ("inputs(&self) -> &[i64]", "inputs(&self.rt)"),
+            ("image_size(&self) -> i64", "image_size(&self.rt)"),
+            ("touched(&self) -> Vec<i64>", "touched(&self.rt)"),
+        ];
+        for (i, (signature, call)) in methods.iter().enumerate() {
+            if i > 0 {
+                writeln!(out).unwrap();
+            }
+            writeln!(out, "    fn {} {{", signature).unwrap();
+            writeln!(out, "        ::intcode_rs::IntCodeComputer::{}", call).unwrap();
+            writeln!(out, "    }}").unwrap();
+        }
+        writeln!(out, "}}").unwrap();
+        out
+    }
+}
+
+// Rust source for a type called `name` that runs `image`, see the top of the file
+pub fn compile(image: &[i64], name: &str) -> String {
+    Compiler::new(image).emit(name)
+}
+
+// `compile` for build scripts: reads a comma-separated image and writes the source out
+pub fn compile_file<P: AsRef<Path>, Q: AsRef<Path>>(image: P, out: Q, name: &str) -> io::Result<()> {
+    let image: Vec<i64> = fs::read_to_string(image)?
+        .split(',')
+        .map(|val| val.trim().parse())
+        .collect::<Result<_, _>>()
+        .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+    fs::write(out, compile(&image, name))
+}
@@ -0,0 +1,27 @@
+// For programs that talk in text: every input and output is one character code, lines end in
+// a newline (10), and a value too big for ASCII is an answer rather than part of the text.
+use crate::IntCodeComputer;
+
+// `line` plus the newline that ends it
+pub fn encode(line: &str) -> Vec<i64> {
+    line.bytes().chain(Some(b'\n')).map(i64::from).collect()
+}
+
+pub fn push_line<C: IntCodeComputer + ?Sized>(comp: &mut C, line: &str) {
+    for val in encode(line) {
+        comp.push(val);
+    }
+}
+
+// the text in `out`, leaving out anything that isn't ASCII
+pub fn decode(out: &[i64]) -> String {
+    out.iter()
+        .filter(|val| (0..128).contains(*val))
+        .map(|val| *val as u8 as char)
+        .collect()
+}
+
+// the last value in `out` that can't be a character
+pub fn answer(out: &[i64]) -> Option<i64> {
+    out.iter().rev().copied().find(|val| !(0..128).contains(val))
+}
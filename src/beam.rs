@@ -0,0 +1,175 @@
+// For programs like the tractor beam drone system, which read one `(x, y)` and answer 1 if
+// that point is pulled by the beam or 0 if it isn't, then halt. Every probe goes through an
+// `Oracle`, so each point runs on a reset machine and is only ever run once.
+//
+// Grid scans hand the points out to scoped worker threads. Tracing doesn't look at every cell:
+// it takes the beam to be one unbroken run of cells on each row, starting no further left and
+// ending no further left than on the row above, which holds for a beam fanning out from the
+// origin. Each row then costs about as many probes as its edges moved.
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use crate::maze::Pos;
+use crate::oracle::{CallError, Oracle};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ProbeError {
+    Call { pos: Pos, err: CallError<i64> },
+    // anything but a single 0 or 1
+    BadAnswer { pos: Pos, outputs: Vec<i64> },
+    // row `y` ran on past ten cells per row down, so it isn't a beam fanning out from the origin
+    Unbounded { y: i64 },
+}
+
+// first and last x of the beam on a row, both inclusive
+pub type Span = (i64, i64);
+
+#[derive(Debug)]
+pub struct Beam {
+    oracle: Oracle,
+    threads: usize,
+    // spans traced so far, from row 0 down
+    spans: Mutex<Vec<Option<Span>>>,
+}
+
+impl Beam {
+    pub fn new(image: Vec<i64>) -> Beam {
+        Beam::shared(Arc::new(image))
+    }
+
+    pub fn shared(image: Arc<Vec<i64>>) -> Beam {
+        let threads = thread::available_parallelism().map(|n| n.get()).unwrap_or(1);
+        Beam { oracle: Oracle::shared(image).with_cache(), threads, spans: Mutex::new(Vec::new()) }
+    }
+
+    pub fn with_threads(mut self, threads: usize) -> Beam {
+        self.threads = threads.max(1);
+        self
+    }
+
+    pub fn probe(&self, (x, y): Pos) -> Result<bool, ProbeError> {
+        let pos = (x, y);
+        match self.oracle.call(&[x, y]) {
+            Ok(outputs) => match outputs[..] {
+                [0] => Ok(false),
+                [1] => Ok(true),
+                _ => Err(ProbeError::BadAnswer { pos, outputs }),
+            },
+            Err(err) => Err(ProbeError::Call { pos, err }),
+        }
+    }
+
+    // `probe` over all of `points` across the worker threads, answers in the same order
+    pub fn probe_many(&self, points: &[Pos]) -> Result<Vec<bool>, ProbeError> {
+        let next = AtomicUsize::new(0);
+        let answers = Mutex::new(Vec::with_capacity(points.len()));
+        thread::scope(|scope| {
+            for _ in 0..self.threads.min(points.len()) {
+                scope.spawn(|| loop {
+                    let index = next.fetch_add(1, Ordering::Relaxed);
+                    if index >= points.len() {
+                        break;
+                    }
+                    let answer = self.probe(points[index]);
+                    answers.lock().unwrap().push((index, answer));
+                });
+            }
+        });
+        let mut answers = answers.into_inner().unwrap();
+        answers.sort_by_key(|(index, _)| *index);
+        answers.into_iter().map(|(_, answer)| answer).collect()
+    }
+
+    // every cell of the `width` by `height` grid at the origin, as rows
+    pub fn scan(&self, width: i64, height: i64) -> Result<Vec<Vec<bool>>, ProbeError> {
+        let points: Vec<Pos> = (0..height).flat_map(|y| (0..width).map(move |x| (x, y))).collect();
+        let answers = self.probe_many(&points)?;
+        Ok(answers.chunks(width.max(1) as usize).map(<[bool]>::to_vec).collect())
+    }
+
+    // how many cells of the grid the beam pulls
+    pub fn count(&self, width: i64, height: i64) -> Result<usize, ProbeError> {
+        let rows = self.scan(width, height)?;
+        Ok(rows.iter().flatten().filter(|pulled| **pulled).count())
+    }
+
+    // points actually run so far, however many times they were asked about
+    pub fn probes(&self) -> usize {
+        self.oracle.cached()
+    }
+
+    // the beam's span on each of the first `rows` rows, `None` for rows it misses
+    pub fn trace(&self, rows: i64) -> Result<Vec<Option<Span>>, ProbeError> {
+        (0..rows).map(|y| self.span(y)).collect()
+    }
+
+    // The beam's span on row `y`, tracing down to it from the last row traced. The lock is
+    // only held to read and extend the spans, so threads tracing at once can both work out the
+    // same row; the probes are cached, and only the first to finish records it.
+    pub fn span(&self, y: i64) -> Result<Option<Span>, ProbeError> {
+        if y < 0 {
+            return Ok(None);
+        }
+        loop {
+            let (row, above) = {
+                let spans = self.spans.lock().unwrap();
+                if let Some(span) = spans.get(y as usize) {
+                    return Ok(*span);
+                }
+                (spans.len() as i64, spans.last().copied().flatten())
+            };
+            let span = self.next_span(row, above)?;
+            let mut spans = self.spans.lock().unwrap();
+            if spans.len() as i64 == row {
+                spans.push(span);
+            }
+        }
+    }
+
+    // Walks the start right from where it was on the row above, then the end right from
+    // wherever's further. Neither goes past ten cells per row down: under an empty row the
+    // start is looked for from the left edge out to there, and not finding it counts as a
+    // miss, while an end still going is `ProbeError::Unbounded`.
+    fn next_span(&self, y: i64, above: Option<Span>) -> Result<Option<Span>, ProbeError> {
+        let limit = 10 * (y + 1);
+        let mut start = above.map(|(start, _)| start).unwrap_or(0);
+        while !self.probe((start, y))? {
+            start += 1;
+            if start > limit {
+                return Ok(None);
+            }
+        }
+        let mut end = above.map(|(_, end)| end).unwrap_or(start).max(start);
+        if !self.probe((end, y))? {
+            end = start;
+        }
+        while self.probe((end + 1, y))? {
+            end += 1;
+            if end > limit {
+                return Err(ProbeError::Unbounded { y });
+            }
+        }
+        Ok(Some((start, end)))
+    }
+
+    // The top left corner of the first `size` by `size` square that fits inside the beam,
+    // looking no further down than `max_rows`. Squares are tried by their bottom row, so the
+    // one found is the nearest to the origin. There's no square smaller than 1 by 1.
+    pub fn fit_square(&self, size: i64, max_rows: i64) -> Result<Option<Pos>, ProbeError> {
+        if size < 1 {
+            return Ok(None);
+        }
+        for bottom in size - 1..max_rows {
+            let top = bottom - (size - 1);
+            let (x, _) = match self.span(bottom)? {
+                Some(span) => span,
+                None => continue,
+            };
+            match self.span(top)? {
+                Some((start, end)) if start <= x && end >= x + size - 1 => return Ok(Some((x, top))),
+                _ => continue,
+            }
+        }
+        Ok(None)
+    }
+}
@@ -0,0 +1,325 @@
+// Static control-flow graph over an image. Instructions are found by recursive descent from
+// the entry point: straight-line code falls through, and `jt`/`jf` branches are followed when
+// their target is an immediate operand. A branch whose target comes from memory can't be
+// resolved statically, so it's flagged as indirect and whatever it reaches shows up as
+// unreachable. A branch whose condition is immediate only gets the edge it can actually take.
+use fxhash::FxHashMap;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Write;
+use crate::disasm::{self, Line};
+use crate::instruction::{Op, ParamMode};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EdgeKind {
+    Fallthrough,
+    Taken,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Edge {
+    pub to: i64,
+    pub kind: EdgeKind,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Block {
+    pub start: i64,
+    // one past the last cell of the last instruction
+    pub end: i64,
+    pub lines: Vec<Line>,
+    pub successors: Vec<Edge>,
+    // the block ends in a jump whose target isn't known until runtime
+    pub indirect: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RegionKind {
+    // decodes cleanly as instructions but nothing reaches it statically
+    Unreachable,
+    Data,
+}
+
+// a run of cells not covered by any reachable instruction
+#[derive(Debug, Clone, PartialEq)]
+pub struct Region {
+    pub start: i64,
+    pub end: i64,
+    pub kind: RegionKind,
+}
+
+#[derive(Debug, Clone)]
+pub struct Cfg {
+    entries: Vec<i64>,
+    blocks: BTreeMap<i64, Block>,
+    regions: Vec<Region>,
+    // reachable addresses that don't hold a valid instruction
+    invalid: Vec<i64>,
+}
+
+// successors of a single instruction, plus whether it jumps somewhere unknown
+fn successors(line: &Line) -> (Vec<Edge>, bool) {
+    let instr = match line.instr() {
+        Some(instr) => instr,
+        None => return (Vec::new(), false),
+    };
+    let next = line.addr() + instr.size();
+    match instr.op {
+        Op::Halt => (Vec::new(), false),
+        Op::JumpIfTrue | Op::JumpIfFalse => {
+            let (cond, target) = (&instr.operands[0], &instr.operands[1]);
+            // with an immediate condition only one way is ever taken
+            let (may_take, may_fall) = match cond.mode {
+                ParamMode::Immediate => {
+                    let taken = (cond.val != 0) == (instr.op == Op::JumpIfTrue);
+                    (taken, !taken)
+                },
+                _ => (true, true),
+            };
+            let mut edges = Vec::new();
+            let mut indirect = false;
+            if may_take {
+                match target.mode {
+                    ParamMode::Immediate => edges.push(Edge { to: target.val, kind: EdgeKind::Taken }),
+                    _ => indirect = true,
+                }
+            }
+            if may_fall {
+                edges.push(Edge { to: next, kind: EdgeKind::Fallthrough });
+            }
+            (edges, indirect)
+        },
+        _ => (vec![Edge { to: next, kind: EdgeKind::Fallthrough }], false),
+    }
+}
+
+fn ends_block(line: &Line) -> bool {
+    match line.instr() {
+        Some(instr) => matches!(instr.op, Op::JumpIfTrue | Op::JumpIfFalse | Op::Halt),
+        None => true,
+    }
+}
+
+impl Cfg {
+    pub fn build(image: &[i64]) -> Cfg {
+        Cfg::build_from(image, &[0])
+    }
+
+    // extra entry points are how known targets of indirect jumps get pulled in
+    pub fn build_from(image: &[i64], entries: &[i64]) -> Cfg {
+        let mut instrs: BTreeMap<i64, Line> = BTreeMap::new();
+        let mut edges: FxHashMap<i64, (Vec<Edge>, bool)> = FxHashMap::default();
+        let mut leaders: BTreeSet<i64> = entries.iter().copied().collect();
+        let mut invalid = BTreeSet::new();
+        let mut work: Vec<i64> = entries.to_vec();
+        while let Some(addr) = work.pop() {
+            if instrs.contains_key(&addr) || invalid.contains(&addr) {
+                continue;
+            }
+            let line = match disasm::decode(image, addr).filter(|_| addr >= 0) {
+                Some(line) => line,
+                None => {
+                    invalid.insert(addr);
+                    continue;
+                },
+            };
+            let (succs, indirect) = successors(&line);
+            for edge in succs.iter() {
+                if ends_block(&line) {
+                    leaders.insert(edge.to);
+                }
+                work.push(edge.to);
+            }
+            edges.insert(addr, (succs, indirect));
+            instrs.insert(addr, line);
+        }
+
+        let mut blocks = BTreeMap::new();
+        for start in leaders.iter().filter(|addr| instrs.contains_key(addr)) {
+            let mut addr = *start;
+            let mut lines = Vec::new();
+            loop {
+                let line = instrs[&addr].clone();
+                let next = addr + line.size();
+                let last = ends_block(&line) || leaders.contains(&next) || !instrs.contains_key(&next);
+                lines.push(line);
+                if last {
+                    break;
+                }
+                addr = next;
+            }
+            let (successors, indirect) = edges[&addr].clone();
+            let end = addr + lines.last().map(Line::size).unwrap_or(0);
+            blocks.insert(*start, Block { start: *start, end, lines, successors, indirect });
+        }
+
+        let mut covered = vec![false; image.len()];
+        for line in instrs.values() {
+            for addr in line.addr()..line.addr() + line.size() {
+                covered[addr as usize] = true;
+            }
+        }
+        let mut regions = Vec::new();
+        let mut addr = 0;
+        while addr < image.len() {
+            if covered[addr] {
+                addr += 1;
+                continue;
+            }
+            let start = addr;
+            while addr < image.len() && !covered[addr] {
+                addr += 1;
+            }
+            // the gap is unreachable code if a linear sweep of it is nothing but instructions
+            let gap = &image[..addr];
+            let mut at = start as i64;
+            while let Some(line) = disasm::decode(gap, at) {
+                at += line.size();
+            }
+            let kind = match at as usize == addr {
+                true => RegionKind::Unreachable,
+                false => RegionKind::Data,
+            };
+            regions.push(Region { start: start as i64, end: addr as i64, kind });
+        }
+
+        Cfg { entries: entries.to_vec(), blocks, regions, invalid: invalid.into_iter().collect() }
+    }
+
+    pub fn blocks(&self) -> impl Iterator<Item = &Block> {
+        self.blocks.values()
+    }
+
+    pub fn block(&self, start: i64) -> Option<&Block> {
+        self.blocks.get(&start)
+    }
+
+    pub fn regions(&self) -> &[Region] {
+        &self.regions
+    }
+
+    pub fn invalid(&self) -> &[i64] {
+        &self.invalid
+    }
+
+    // addresses of the indirect jumps that end blocks
+    pub fn indirect(&self) -> Vec<i64> {
+        self.blocks.values()
+            .filter(|block| block.indirect)
+            .filter_map(|block| block.lines.last().map(Line::addr))
+            .collect()
+    }
+
+    // start of every statically reachable instruction, in ascending order
+    pub fn instructions(&self) -> Vec<i64> {
+        self.blocks.values()
+            .flat_map(|block| block.lines.iter().map(Line::addr))
+            .collect()
+    }
+
+    pub fn is_code(&self, addr: i64) -> bool {
+        match self.blocks.range(..=addr).next_back() {
+            Some((_, block)) => addr < block.end,
+            None => false,
+        }
+    }
+
+    // Disassembly that decodes from every reachable instruction and only sweeps linearly
+    // through unreachable regions. Data islands come out as data.
+    pub fn lines(&self, image: &[i64]) -> Vec<Line> {
+        let mut lines = Vec::new();
+        for line in disasm::disassemble_from(image, &self.instructions()) {
+            let island = self.regions.iter()
+                .find(|region| region.start <= line.addr() && line.addr() < region.end)
+                .filter(|region| region.kind == RegionKind::Data);
+            match (island, &line) {
+                (Some(_), Line::Instr { addr, words, .. }) => {
+                    for (i, val) in words.iter().enumerate() {
+                        lines.push(Line::Data { addr: addr + i as i64, val: *val });
+                    }
+                },
+                _ => lines.push(line),
+            }
+        }
+        lines
+    }
+
+    pub fn dot(&self) -> String {
+        let mut dot = String::new();
+        writeln!(dot, "digraph cfg {{").unwrap();
+        writeln!(dot, "    node [shape=box, fontname=\"monospace\"];").unwrap();
+        for block in self.blocks.values() {
+            let mut label = String::new();
+            for line in block.lines.iter() {
+                write!(label, "{}\\l", escape(&line.to_string())).unwrap();
+            }
+            let style = match (block.indirect, self.entries.contains(&block.start)) {
+                (true, _) => ", color=red",
+                (false, true) => ", style=bold",
+                _ => "",
+            };
+            writeln!(dot, "    b{} [label=\"{}\"{}];", block.start, label, style).unwrap();
+            for edge in block.successors.iter() {
+                let to = match self.blocks.contains_key(&edge.to) {
+                    true => format!("b{}", edge.to),
+                    false => format!("invalid{}", edge.to),
+                };
+                let style = match edge.kind {
+                    EdgeKind::Taken => " [label=\"taken\"]",
+                    EdgeKind::Fallthrough => "",
+                };
+                writeln!(dot, "    b{} -> {}{};", block.start, to, style).unwrap();
+            }
+        }
+        for addr in self.invalid.iter() {
+            writeln!(dot, "    invalid{} [label=\"{}: invalid\", shape=octagon];", addr, addr).unwrap();
+        }
+        writeln!(dot, "}}").unwrap();
+        dot
+    }
+
+    pub fn json(&self) -> String {
+        let blocks: Vec<String> = self.blocks.values().map(|block| {
+            let lines: Vec<String> = block.lines.iter()
+                .map(|line| format!("{{\"addr\":{},\"text\":\"{}\"}}", line.addr(), escape(&text(line))))
+                .collect();
+            let successors: Vec<String> = block.successors.iter()
+                .map(|edge| {
+                    let kind = match edge.kind {
+                        EdgeKind::Taken => "taken",
+                        EdgeKind::Fallthrough => "fallthrough",
+                    };
+                    format!("{{\"to\":{},\"kind\":\"{}\"}}", edge.to, kind)
+                })
+                .collect();
+            format!(
+                "{{\"start\":{},\"end\":{},\"indirect\":{},\"lines\":[{}],\"successors\":[{}]}}",
+                block.start, block.end, block.indirect, lines.join(","), successors.join(","),
+            )
+        }).collect();
+        let regions: Vec<String> = self.regions.iter().map(|region| {
+            let kind = match region.kind {
+                RegionKind::Unreachable => "unreachable",
+                RegionKind::Data => "data",
+            };
+            format!("{{\"start\":{},\"end\":{},\"kind\":\"{}\"}}", region.start, region.end, kind)
+        }).collect();
+        let entries: Vec<String> = self.entries.iter().map(i64::to_string).collect();
+        let invalid: Vec<String> = self.invalid.iter().map(i64::to_string).collect();
+        format!(
+            "{{\"entries\":[{}],\"blocks\":[{}],\"regions\":[{}],\"invalid\":[{}]}}",
+            entries.join(","), blocks.join(","), regions.join(","), invalid.join(","),
+        )
+    }
+}
+
+fn text(line: &Line) -> String {
+    match line {
+        Line::Instr { text, .. } => text.clone(),
+        Line::Data { val, .. } => format!(".data {}", val),
+    }
+}
+
+// good enough for both DOT and JSON string literals; instruction text is plain ASCII
+fn escape(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('"', "\\\"")
+}
@@ -0,0 +1,154 @@
+use fxhash::FxHashMap;
+use std::fmt::Write;
+use crate::cfg::Cfg;
+use crate::disasm::{self, Line};
+use crate::instruction::Op;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Branch {
+    pub taken: u64,
+    pub not_taken: u64,
+}
+
+// Execution counts keyed by instruction address. Coverage from separate runs of the same
+// image can be merged, so a sweep over many inputs reports one combined picture.
+#[derive(Debug, Clone, Default)]
+pub struct Coverage {
+    hits: FxHashMap<i64, u64>,
+    branches: FxHashMap<i64, Branch>,
+}
+
+impl Coverage {
+    pub fn new() -> Coverage {
+        Coverage::default()
+    }
+
+    pub(crate) fn hit(&mut self, addr: i64) {
+        *self.hits.entry(addr).or_insert(0) += 1;
+    }
+
+    pub(crate) fn branch(&mut self, addr: i64, taken: bool) {
+        let branch = self.branches.entry(addr).or_default();
+        match taken {
+            true => branch.taken += 1,
+            false => branch.not_taken += 1,
+        }
+    }
+
+    pub fn merge(&mut self, other: &Coverage) {
+        for (addr, count) in other.hits.iter() {
+            *self.hits.entry(*addr).or_insert(0) += count;
+        }
+        for (addr, branch) in other.branches.iter() {
+            let ours = self.branches.entry(*addr).or_default();
+            ours.taken += branch.taken;
+            ours.not_taken += branch.not_taken;
+        }
+    }
+
+    pub fn hits(&self, addr: i64) -> u64 {
+        *self.hits.get(&addr).unwrap_or(&0)
+    }
+
+    pub fn branch_at(&self, addr: i64) -> Option<Branch> {
+        self.branches.get(&addr).copied()
+    }
+
+    // addresses of every instruction that ran at least once, in ascending order
+    pub fn executed(&self) -> Vec<i64> {
+        let mut addrs: Vec<i64> = self.hits.keys().copied().collect();
+        addrs.sort_unstable();
+        addrs
+    }
+
+    // Decodes from every address known to have executed, plus anything `hints` says is code.
+    fn lines(&self, image: &[i64], hints: &[i64]) -> Vec<Line> {
+        let mut starts = self.executed();
+        starts.extend_from_slice(hints);
+        starts.sort_unstable();
+        starts.dedup();
+        disasm::disassemble_from(image, &starts)
+    }
+
+    pub fn annotate(&self, image: &[i64]) -> String {
+        self.annotate_with(image, &[])
+    }
+
+    // `annotate`, aligned on the reachable instructions of a control-flow graph as well, so
+    // code that never ran still lines up
+    pub fn annotate_cfg(&self, image: &[i64], cfg: &Cfg) -> String {
+        self.annotate_with(image, &cfg.instructions())
+    }
+
+    fn annotate_with(&self, image: &[i64], hints: &[i64]) -> String {
+        let mut report = String::new();
+        for line in self.lines(image, hints) {
+            // a data line with a hit count is code that was rewritten before it ran
+            let count = match (&line, self.hits.get(&line.addr())) {
+                (_, Some(count)) => format!("{:>8}", count),
+                (Line::Instr { .. }, None) => format!("{:>8}", "#####"),
+                (Line::Data { .. }, None) => format!("{:>8}", "-"),
+            };
+            write!(report, "{} | {}", count, line).unwrap();
+            if let Some(branch) = self.branches.get(&line.addr()) {
+                write!(report, "    ; taken {} / not taken {}", branch.taken, branch.not_taken).unwrap();
+            }
+            report.push('\n');
+        }
+        report
+    }
+
+    // lcov tracefile where "line" N is memory address N - 1, so every cell an instruction
+    // occupies is reported with that instruction's hit count
+    pub fn lcov(&self, image: &[i64], source: &str) -> String {
+        self.lcov_with(image, &[], source)
+    }
+
+    pub fn lcov_cfg(&self, image: &[i64], cfg: &Cfg, source: &str) -> String {
+        self.lcov_with(image, &cfg.instructions(), source)
+    }
+
+    fn lcov_with(&self, image: &[i64], hints: &[i64], source: &str) -> String {
+        let mut report = String::new();
+        writeln!(report, "TN:").unwrap();
+        writeln!(report, "SF:{}", source).unwrap();
+        let (mut found, mut hit) = (0, 0);
+        let (mut branches_found, mut branches_hit) = (0, 0);
+        for line in self.lines(image, hints) {
+            if let Line::Data { .. } = line {
+                continue;
+            }
+            let count = self.hits(line.addr());
+            for addr in line.addr()..line.addr() + line.size() {
+                writeln!(report, "DA:{},{}", addr + 1, count).unwrap();
+                found += 1;
+                if count > 0 {
+                    hit += 1;
+                }
+            }
+            if let Some(instr) = line.instr() {
+                if !matches!(instr.op, Op::JumpIfTrue | Op::JumpIfFalse) {
+                    continue;
+                }
+            }
+            let branch = self.branches.get(&line.addr()).copied().unwrap_or_default();
+            for (i, n) in [branch.taken, branch.not_taken].iter().enumerate() {
+                let taken = match count {
+                    0 => "-".to_string(),
+                    _ => n.to_string(),
+                };
+                writeln!(report, "BRDA:{},0,{},{}", line.addr() + 1, i, taken).unwrap();
+                branches_found += 1;
+                if *n > 0 {
+                    branches_hit += 1;
+                }
+            }
+        }
+        writeln!(report, "BRF:{}", branches_found).unwrap();
+        writeln!(report, "BRH:{}", branches_hit).unwrap();
+        writeln!(report, "LF:{}", found).unwrap();
+        writeln!(report, "LH:{}", hit).unwrap();
+        writeln!(report, "end_of_record").unwrap();
+        report
+    }
+}
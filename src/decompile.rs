@@ -0,0 +1,669 @@
+// Decompiler to C-like pseudocode. Decoded instructions are lifted into a small IR, split
+// into functions, structured into if/else and loops, and printed.
+//
+// Functions are found from the calling convention the Advent of Code programs use: the caller
+// stores the return address (the cell right after the jump) into a relative slot [rb+k],
+// arguments into [rb+k+1], [rb+k+2], ..., and jumps unconditionally to the callee. The callee
+// opens with `arb N`, so its arguments sit just below the new relative base, and returns with
+// `arb -N` and a jump through [rb+0] (well, [rb-N] before the epilogue). Results come back in
+// the argument slots. Inside a function, [rb-N] is the return address, the cells above it are
+// named `a1`, `a2`, ... for arguments and `l1`, `l2`, ... for the locals past them, and
+// the outgoing slots at [rb+j] are `r{j}`.
+//
+// Structuring assumes each function's code is laid out in address order, which compiled
+// Intcode is: a backward jump makes a loop, a forward conditional jump an `if`, and a forward
+// jump right before the `if`'s target an `else`. Anything else comes out as a `goto`.
+//
+// Memory cells accessed in position mode get names from how they're used: `in{addr}` for
+// cells written by input instructions, `flag{addr}` for cells only ever set by comparisons
+// (tests of those are folded into the condition), `g{addr}` for other cells used at least
+// three times, and `mem[addr]` for the rest and for anything inside the code.
+use fxhash::{FxHashMap, FxHashSet};
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use crate::instruction::{self, Instruction, Op, ParamMode};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum BinOp {
+    Add,
+    Mul,
+    Lt,
+    Eq,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Expr {
+    Const(i64),
+    Cell(i64),
+    // [rb+offset]
+    Rel(i64),
+    Bin(BinOp, Box<Expr>, Box<Expr>),
+}
+
+// `expr != 0`, or `expr == 0` when negated
+#[derive(Debug, Clone, PartialEq)]
+struct Cond {
+    expr: Expr,
+    negated: bool,
+}
+
+impl Cond {
+    fn not(self) -> Cond {
+        Cond { expr: self.expr, negated: !self.negated }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Stmt {
+    Assign { addr: i64, dst: Expr, val: Expr },
+    Input { addr: i64, dst: Expr },
+    Output { addr: i64, val: Expr },
+    Rb { addr: i64, delta: Expr },
+    Call { addr: i64, func: i64, args: Vec<Expr> },
+    Sys { addr: i64 },
+    Return { addr: i64 },
+    Halt { addr: i64 },
+    If { addr: i64, cond: Cond, then: Vec<Stmt>, els: Vec<Stmt> },
+    While { addr: i64, cond: Option<Cond>, body: Vec<Stmt> },
+    DoWhile { addr: i64, body: Vec<Stmt>, cond: Cond },
+    Break,
+    Continue,
+    Goto { addr: i64, target: Expr },
+    // only exist between lifting and structuring
+    Jump { addr: i64, target: i64 },
+    Branch { addr: i64, cond: Cond, target: i64 },
+}
+
+impl Stmt {
+    fn addr(&self) -> Option<i64> {
+        match self {
+            Stmt::Assign { addr, .. } | Stmt::Input { addr, .. } | Stmt::Output { addr, .. }
+            | Stmt::Rb { addr, .. } | Stmt::Call { addr, .. } | Stmt::Sys { addr }
+            | Stmt::Return { addr } | Stmt::Halt { addr } | Stmt::If { addr, .. }
+            | Stmt::While { addr, .. } | Stmt::DoWhile { addr, .. } | Stmt::Goto { addr, .. }
+            | Stmt::Jump { addr, .. } | Stmt::Branch { addr, .. } => Some(*addr),
+            Stmt::Break | Stmt::Continue => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Instr {
+    addr: i64,
+    decoded: Instruction,
+}
+
+impl Instr {
+    fn op(&self) -> Op {
+        self.decoded.op
+    }
+
+    fn next(&self) -> i64 {
+        self.addr + self.decoded.size()
+    }
+
+    fn operand(&self, i: usize) -> Expr {
+        let operand = &self.decoded.operands[i];
+        match operand.mode {
+            ParamMode::Immediate => Expr::Const(operand.val),
+            ParamMode::Relative => Expr::Rel(operand.val),
+            ParamMode::Position => Expr::Cell(operand.val),
+        }
+    }
+
+    // `Some(true)` for a jump that's always taken, `Some(false)` for one that never is
+    fn constant_jump(&self) -> Option<bool> {
+        match (self.op(), self.operand(0)) {
+            (Op::JumpIfTrue, Expr::Const(c)) => Some(c != 0),
+            (Op::JumpIfFalse, Expr::Const(c)) => Some(c == 0),
+            _ => None,
+        }
+    }
+
+    // the return address this instruction stores into a relative slot, if it's that kind
+    // of instruction: add 0, K or mul 1, K in either order
+    fn stored_constant(&self) -> Option<(i64, i64)> {
+        if self.op() != Op::Add && self.op() != Op::Mul {
+            return None;
+        }
+        let slot = match self.operand(2) {
+            Expr::Rel(slot) => slot,
+            _ => return None,
+        };
+        match (self.op(), self.operand(0), self.operand(1)) {
+            (Op::Add, Expr::Const(0), Expr::Const(k)) | (Op::Add, Expr::Const(k), Expr::Const(0)) => Some((slot, k)),
+            (Op::Mul, Expr::Const(1), Expr::Const(k)) | (Op::Mul, Expr::Const(k), Expr::Const(1)) => Some((slot, k)),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Role {
+    Input,
+    Flag,
+    Var,
+    Raw,
+}
+
+struct Function {
+    entry: i64,
+    // size of the frame opened by the prologue, if there is one
+    frame: Option<i64>,
+    instrs: BTreeMap<i64, Instr>,
+}
+
+struct Decompiler<'a> {
+    image: &'a [i64],
+    // call instruction address -> (callee, return slot)
+    calls: FxHashMap<i64, (i64, i64)>,
+    // callee -> most arguments any caller passes
+    arity: FxHashMap<i64, usize>,
+    roles: FxHashMap<i64, Role>,
+    code: FxHashSet<i64>,
+}
+
+impl<'a> Decompiler<'a> {
+    fn decode(&self, addr: i64) -> Option<Instr> {
+        let decoded = instruction::decode(self.image, addr).ok()?;
+        Some(Instr { addr, decoded })
+    }
+
+    // Everything reachable from `entry`, with calls stepping over the callee. Returns the
+    // instructions and any callees found.
+    fn reach(&mut self, entry: i64) -> (BTreeMap<i64, Instr>, Vec<i64>) {
+        let mut instrs: BTreeMap<i64, Instr> = BTreeMap::new();
+        let mut callees = Vec::new();
+        let mut work = vec![entry];
+        while let Some(addr) = work.pop() {
+            if instrs.contains_key(&addr) {
+                continue;
+            }
+            let instr = match self.decode(addr) {
+                Some(instr) => instr,
+                None => continue,
+            };
+            let next = instr.next();
+            match instr.op() {
+                Op::Halt => (),
+                Op::JumpIfTrue | Op::JumpIfFalse => self.follow(&instr, &mut work, &mut callees),
+                _ => work.push(next),
+            }
+            instrs.insert(addr, instr);
+        }
+        (instrs, callees)
+    }
+
+    // queues where a jump goes next, recording it if it's a call
+    fn follow(&mut self, instr: &Instr, work: &mut Vec<i64>, callees: &mut Vec<i64>) {
+        let (addr, next) = (instr.addr, instr.next());
+        match (instr.constant_jump(), instr.operand(1)) {
+            (Some(true), Expr::Const(target)) => {
+                // a call is an unconditional jump right after storing its own return address
+                let ret = self.decode_before(addr)
+                    .and_then(|prev| prev.stored_constant())
+                    .filter(|(_, k)| *k == next);
+                match ret {
+                    Some((slot, _)) => {
+                        self.calls.insert(addr, (target, slot));
+                        callees.push(target);
+                        work.push(next);
+                    },
+                    None => work.push(target),
+                }
+            },
+            (Some(true), _) => (),
+            (Some(false), _) => work.push(next),
+            (None, target) => {
+                if let Expr::Const(target) = target {
+                    work.push(target);
+                }
+                work.push(next);
+            },
+        }
+    }
+
+    // the four-cell instruction ending right at `addr`, which is what stores the return address
+    fn decode_before(&self, addr: i64) -> Option<Instr> {
+        self.decode(addr - 4).filter(|instr| instr.next() == addr)
+    }
+
+    fn functions(&mut self) -> Vec<Function> {
+        let mut functions = Vec::new();
+        let mut seen = FxHashSet::default();
+        let mut work = vec![0];
+        while let Some(entry) = work.pop() {
+            if !seen.insert(entry) {
+                continue;
+            }
+            let (instrs, callees) = self.reach(entry);
+            work.extend(callees);
+            let frame = match instrs.get(&entry) {
+                Some(first) if entry != 0 && first.op() == Op::UpdateRb => match first.operand(0) {
+                    Expr::Const(n) if n > 0 => Some(n),
+                    _ => None,
+                },
+                _ => None,
+            };
+            functions.push(Function { entry, frame, instrs });
+        }
+        functions.sort_by_key(|function| function.entry);
+        functions
+    }
+
+    fn classify(&mut self, functions: &[Function]) {
+        let mut uses: FxHashMap<i64, usize> = FxHashMap::default();
+        let mut writes: FxHashMap<i64, FxHashSet<Op>> = FxHashMap::default();
+        for instr in functions.iter().flat_map(|function| function.instrs.values()) {
+            for addr in instr.addr..instr.next() {
+                self.code.insert(addr);
+            }
+            for i in 0..instr.op().params() {
+                if let Expr::Cell(cell) = instr.operand(i) {
+                    *uses.entry(cell).or_insert(0) += 1;
+                    let writes_here = matches!(
+                        (instr.op(), i),
+                        (Op::Add | Op::Mul | Op::LessThan | Op::Equals, 2) | (Op::Read, 0)
+                    );
+                    if writes_here {
+                        writes.entry(cell).or_default().insert(instr.op());
+                    }
+                }
+            }
+        }
+        for (cell, count) in uses {
+            let written = writes.get(&cell);
+            let role = match written {
+                _ if self.code.contains(&cell) => Role::Raw,
+                Some(ops) if ops.contains(&Op::Read) => Role::Input,
+                Some(ops) if ops.iter().all(|op| matches!(op, Op::LessThan | Op::Equals)) => Role::Flag,
+                _ if count >= 3 => Role::Var,
+                _ => Role::Raw,
+            };
+            self.roles.insert(cell, role);
+        }
+    }
+
+    fn value(&self, instr: &Instr) -> Expr {
+        let (a, b) = (instr.operand(0), instr.operand(1));
+        match (instr.op(), a, b) {
+            (Op::Add, Expr::Const(0), x) | (Op::Add, x, Expr::Const(0)) => x,
+            (Op::Mul, Expr::Const(1), x) | (Op::Mul, x, Expr::Const(1)) => x,
+            (Op::Add, Expr::Const(x), Expr::Const(y)) => Expr::Const(x.wrapping_add(y)),
+            (Op::Mul, Expr::Const(x), Expr::Const(y)) => Expr::Const(x.wrapping_mul(y)),
+            (op, a, b) => {
+                let op = match op {
+                    Op::Add => BinOp::Add,
+                    Op::Mul => BinOp::Mul,
+                    Op::LessThan => BinOp::Lt,
+                    _ => BinOp::Eq,
+                };
+                Expr::Bin(op, Box::new(a), Box::new(b))
+            },
+        }
+    }
+
+    fn lift(&self, function: &Function) -> Vec<Stmt> {
+        let mut stmts: Vec<Stmt> = Vec::new();
+        for instr in function.instrs.values() {
+            let addr = instr.addr;
+            // statements folded into this one give it their address
+            let mut at = addr;
+            let stmt = match instr.op() {
+                Op::Add | Op::Mul | Op::LessThan | Op::Equals => Stmt::Assign { addr, dst: instr.operand(2), val: self.value(instr) },
+                Op::Read => Stmt::Input { addr, dst: instr.operand(0) },
+                Op::Write => Stmt::Output { addr, val: instr.operand(0) },
+                Op::UpdateRb if addr == function.entry && function.frame.is_some() => continue,
+                Op::UpdateRb => Stmt::Rb { addr, delta: instr.operand(0) },
+                Op::JumpIfTrue | Op::JumpIfFalse => {
+                    let cond = Cond { expr: instr.operand(0), negated: instr.op() == Op::JumpIfFalse };
+                    match (instr.constant_jump(), instr.operand(1)) {
+                        (Some(false), _) => continue,
+                        (Some(true), _) if self.calls.contains_key(&addr) => {
+                            let (func, slot) = self.calls[&addr];
+                            self.call(&mut stmts, &mut at, func, slot)
+                        },
+                        (Some(true), Expr::Rel(_)) => {
+                            // drop the epilogue
+                            if let Some(Stmt::Rb { addr, delta: Expr::Const(n) }) = stmts.last() {
+                                if Some(-*n) == function.frame {
+                                    at = *addr;
+                                    stmts.pop();
+                                }
+                            }
+                            Stmt::Return { addr: at }
+                        },
+                        (Some(true), Expr::Const(target)) => Stmt::Jump { addr, target },
+                        (Some(true), target) => Stmt::Goto { addr, target },
+                        (None, Expr::Const(target)) => {
+                            let cond = self.fold(&mut stmts, &mut at, cond);
+                            Stmt::Branch { addr: at, cond, target }
+                        },
+                        (None, target) => {
+                            let cond = self.fold(&mut stmts, &mut at, cond);
+                            Stmt::If { addr: at, cond, then: vec![Stmt::Goto { addr, target }], els: Vec::new() }
+                        },
+                    }
+                },
+                Op::Halt => Stmt::Halt { addr },
+                Op::Syscall => Stmt::Sys { addr },
+            };
+            stmts.push(stmt);
+        }
+        stmts
+    }
+
+    // a test of a flag set by the statement just before folds the comparison into the test
+    fn fold(&self, stmts: &mut Vec<Stmt>, at: &mut i64, cond: Cond) -> Cond {
+        if let (Expr::Cell(cell), Some(Stmt::Assign { addr, dst: Expr::Cell(dst), val })) = (&cond.expr, stmts.last()) {
+            if cell == dst && self.roles.get(cell) == Some(&Role::Flag) {
+                let expr = val.clone();
+                *at = *addr;
+                stmts.pop();
+                return Cond { expr, negated: cond.negated };
+            }
+        }
+        cond
+    }
+
+    // pulls the stores of the return address and arguments off the end of `stmts`
+    fn call(&self, stmts: &mut Vec<Stmt>, at: &mut i64, func: i64, slot: i64) -> Stmt {
+        let mut args: BTreeMap<i64, Expr> = BTreeMap::new();
+        while let Some(Stmt::Assign { addr, dst: Expr::Rel(offset), val }) = stmts.last() {
+            if *offset < slot || args.contains_key(offset) {
+                break;
+            }
+            args.insert(*offset, val.clone());
+            *at = *addr;
+            stmts.pop();
+        }
+        args.remove(&slot);
+        let count = args.keys().next_back().map(|last| last - slot).unwrap_or(0);
+        let args = (1..=count).map(|j| args.remove(&(slot + j)).unwrap_or(Expr::Rel(slot + j))).collect();
+        Stmt::Call { addr: *at, func, args }
+    }
+
+    // `lp` is the innermost loop around `items`: its header, where `continue` goes (the header,
+    // or the condition of a do-while), and its exit
+    fn structure(&self, items: &[Stmt], end: i64, lp: Option<(i64, i64, i64)>) -> Vec<Stmt> {
+        let index = |target: i64| -> Option<usize> {
+            match items.iter().position(|item| item.addr() == Some(target)) {
+                Some(i) => Some(i),
+                None if target == end => Some(items.len()),
+                None => None,
+            }
+        };
+        let mut out = Vec::new();
+        let mut i = 0;
+        while i < items.len() {
+            let addr = items[i].addr().unwrap_or(end);
+            // a later jump back here makes this a loop header
+            let back = items.iter().rposition(|item| match item {
+                Stmt::Jump { addr: at, target } | Stmt::Branch { addr: at, target, .. } => *target == addr && *at >= addr,
+                _ => false,
+            });
+            let header = lp.map(|(header, _, _)| header) != Some(addr);
+            if let (Some(j), true) = (back, header) {
+                let exit = items.get(j + 1).and_then(Stmt::addr).unwrap_or(end);
+                let cont = items[j].addr().unwrap_or(end);
+                let stmt = match &items[j] {
+                    Stmt::Branch { cond, .. } => Stmt::DoWhile {
+                        addr,
+                        body: self.structure(&items[i..j], cont, Some((addr, cont, exit))),
+                        cond: cond.clone(),
+                    },
+                    _ => match &items[i] {
+                        Stmt::Branch { cond, target, .. } if *target == exit => Stmt::While {
+                            addr,
+                            cond: Some(cond.clone().not()),
+                            body: self.structure(&items[i + 1..j], cont, Some((addr, addr, exit))),
+                        },
+                        _ => Stmt::While {
+                            addr,
+                            cond: None,
+                            body: self.structure(&items[i..j], cont, Some((addr, addr, exit))),
+                        },
+                    },
+                };
+                out.push(stmt);
+                i = j + 1;
+                continue;
+            }
+            if let (Stmt::Jump { target, .. } | Stmt::Branch { target, .. }, Some((_, cont, exit))) = (&items[i], lp) {
+                let exit_or_continue = match *target {
+                    t if t == exit => Some(Stmt::Break),
+                    t if t == cont => Some(Stmt::Continue),
+                    _ => None,
+                };
+                if let Some(stmt) = exit_or_continue {
+                    out.push(match &items[i] {
+                        Stmt::Branch { cond, .. } => Stmt::If { addr, cond: cond.clone(), then: vec![stmt], els: Vec::new() },
+                        _ => stmt,
+                    });
+                    i += 1;
+                    continue;
+                }
+            }
+            // a branch forward to a statement in this block
+            let forward = match &items[i] {
+                Stmt::Branch { target, .. } if *target > addr => index(*target),
+                _ => None,
+            };
+            if let (Stmt::Branch { cond, target, .. }, Some(t)) = (&items[i], forward) {
+                let els = match items.get(t.wrapping_sub(1)) {
+                    Some(Stmt::Jump { target: e, .. }) if t - 1 > i && *e > *target => index(*e).map(|e| (t - 1, e)),
+                    _ => None,
+                };
+                match els {
+                    Some((jump, e)) => {
+                        out.push(Stmt::If {
+                            addr,
+                            cond: cond.clone().not(),
+                            then: self.structure(&items[i + 1..jump], items[jump].addr().unwrap_or(end), lp),
+                            els: self.structure(&items[t..e], items.get(e).and_then(Stmt::addr).unwrap_or(end), lp),
+                        });
+                        i = e;
+                    },
+                    None => {
+                        out.push(Stmt::If {
+                            addr,
+                            cond: cond.clone().not(),
+                            then: self.structure(&items[i + 1..t], *target, lp),
+                            els: Vec::new(),
+                        });
+                        i = t;
+                    },
+                }
+                continue;
+            }
+            match &items[i] {
+                Stmt::Branch { cond, target, .. } => out.push(Stmt::If {
+                    addr,
+                    cond: cond.clone(),
+                    then: vec![Stmt::Goto { addr, target: Expr::Const(*target) }],
+                    els: Vec::new(),
+                }),
+                // a jump to the next statement does nothing
+                Stmt::Jump { target, .. } if items.get(i + 1).and_then(Stmt::addr).unwrap_or(end) == *target => (),
+                Stmt::Jump { target, .. } => out.push(Stmt::Goto { addr, target: Expr::Const(*target) }),
+                stmt => out.push(stmt.clone()),
+            }
+            i += 1;
+        }
+        out
+    }
+}
+
+struct Printer<'a> {
+    roles: &'a FxHashMap<i64, Role>,
+    frame: Option<i64>,
+    arity: usize,
+    labels: FxHashSet<i64>,
+    out: String,
+}
+
+impl<'a> Printer<'a> {
+    fn name(&self, expr: &Expr) -> String {
+        match expr {
+            Expr::Const(val) => val.to_string(),
+            Expr::Cell(cell) => match self.roles.get(cell) {
+                Some(Role::Input) => format!("in{}", cell),
+                Some(Role::Flag) => format!("flag{}", cell),
+                Some(Role::Var) => format!("g{}", cell),
+                _ => format!("mem[{}]", cell),
+            },
+            Expr::Rel(offset) => match self.frame {
+                Some(n) if *offset == -n => "ret".to_string(),
+                Some(n) if *offset < 0 && *offset > -n => {
+                    let slot = (offset + n) as usize;
+                    match slot <= self.arity {
+                        true => format!("a{}", slot),
+                        false => format!("l{}", slot - self.arity),
+                    }
+                },
+                _ if *offset < 0 => format!("rb[{}]", offset),
+                _ => format!("r{}", offset),
+            },
+            Expr::Bin(op, a, b) => {
+                let op = match op {
+                    BinOp::Add => "+",
+                    BinOp::Mul => "*",
+                    BinOp::Lt => "<",
+                    BinOp::Eq => "==",
+                };
+                format!("{} {} {}", self.operand(a), op, self.operand(b))
+            },
+        }
+    }
+
+    fn operand(&self, expr: &Expr) -> String {
+        match expr {
+            Expr::Bin(..) => format!("({})", self.name(expr)),
+            _ => self.name(expr),
+        }
+    }
+
+    fn cond(&self, cond: &Cond) -> String {
+        match (&cond.expr, cond.negated) {
+            (Expr::Bin(BinOp::Lt, a, b), false) => format!("{} < {}", self.operand(a), self.operand(b)),
+            (Expr::Bin(BinOp::Lt, a, b), true) => format!("{} >= {}", self.operand(a), self.operand(b)),
+            (Expr::Bin(BinOp::Eq, a, b), false) => format!("{} == {}", self.operand(a), self.operand(b)),
+            (Expr::Bin(BinOp::Eq, a, b), true) => format!("{} != {}", self.operand(a), self.operand(b)),
+            (expr, false) => format!("{} != 0", self.operand(expr)),
+            (expr, true) => format!("{} == 0", self.operand(expr)),
+        }
+    }
+
+    fn line(&mut self, depth: usize, text: &str) {
+        writeln!(self.out, "{}{}", "    ".repeat(depth), text).unwrap();
+    }
+
+    fn block(&mut self, depth: usize, stmts: &[Stmt]) {
+        for stmt in stmts {
+            if let Some(addr) = stmt.addr().filter(|addr| self.labels.remove(addr)) {
+                self.line(depth.saturating_sub(1), &format!("L{}:", addr));
+            }
+            self.stmt(depth, stmt);
+        }
+    }
+
+    fn stmt(&mut self, depth: usize, stmt: &Stmt) {
+        let text = match stmt {
+            Stmt::Assign { dst, val, .. } => format!("{} = {};", self.name(dst), self.name(val)),
+            Stmt::Input { dst, .. } => format!("{} = input();", self.name(dst)),
+            Stmt::Output { val, .. } => format!("output({});", self.name(val)),
+            Stmt::Rb { delta, .. } => format!("rb += {};", self.name(delta)),
+            Stmt::Call { func, args, .. } => {
+                let args: Vec<String> = args.iter().map(|arg| self.name(arg)).collect();
+                format!("f{}({});", func, args.join(", "))
+            },
+            Stmt::Sys { .. } => "syscall();".to_string(),
+            Stmt::Return { .. } => "return;".to_string(),
+            Stmt::Halt { .. } => "halt();".to_string(),
+            Stmt::Break => "break;".to_string(),
+            Stmt::Continue => "continue;".to_string(),
+            Stmt::Goto { target: Expr::Const(target), .. } => format!("goto L{};", target),
+            Stmt::Goto { target, .. } => format!("goto *{};", self.name(target)),
+            Stmt::If { cond, then, els, .. } => {
+                self.line(depth, &format!("if ({}) {{", self.cond(cond)));
+                self.block(depth + 1, then);
+                if !els.is_empty() {
+                    self.line(depth, "} else {");
+                    self.block(depth + 1, els);
+                }
+                "}".to_string()
+            },
+            Stmt::While { cond, body, .. } => {
+                let cond = cond.as_ref().map(|cond| self.cond(cond)).unwrap_or_else(|| "true".to_string());
+                self.line(depth, &format!("while ({}) {{", cond));
+                self.block(depth + 1, body);
+                "}".to_string()
+            },
+            Stmt::DoWhile { body, cond, .. } => {
+                self.line(depth, "do {");
+                self.block(depth + 1, body);
+                format!("}} while ({});", self.cond(cond))
+            },
+            Stmt::Jump { target, .. } => format!("goto L{};", target),
+            Stmt::Branch { cond, target, .. } => format!("if ({}) goto L{};", self.cond(cond), target),
+        };
+        self.line(depth, &text);
+    }
+}
+
+fn gotos(stmts: &[Stmt], labels: &mut FxHashSet<i64>) {
+    for stmt in stmts {
+        match stmt {
+            Stmt::Goto { target: Expr::Const(target), .. } | Stmt::Jump { target, .. } | Stmt::Branch { target, .. } => {
+                labels.insert(*target);
+            },
+            Stmt::If { then, els, .. } => {
+                gotos(then, labels);
+                gotos(els, labels);
+            },
+            Stmt::While { body, .. } | Stmt::DoWhile { body, .. } => gotos(body, labels),
+            _ => (),
+        }
+    }
+}
+
+pub fn decompile(image: &[i64]) -> String {
+    let mut decompiler = Decompiler {
+        image,
+        calls: FxHashMap::default(),
+        arity: FxHashMap::default(),
+        roles: FxHashMap::default(),
+        code: FxHashSet::default(),
+    };
+    let functions = decompiler.functions();
+    decompiler.classify(&functions);
+    let lifted: Vec<Vec<Stmt>> = functions.iter().map(|function| decompiler.lift(function)).collect();
+    for stmt in lifted.iter().flatten() {
+        if let Stmt::Call { func, args, .. } = stmt {
+            let arity = decompiler.arity.entry(*func).or_insert(0);
+            *arity = (*arity).max(args.len());
+        }
+    }
+
+    let mut out = String::new();
+    for (function, stmts) in functions.iter().zip(lifted.iter()) {
+        let end = function.instrs.values().next_back().map(Instr::next).unwrap_or(function.entry);
+        let body = decompiler.structure(stmts, end, None);
+        let arity = decompiler.arity.get(&function.entry).copied().unwrap_or(0);
+        let mut labels = FxHashSet::default();
+        gotos(&body, &mut labels);
+        let mut printer = Printer { roles: &decompiler.roles, frame: function.frame, arity, labels, out: String::new() };
+        let params: Vec<String> = (1..=arity).map(|i| format!("a{}", i)).collect();
+        match function.entry {
+            0 => printer.line(0, "void main() {"),
+            entry => printer.line(0, &format!("void f{}({}) {{", entry, params.join(", "))),
+        }
+        printer.block(1, &body);
+        printer.line(0, "}");
+        if !out.is_empty() {
+            out.push('\n');
+        }
+        out.push_str(&printer.out);
+    }
+    out
+}
@@ -0,0 +1,95 @@
+use std::fmt;
+use crate::instruction::{self, Instruction, Operand, ParamMode};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Line {
+    Instr { addr: i64, words: Vec<i64>, text: String },
+    Data { addr: i64, val: i64 },
+}
+
+impl Line {
+    pub fn addr(&self) -> i64 {
+        match self {
+            Line::Instr { addr, .. } => *addr,
+            Line::Data { addr, .. } => *addr,
+        }
+    }
+
+    // number of memory cells the line covers
+    pub fn size(&self) -> i64 {
+        match self {
+            Line::Instr { words, .. } => words.len() as i64,
+            Line::Data { .. } => 1,
+        }
+    }
+
+    // the instruction an `Instr` line was decoded from
+    pub fn instr(&self) -> Option<Instruction> {
+        match self {
+            Line::Instr { words, .. } => instruction::decode(&words[..], 0).ok(),
+            Line::Data { .. } => None,
+        }
+    }
+}
+
+impl fmt::Display for Line {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Line::Instr { addr, text, .. } => write!(f, "{:>6}: {}", addr, text),
+            Line::Data { addr, val } => write!(f, "{:>6}: .data {}", addr, val),
+        }
+    }
+}
+
+fn operand(operand: &Operand) -> String {
+    match operand.mode {
+        ParamMode::Position => format!("[{}]", operand.val),
+        ParamMode::Immediate => format!("{}", operand.val),
+        ParamMode::Relative if operand.val < 0 => format!("[rb-{}]", -operand.val),
+        ParamMode::Relative => format!("[rb+{}]", operand.val),
+    }
+}
+
+// Decodes the instruction at `addr`, or `None` if the cell isn't a valid instruction.
+pub fn decode(image: &[i64], addr: i64) -> Option<Line> {
+    let instr = instruction::decode(image, addr).ok()?;
+    let args: Vec<String> = instr.operands().iter().map(operand).collect();
+    let text = match args.len() {
+        0 => instr.op.mnemonic().to_string(),
+        _ => format!("{} {}", instr.op.mnemonic(), args.join(", ")),
+    };
+    Some(Line::Instr { addr, words: instr.encode(), text })
+}
+
+// Linear-sweep disassembly of a whole image. Anything that doesn't decode is emitted as data.
+pub fn disassemble(image: &[i64]) -> Vec<Line> {
+    let mut lines = Vec::new();
+    let mut addr = 0;
+    while (addr as usize) < image.len() {
+        let line = decode(image, addr).unwrap_or(Line::Data { addr, val: image[addr as usize] });
+        addr += line.size();
+        lines.push(line);
+    }
+    lines
+}
+
+// Linear sweep that realigns on `starts`, the sorted addresses of instructions known to be
+// real (because they ran, or a control-flow graph reached them). An instruction that would
+// run over the next known start is emitted as data instead, which keeps the listing in step
+// with the code even when code and data are interleaved.
+pub fn disassemble_from(image: &[i64], starts: &[i64]) -> Vec<Line> {
+    let mut lines = Vec::new();
+    let mut addr = 0;
+    while (addr as usize) < image.len() {
+        let next = starts.partition_point(|a| *a <= addr);
+        let next_start = *starts.get(next).unwrap_or(&i64::MAX);
+        let known = starts.binary_search(&addr).is_ok();
+        let line = match decode(image, addr) {
+            Some(line) if known || addr + line.size() <= next_start => line,
+            _ => Line::Data { addr, val: image[addr as usize] },
+        };
+        addr += line.size();
+        lines.push(line);
+    }
+    lines
+}
@@ -0,0 +1,127 @@
+use fxhash::FxHashMap;
+use std::collections::VecDeque;
+use crate::Word;
+use crate::memory::{Marks, Memory};
+
+// The instruction that last wrote a cell: `step` counts instructions from the moment
+// recording was switched on, starting at zero.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Writer {
+    pub step: u64,
+    pub pc: i64,
+}
+
+// everything needed to undo one instruction
+#[derive(Debug, Clone)]
+pub(crate) struct Step<W> {
+    pub(crate) pc: i64,
+    pub(crate) rb: i64,
+    // previous contents of every cell the instruction wrote, in write order
+    pub(crate) writes: Vec<(i64, Option<W>)>,
+    // what protection started tracking during the instruction
+    pub(crate) marks: Marks,
+    pub(crate) writers: Vec<(i64, Option<Writer>)>,
+    pub(crate) input: Option<W>,
+    pub(crate) output: bool,
+}
+
+// A full copy of the machine taken every `interval` steps. Only the newest window keeps a
+// per-instruction undo log; older windows are rebuilt on demand by restoring their keyframe
+// and replaying the inputs they consumed.
+#[derive(Debug, Clone)]
+pub(crate) struct Keyframe<W> {
+    pub(crate) step: u64,
+    pub(crate) mem: Memory<W>,
+    pub(crate) pc: i64,
+    pub(crate) rb: i64,
+    pub(crate) outputs: usize,
+    pub(crate) writers: FxHashMap<i64, Writer>,
+    // inputs consumed between this keyframe and the next
+    pub(crate) fed: Vec<W>,
+}
+
+// Undo log for `ProcIntCode`'s time-travel debugging. Memory is bounded by `keep` keyframes
+// plus at most `interval` undo entries; anything older than the oldest keyframe is gone.
+// Replaying a window re-runs host call handlers, so they should be deterministic.
+#[derive(Debug, Clone)]
+pub struct History<W> {
+    pub(crate) interval: u64,
+    pub(crate) keep: usize,
+    pub(crate) steps: u64,
+    pub(crate) undo: Vec<Step<W>>,
+    pub(crate) keyframes: VecDeque<Keyframe<W>>,
+    pub(crate) writers: FxHashMap<i64, Writer>,
+}
+
+impl<W: Word> History<W> {
+    pub(crate) fn new(interval: u64, keep: usize) -> History<W> {
+        assert!(interval > 0 && keep > 0, "history needs a nonzero interval and at least one keyframe");
+        History {
+            interval,
+            keep,
+            steps: 0,
+            undo: Vec::new(),
+            keyframes: VecDeque::new(),
+            writers: FxHashMap::default(),
+        }
+    }
+
+    // instructions executed since recording started, less any stepped back over
+    pub fn steps(&self) -> u64 {
+        self.steps
+    }
+
+    // the earliest step `step_back` can still reach
+    pub fn horizon(&self) -> u64 {
+        self.keyframes.front().map(|keyframe| keyframe.step).unwrap_or(self.steps)
+    }
+
+    pub fn last_writer(&self, addr: i64) -> Option<Writer> {
+        self.writers.get(&addr).copied()
+    }
+
+    pub(crate) fn keyframe(&mut self, mem: &Memory<W>, pc: i64, rb: i64, outputs: usize) {
+        self.undo.clear();
+        self.keyframes.push_back(Keyframe {
+            step: self.steps,
+            mem: mem.clone(),
+            pc,
+            rb,
+            outputs,
+            writers: self.writers.clone(),
+            fed: Vec::new(),
+        });
+        if self.keyframes.len() > self.keep {
+            self.keyframes.pop_front();
+        }
+    }
+
+    // true once the newest window is full and the next keyframe is due
+    pub(crate) fn push(&mut self, mut step: Step<W>) -> bool {
+        for (addr, _) in step.writes.iter() {
+            let writer = Writer { step: self.steps, pc: step.pc };
+            step.writers.push((*addr, self.writers.insert(*addr, writer)));
+        }
+        if let (Some(input), Some(keyframe)) = (&step.input, self.keyframes.back_mut()) {
+            keyframe.fed.push(input.clone());
+        }
+        self.undo.push(step);
+        self.steps += 1;
+        self.steps - self.keyframes.back().map(|keyframe| keyframe.step).unwrap_or(0) >= self.interval
+    }
+
+    pub(crate) fn pop(&mut self) -> Option<Step<W>> {
+        let step = self.undo.pop()?;
+        for (addr, writer) in step.writers.iter().rev() {
+            match writer {
+                Some(writer) => self.writers.insert(*addr, *writer),
+                None => self.writers.remove(addr),
+            };
+        }
+        if let (Some(_), Some(keyframe)) = (&step.input, self.keyframes.back_mut()) {
+            keyframe.fed.pop();
+        }
+        self.steps -= 1;
+        Some(step)
+    }
+}
@@ -0,0 +1,210 @@
+// The standard instruction set in one place. Both machines decode through `decode`, and so
+// does the disassembler that all of the static tooling is built on, so they can't disagree
+// about what counts as a valid instruction.
+use crate::Word;
+use crate::memory::Memory;
+use crate::syscall::SYSCALL;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ParamMode {
+    Position,
+    Immediate,
+    Relative,
+}
+
+impl ParamMode {
+    pub fn from_digit(digit: i64) -> Option<ParamMode> {
+        match digit {
+            0 => Some(ParamMode::Position),
+            1 => Some(ParamMode::Immediate),
+            2 => Some(ParamMode::Relative),
+            _ => None,
+        }
+    }
+
+    pub fn digit(self) -> i64 {
+        match self {
+            ParamMode::Position => 0,
+            ParamMode::Immediate => 1,
+            ParamMode::Relative => 2,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Op {
+    Add,
+    Mul,
+    Read,
+    Write,
+    JumpIfTrue,
+    JumpIfFalse,
+    LessThan,
+    Equals,
+    UpdateRb,
+    // host call, see the `syscall` module for the calling convention
+    Syscall,
+    Halt,
+}
+
+impl Op {
+    pub fn from_code(code: i64) -> Option<Op> {
+        match code {
+            1 => Some(Op::Add),
+            2 => Some(Op::Mul),
+            3 => Some(Op::Read),
+            4 => Some(Op::Write),
+            5 => Some(Op::JumpIfTrue),
+            6 => Some(Op::JumpIfFalse),
+            7 => Some(Op::LessThan),
+            8 => Some(Op::Equals),
+            9 => Some(Op::UpdateRb),
+            SYSCALL => Some(Op::Syscall),
+            99 => Some(Op::Halt),
+            _ => None,
+        }
+    }
+
+    // the lowest two digits of the opcode word
+    pub fn code(self) -> i64 {
+        match self {
+            Op::Add => 1,
+            Op::Mul => 2,
+            Op::Read => 3,
+            Op::Write => 4,
+            Op::JumpIfTrue => 5,
+            Op::JumpIfFalse => 6,
+            Op::LessThan => 7,
+            Op::Equals => 8,
+            Op::UpdateRb => 9,
+            Op::Syscall => SYSCALL,
+            Op::Halt => 99,
+        }
+    }
+
+    pub fn params(self) -> usize {
+        match self {
+            Op::Add | Op::Mul | Op::LessThan | Op::Equals => 3,
+            Op::JumpIfTrue | Op::JumpIfFalse => 2,
+            Op::Read | Op::Write | Op::UpdateRb => 1,
+            Op::Syscall | Op::Halt => 0,
+        }
+    }
+
+    // number of memory cells the instruction occupies
+    pub fn size(self) -> i64 {
+        self.params() as i64 + 1
+    }
+
+    // the name the disassembler prints
+    pub fn mnemonic(self) -> &'static str {
+        match self {
+            Op::Add => "add",
+            Op::Mul => "mul",
+            Op::Read => "in",
+            Op::Write => "out",
+            Op::JumpIfTrue => "jt",
+            Op::JumpIfFalse => "jf",
+            Op::LessThan => "lt",
+            Op::Equals => "eq",
+            Op::UpdateRb => "arb",
+            Op::Syscall => "sys",
+            Op::Halt => "hlt",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Operand<W = i64> {
+    pub mode: ParamMode,
+    pub val: W,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Instruction<W = i64> {
+    pub op: Op,
+    // only the first `op.params()` mean anything, the rest are always zero in position mode
+    pub operands: [Operand<W>; 3],
+}
+
+impl<W: Word> Instruction<W> {
+    // panics unless there's exactly one operand per parameter
+    pub fn new(op: Op, operands: &[Operand<W>]) -> Instruction<W> {
+        assert_eq!(op.params(), operands.len(), "{} takes {} operands", op.mnemonic(), op.params());
+        let mut slots = [Operand::unused(), Operand::unused(), Operand::unused()];
+        slots[..operands.len()].clone_from_slice(operands);
+        Instruction { op, operands: slots }
+    }
+
+    pub fn operands(&self) -> &[Operand<W>] {
+        &self.operands[..self.op.params()]
+    }
+
+    pub fn size(&self) -> i64 {
+        self.op.size()
+    }
+
+    // the words `decode` would read this back from
+    pub fn encode(&self) -> Vec<W> {
+        let modes = self.operands().iter().enumerate()
+            .map(|(i, operand)| operand.mode.digit() * 10i64.pow(i as u32 + 2))
+            .sum::<i64>();
+        let mut words = vec![W::from(self.op.code() + modes)];
+        words.extend(self.operands().iter().map(|operand| operand.val.clone()));
+        words
+    }
+}
+
+impl<W: Word> Operand<W> {
+    fn unused() -> Operand<W> {
+        Operand { mode: ParamMode::Position, val: W::default() }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DecodeError {
+    // the opcode word is negative or doesn't fit in an i64
+    BadWord,
+    UnknownOpcode(i64),
+    // the opcode word has a mode digit other than 0, 1 or 2 for one of the parameters
+    BadModes(i64),
+    // the instruction runs off the end of the image
+    Truncated,
+}
+
+// Anything instructions can be decoded out of. `None` means the address is outside the
+// image; a machine's memory has no outside, so it never says that.
+pub trait Fetch<W> {
+    fn fetch(&self, addr: i64) -> Option<W>;
+}
+
+impl<W: Clone> Fetch<W> for [W] {
+    fn fetch(&self, addr: i64) -> Option<W> {
+        match addr < 0 {
+            true => None,
+            false => self.get(addr as usize).cloned(),
+        }
+    }
+}
+
+impl<W: Word> Fetch<W> for Memory<W> {
+    fn fetch(&self, addr: i64) -> Option<W> {
+        Some(self.get(addr))
+    }
+}
+
+pub fn decode<W: Word, M: Fetch<W> + ?Sized>(mem: &M, pc: i64) -> Result<Instruction<W>, DecodeError> {
+    let word = mem.fetch(pc).ok_or(DecodeError::Truncated)?;
+    let word = word.to_i64().filter(|word| *word >= 0).ok_or(DecodeError::BadWord)?;
+    let op = Op::from_code(word % 100).ok_or(DecodeError::UnknownOpcode(word % 100))?;
+    let mut modes = word / 100;
+    let mut operands = [Operand::unused(), Operand::unused(), Operand::unused()];
+    // digits past the last parameter's mode are ignored, as the machines always have
+    for (i, operand) in operands.iter_mut().take(op.params()).enumerate() {
+        let mode = ParamMode::from_digit(modes % 10).ok_or(DecodeError::BadModes(word))?;
+        let val = mem.fetch(pc + 1 + i as i64).ok_or(DecodeError::Truncated)?;
+        *operand = Operand { mode, val };
+        modes /= 10;
+    }
+    Ok(Instruction { op, operands })
+}
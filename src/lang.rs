@@ -0,0 +1,947 @@
+// Compiler for a small structured language, down to an image `ProcIntCode` or `PolyIntCode`
+// can run:
+//
+//   var primes[5] = { 2, 3, 5, 7, 11 };
+//   var calls = 0;
+//
+//   fn fib(n) {
+//       calls = calls + 1;
+//       if (n < 2) { return n; }
+//       return fib(n - 1) + fib(n - 2);
+//   }
+//
+//   fn main() {
+//       var i = input();
+//       while (i < 5) {
+//           output(fib(primes[i]));
+//           i = i + 1;
+//       }
+//   }
+//
+// Everything is an i64. Globals are scalars or fixed-size arrays, optionally initialized with
+// constants; locals are scalars, declared anywhere in a function and zeroed when declared.
+// Operators, loosest first: `||`, `&&` (both short-circuit), `==` `!=`, `<` `>` `<=` `>=`,
+// `+` `-`, `*`, and unary `-` and `!`. Statements are `if`/`else`, `while`, `break`,
+// `continue`, `return`, assignment, `output(e)` and bare expressions; `input()` reads.
+//
+// The relative base is the frame pointer. A frame holds the return address at [rb+0], the
+// arguments from [rb+1], then scratch slots for expressions, then locals. A call copies the
+// arguments to just past the caller's frame, stores the return address below them, moves rb
+// up by the caller's frame size and jumps; the callee returns through [rb+0] with the result
+// in a global cell, and the caller moves rb back down. Array elements are reached by writing
+// the element's address into the operand of the instruction that uses it.
+use fxhash::FxHashMap;
+use std::fmt;
+use crate::instruction::{Instruction, Op, Operand, ParamMode};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CompileError {
+    pub line: usize,
+    pub msg: String,
+}
+
+impl fmt::Display for CompileError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.msg)
+    }
+}
+
+fn error<T>(line: usize, msg: String) -> Result<T, CompileError> {
+    Err(CompileError { line, msg })
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Tok {
+    Num(i64),
+    Ident(String),
+    Sym(&'static str),
+    Eof,
+}
+
+// longest first, so `<=` isn't lexed as `<` `=`
+const SYMBOLS: [&str; 21] = [
+    "<=", ">=", "==", "!=", "&&", "||",
+    "(", ")", "{", "}", "[", "]", ",", ";", "=", "+", "-", "*", "<", ">", "!",
+];
+
+fn lex(source: &str) -> Result<Vec<(Tok, usize)>, CompileError> {
+    let mut tokens = Vec::new();
+    for (i, text) in source.lines().enumerate() {
+        let line = i + 1;
+        let text = text.split("//").next().unwrap_or("");
+        let mut rest = text.trim_start();
+        while !rest.is_empty() {
+            let c = rest.chars().next().unwrap();
+            let len = if c.is_ascii_digit() {
+                let len = rest.find(|c: char| !c.is_ascii_digit()).unwrap_or(rest.len());
+                match rest[..len].parse() {
+                    Ok(val) => tokens.push((Tok::Num(val), line)),
+                    Err(_) => return error(line, format!("`{}` doesn't fit in an i64", &rest[..len])),
+                }
+                len
+            } else if c.is_ascii_alphabetic() || c == '_' {
+                let len = rest.find(|c: char| !c.is_ascii_alphanumeric() && c != '_').unwrap_or(rest.len());
+                tokens.push((Tok::Ident(rest[..len].to_string()), line));
+                len
+            } else {
+                match SYMBOLS.iter().find(|sym| rest.starts_with(*sym)) {
+                    Some(sym) => {
+                        tokens.push((Tok::Sym(sym), line));
+                        sym.len()
+                    },
+                    None => return error(line, format!("unexpected `{}`", c)),
+                }
+            };
+            rest = rest[len..].trim_start();
+        }
+    }
+    let last = source.lines().count().max(1);
+    tokens.push((Tok::Eof, last));
+    Ok(tokens)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum BinOp {
+    Or,
+    And,
+    Eq,
+    Ne,
+    Lt,
+    Gt,
+    Le,
+    Ge,
+    Add,
+    Sub,
+    Mul,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum ExprKind {
+    Num(i64),
+    Var(String),
+    Index(String, Box<Expr>),
+    Call(String, Vec<Expr>),
+    Neg(Box<Expr>),
+    Not(Box<Expr>),
+    Binary(BinOp, Box<Expr>, Box<Expr>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Expr {
+    kind: ExprKind,
+    line: usize,
+}
+
+impl Expr {
+    // whether evaluating this could run a function, which might change globals
+    fn calls(&self) -> bool {
+        match &self.kind {
+            ExprKind::Num(_) | ExprKind::Var(_) => false,
+            ExprKind::Call(name, _) if name == "input" => false,
+            ExprKind::Call(..) => true,
+            ExprKind::Index(_, e) | ExprKind::Neg(e) | ExprKind::Not(e) => e.calls(),
+            ExprKind::Binary(_, a, b) => a.calls() || b.calls(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Stmt {
+    Var { name: String, init: Option<Expr>, line: usize },
+    Assign { target: Expr, val: Expr },
+    If { cond: Expr, then: Vec<Stmt>, els: Vec<Stmt> },
+    While { cond: Expr, body: Vec<Stmt> },
+    Break(usize),
+    Continue(usize),
+    Return(Option<Expr>),
+    Output(Expr),
+    Expr(Expr),
+}
+
+#[derive(Debug, Clone)]
+struct Function {
+    name: String,
+    params: Vec<String>,
+    body: Vec<Stmt>,
+    line: usize,
+}
+
+#[derive(Debug, Clone)]
+struct Global {
+    name: String,
+    // `None` for a scalar
+    len: Option<usize>,
+    init: Vec<i64>,
+    line: usize,
+}
+
+struct Parser {
+    tokens: Vec<(Tok, usize)>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> &Tok {
+        &self.tokens[self.pos].0
+    }
+
+    fn line(&self) -> usize {
+        self.tokens[self.pos].1
+    }
+
+    fn advance(&mut self) -> Tok {
+        let tok = self.tokens[self.pos].0.clone();
+        if tok != Tok::Eof {
+            self.pos += 1;
+        }
+        tok
+    }
+
+    fn eat(&mut self, sym: &str) -> bool {
+        match self.peek() {
+            Tok::Sym(s) if *s == sym => {
+                self.pos += 1;
+                true
+            },
+            _ => false,
+        }
+    }
+
+    fn keyword(&mut self, word: &str) -> bool {
+        match self.peek() {
+            Tok::Ident(ident) if ident == word => {
+                self.pos += 1;
+                true
+            },
+            _ => false,
+        }
+    }
+
+    fn unexpected<T>(&self, wanted: &str) -> Result<T, CompileError> {
+        let found = match self.peek() {
+            Tok::Num(val) => format!("`{}`", val),
+            Tok::Ident(ident) => format!("`{}`", ident),
+            Tok::Sym(sym) => format!("`{}`", sym),
+            Tok::Eof => "the end of the file".to_string(),
+        };
+        error(self.line(), format!("expected {}, found {}", wanted, found))
+    }
+
+    fn expect(&mut self, sym: &str) -> Result<(), CompileError> {
+        match self.eat(sym) {
+            true => Ok(()),
+            false => self.unexpected(&format!("`{}`", sym)),
+        }
+    }
+
+    fn ident(&mut self) -> Result<String, CompileError> {
+        match self.peek().clone() {
+            Tok::Ident(ident) => {
+                self.pos += 1;
+                Ok(ident)
+            },
+            _ => self.unexpected("a name"),
+        }
+    }
+
+    fn constant(&mut self) -> Result<i64, CompileError> {
+        let negative = self.eat("-");
+        match self.peek().clone() {
+            Tok::Num(val) => {
+                self.pos += 1;
+                Ok(if negative { -val } else { val })
+            },
+            _ => self.unexpected("a number"),
+        }
+    }
+
+    fn program(&mut self) -> Result<(Vec<Global>, Vec<Function>), CompileError> {
+        let (mut globals, mut functions) = (Vec::new(), Vec::new());
+        while *self.peek() != Tok::Eof {
+            let line = self.line();
+            if self.keyword("fn") {
+                let name = self.ident()?;
+                self.expect("(")?;
+                let mut params = Vec::new();
+                while !self.eat(")") {
+                    if !params.is_empty() {
+                        self.expect(",")?;
+                    }
+                    params.push(self.ident()?);
+                }
+                let body = self.block()?;
+                functions.push(Function { name, params, body, line });
+            } else if self.keyword("var") {
+                globals.push(self.global(line)?);
+            } else {
+                return self.unexpected("`fn` or `var`");
+            }
+        }
+        Ok((globals, functions))
+    }
+
+    fn global(&mut self, line: usize) -> Result<Global, CompileError> {
+        let name = self.ident()?;
+        let len = match self.eat("[") {
+            true => {
+                let len = match self.advance() {
+                    Tok::Num(len) if len > 0 => len as usize,
+                    _ => return error(line, format!("`{}` needs a positive size", name)),
+                };
+                self.expect("]")?;
+                Some(len)
+            },
+            false => None,
+        };
+        let mut init = Vec::new();
+        if self.eat("=") {
+            match len {
+                Some(_) => {
+                    self.expect("{")?;
+                    while !self.eat("}") {
+                        if !init.is_empty() {
+                            self.expect(",")?;
+                        }
+                        init.push(self.constant()?);
+                    }
+                },
+                None => init.push(self.constant()?),
+            }
+        }
+        if init.len() > len.unwrap_or(1) {
+            return error(line, format!("too many initializers for `{}`", name));
+        }
+        self.expect(";")?;
+        Ok(Global { name, len, init, line })
+    }
+
+    fn block(&mut self) -> Result<Vec<Stmt>, CompileError> {
+        self.expect("{")?;
+        let mut stmts = Vec::new();
+        while !self.eat("}") {
+            stmts.push(self.stmt()?);
+        }
+        Ok(stmts)
+    }
+
+    fn stmt(&mut self) -> Result<Stmt, CompileError> {
+        let line = self.line();
+        let stmt = if self.keyword("var") {
+            let name = self.ident()?;
+            let init = match self.eat("=") {
+                true => Some(self.expr()?),
+                false => None,
+            };
+            Stmt::Var { name, init, line }
+        } else if self.keyword("if") {
+            return self.if_stmt();
+        } else if self.keyword("while") {
+            self.expect("(")?;
+            let cond = self.expr()?;
+            self.expect(")")?;
+            return Ok(Stmt::While { cond, body: self.block()? });
+        } else if self.keyword("break") {
+            Stmt::Break(line)
+        } else if self.keyword("continue") {
+            Stmt::Continue(line)
+        } else if self.keyword("return") {
+            match self.peek() {
+                Tok::Sym(";") => Stmt::Return(None),
+                _ => Stmt::Return(Some(self.expr()?)),
+            }
+        } else if self.keyword("output") {
+            self.expect("(")?;
+            let val = self.expr()?;
+            self.expect(")")?;
+            Stmt::Output(val)
+        } else {
+            let expr = self.expr()?;
+            match self.eat("=") {
+                true => match expr.kind {
+                    ExprKind::Var(_) | ExprKind::Index(..) => Stmt::Assign { target: expr, val: self.expr()? },
+                    _ => return error(line, "can only assign to a variable or array element".to_string()),
+                },
+                false => Stmt::Expr(expr),
+            }
+        };
+        self.expect(";")?;
+        Ok(stmt)
+    }
+
+    fn if_stmt(&mut self) -> Result<Stmt, CompileError> {
+        self.expect("(")?;
+        let cond = self.expr()?;
+        self.expect(")")?;
+        let then = self.block()?;
+        let els = match self.keyword("else") {
+            true if self.keyword("if") => vec![self.if_stmt()?],
+            true => self.block()?,
+            false => Vec::new(),
+        };
+        Ok(Stmt::If { cond, then, els })
+    }
+
+    fn expr(&mut self) -> Result<Expr, CompileError> {
+        self.binary(0)
+    }
+
+    // operators at each precedence level, loosest first
+    const LEVELS: [&'static [(&'static str, BinOp)]; 6] = [
+        &[("||", BinOp::Or)],
+        &[("&&", BinOp::And)],
+        &[("==", BinOp::Eq), ("!=", BinOp::Ne)],
+        &[("<=", BinOp::Le), (">=", BinOp::Ge), ("<", BinOp::Lt), (">", BinOp::Gt)],
+        &[("+", BinOp::Add), ("-", BinOp::Sub)],
+        &[("*", BinOp::Mul)],
+    ];
+
+    fn binary(&mut self, level: usize) -> Result<Expr, CompileError> {
+        if level == Parser::LEVELS.len() {
+            return self.unary();
+        }
+        let mut lhs = self.binary(level + 1)?;
+        loop {
+            let line = self.line();
+            let op = Parser::LEVELS[level].iter().find(|(sym, _)| self.peek() == &Tok::Sym(sym));
+            let op = match op {
+                Some((_, op)) => *op,
+                None => return Ok(lhs),
+            };
+            self.pos += 1;
+            let rhs = self.binary(level + 1)?;
+            lhs = Expr { kind: ExprKind::Binary(op, Box::new(lhs), Box::new(rhs)), line };
+        }
+    }
+
+    fn unary(&mut self) -> Result<Expr, CompileError> {
+        let line = self.line();
+        if self.eat("-") {
+            return Ok(Expr { kind: ExprKind::Neg(Box::new(self.unary()?)), line });
+        }
+        if self.eat("!") {
+            return Ok(Expr { kind: ExprKind::Not(Box::new(self.unary()?)), line });
+        }
+        let kind = match self.peek().clone() {
+            Tok::Num(val) => {
+                self.pos += 1;
+                ExprKind::Num(val)
+            },
+            Tok::Sym("(") => {
+                self.pos += 1;
+                let expr = self.expr()?;
+                self.expect(")")?;
+                return Ok(expr);
+            },
+            Tok::Ident(name) => {
+                self.pos += 1;
+                if self.eat("(") {
+                    let mut args = Vec::new();
+                    while !self.eat(")") {
+                        if !args.is_empty() {
+                            self.expect(",")?;
+                        }
+                        args.push(self.expr()?);
+                    }
+                    ExprKind::Call(name, args)
+                } else if self.eat("[") {
+                    let index = self.expr()?;
+                    self.expect("]")?;
+                    ExprKind::Index(name, Box::new(index))
+                } else {
+                    ExprKind::Var(name)
+                }
+            },
+            _ => return self.unexpected("an expression"),
+        };
+        Ok(Expr { kind, line })
+    }
+}
+
+// an instruction parameter, before addresses are known
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Arg {
+    Imm(i64),
+    // position mode at a fixed address
+    Abs(i64),
+    // [rb+offset]
+    Rel(i64),
+    // position mode on a data cell
+    Data(usize),
+    // the address of a data cell, as an immediate
+    DataAddr(usize),
+    // the address of a label, as an immediate
+    Label(usize),
+    // where the stack starts, as an immediate
+    Stack,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Fixup {
+    Data(usize),
+    Label(usize),
+    Stack,
+}
+
+#[derive(Default)]
+struct Asm {
+    words: Vec<i64>,
+    fixups: Vec<(usize, Fixup)>,
+    labels: Vec<Option<i64>>,
+}
+
+impl Asm {
+    fn here(&self) -> i64 {
+        self.words.len() as i64
+    }
+
+    fn label(&mut self) -> usize {
+        self.labels.push(None);
+        self.labels.len() - 1
+    }
+
+    fn bind(&mut self, label: usize) {
+        self.labels[label] = Some(self.here());
+    }
+
+    fn emit(&mut self, op: Op, args: &[Arg]) {
+        let at = self.words.len();
+        let mut operands = Vec::new();
+        for (i, arg) in args.iter().enumerate() {
+            let (mode, val, fixup) = match *arg {
+                Arg::Imm(val) => (ParamMode::Immediate, val, None),
+                Arg::Abs(addr) => (ParamMode::Position, addr, None),
+                Arg::Rel(offset) => (ParamMode::Relative, offset, None),
+                Arg::Data(cell) => (ParamMode::Position, 0, Some(Fixup::Data(cell))),
+                Arg::DataAddr(cell) => (ParamMode::Immediate, 0, Some(Fixup::Data(cell))),
+                Arg::Label(label) => (ParamMode::Immediate, 0, Some(Fixup::Label(label))),
+                Arg::Stack => (ParamMode::Immediate, 0, Some(Fixup::Stack)),
+            };
+            operands.push(Operand { mode, val });
+            if let Some(fixup) = fixup {
+                self.fixups.push((at + 1 + i, fixup));
+            }
+        }
+        self.words.extend(Instruction::new(op, &operands).encode());
+    }
+
+    fn mov(&mut self, from: Arg, to: Arg) {
+        self.emit(Op::Add, &[from, Arg::Imm(0), to]);
+    }
+
+    fn jump(&mut self, to: Arg) {
+        self.emit(Op::JumpIfTrue, &[Arg::Imm(1), to]);
+    }
+
+    // code, then data, then the stack
+    fn link(mut self, data: &[i64]) -> Vec<i64> {
+        let code = self.words.len() as i64;
+        for (at, fixup) in self.fixups.iter() {
+            self.words[*at] = match fixup {
+                Fixup::Data(cell) => code + *cell as i64,
+                Fixup::Label(label) => self.labels[*label].expect("every label is bound"),
+                Fixup::Stack => code + data.len() as i64,
+            };
+        }
+        self.words.extend_from_slice(data);
+        self.words
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Symbol {
+    Scalar(usize),
+    Array(usize),
+}
+
+struct Signature {
+    label: usize,
+    params: usize,
+}
+
+#[derive(Default)]
+struct Gen {
+    asm: Asm,
+    data: Vec<i64>,
+    globals: FxHashMap<String, Symbol>,
+    functions: FxHashMap<String, Signature>,
+    // data cell holding the value a function returns
+    result: usize,
+    // the function being compiled
+    locals: FxHashMap<String, i64>,
+    next_local: i64,
+    // offset of the first scratch slot
+    scratch: i64,
+    // scratch slots used so far
+    depth: i64,
+    frame: i64,
+    // (continue, break) labels of the enclosing loops
+    loops: Vec<(usize, usize)>,
+}
+
+impl Gen {
+    fn temp(&mut self, depth: i64) -> Arg {
+        self.depth = self.depth.max(depth + 1);
+        Arg::Rel(self.scratch + depth)
+    }
+
+    fn var(&self, name: &str, line: usize) -> Result<Arg, CompileError> {
+        if let Some(offset) = self.locals.get(name) {
+            return Ok(Arg::Rel(*offset));
+        }
+        match self.globals.get(name) {
+            Some(Symbol::Scalar(cell)) => Ok(Arg::Data(*cell)),
+            Some(Symbol::Array(_)) => error(line, format!("`{}` is an array", name)),
+            None => error(line, format!("`{}` isn't declared", name)),
+        }
+    }
+
+    fn array(&self, name: &str, line: usize) -> Result<usize, CompileError> {
+        match (self.locals.contains_key(name), self.globals.get(name)) {
+            (false, Some(Symbol::Array(cell))) => Ok(*cell),
+            (false, None) => error(line, format!("`{}` isn't declared", name)),
+            _ => error(line, format!("`{}` isn't an array", name)),
+        }
+    }
+
+    // Evaluates `expr` using scratch slots from `depth` up, and returns where the value ended
+    // up: an immediate, a variable, or the slot at `depth`.
+    fn expr(&mut self, expr: &Expr, depth: i64) -> Result<Arg, CompileError> {
+        let line = expr.line;
+        let val = match &expr.kind {
+            ExprKind::Num(val) => Arg::Imm(*val),
+            ExprKind::Var(name) => self.var(name, line)?,
+            ExprKind::Index(name, index) => {
+                let base = self.array(name, line)?;
+                let index = self.expr(index, depth)?;
+                let to = self.temp(depth);
+                // the load's first operand is 5 cells on
+                let operand = self.asm.here() + 5;
+                self.asm.emit(Op::Add, &[index, Arg::DataAddr(base), Arg::Abs(operand)]);
+                self.asm.mov(Arg::Abs(0), to);
+                to
+            },
+            ExprKind::Call(name, args) if name == "input" => {
+                if !args.is_empty() {
+                    return error(line, "`input` takes no arguments".to_string());
+                }
+                let to = self.temp(depth);
+                self.asm.emit(Op::Read, &[to]);
+                to
+            },
+            ExprKind::Call(name, args) => self.call(name, args, depth, line)?,
+            ExprKind::Neg(e) => match self.expr(e, depth)? {
+                Arg::Imm(val) => Arg::Imm(val.wrapping_neg()),
+                val => {
+                    let to = self.temp(depth);
+                    self.asm.emit(Op::Mul, &[val, Arg::Imm(-1), to]);
+                    to
+                },
+            },
+            ExprKind::Not(e) => match self.expr(e, depth)? {
+                Arg::Imm(val) => Arg::Imm((val == 0) as i64),
+                val => {
+                    let to = self.temp(depth);
+                    self.asm.emit(Op::Equals, &[val, Arg::Imm(0), to]);
+                    to
+                },
+            },
+            ExprKind::Binary(op @ (BinOp::And | BinOp::Or), a, b) => {
+                // to = a != 0, and only look at b if that didn't settle it
+                let to = self.temp(depth);
+                let end = self.asm.label();
+                let a = self.expr(a, depth)?;
+                self.truth(a, to);
+                let settled = match op {
+                    BinOp::And => Op::JumpIfFalse,
+                    _ => Op::JumpIfTrue,
+                };
+                self.asm.emit(settled, &[to, Arg::Label(end)]);
+                let b = self.expr(b, depth)?;
+                self.truth(b, to);
+                self.asm.bind(end);
+                to
+            },
+            ExprKind::Binary(op, a, b) => {
+                let mut lhs = self.expr(a, depth)?;
+                // a call on the right could change a variable read on the left
+                if b.calls() && !matches!(lhs, Arg::Imm(_)) {
+                    let to = self.temp(depth);
+                    self.asm.mov(lhs, to);
+                    lhs = to;
+                }
+                let rhs = self.expr(b, depth + 1)?;
+                self.binary(*op, lhs, rhs, depth)
+            },
+        };
+        Ok(val)
+    }
+
+    // to = val != 0
+    fn truth(&mut self, val: Arg, to: Arg) {
+        self.asm.emit(Op::Equals, &[val, Arg::Imm(0), to]);
+        self.asm.emit(Op::Equals, &[to, Arg::Imm(0), to]);
+    }
+
+    fn binary(&mut self, op: BinOp, a: Arg, b: Arg, depth: i64) -> Arg {
+        if let (Arg::Imm(a), Arg::Imm(b)) = (a, b) {
+            return Arg::Imm(match op {
+                BinOp::Add => a.wrapping_add(b),
+                BinOp::Sub => a.wrapping_sub(b),
+                BinOp::Mul => a.wrapping_mul(b),
+                BinOp::Eq => (a == b) as i64,
+                BinOp::Ne => (a != b) as i64,
+                BinOp::Lt => (a < b) as i64,
+                BinOp::Gt => (a > b) as i64,
+                BinOp::Le => (a <= b) as i64,
+                BinOp::Ge => (a >= b) as i64,
+                BinOp::And | BinOp::Or => unreachable!("logical operators short-circuit"),
+            });
+        }
+        let to = self.temp(depth);
+        match op {
+            BinOp::Add => self.asm.emit(Op::Add, &[a, b, to]),
+            BinOp::Sub => match b {
+                Arg::Imm(b) => self.asm.emit(Op::Add, &[a, Arg::Imm(b.wrapping_neg()), to]),
+                _ => {
+                    let negated = self.temp(depth + 1);
+                    self.asm.emit(Op::Mul, &[b, Arg::Imm(-1), negated]);
+                    self.asm.emit(Op::Add, &[a, negated, to]);
+                },
+            },
+            BinOp::Mul => self.asm.emit(Op::Mul, &[a, b, to]),
+            BinOp::Eq => self.asm.emit(Op::Equals, &[a, b, to]),
+            BinOp::Lt => self.asm.emit(Op::LessThan, &[a, b, to]),
+            BinOp::Gt => self.asm.emit(Op::LessThan, &[b, a, to]),
+            // the rest are negations
+            BinOp::Ne | BinOp::Le | BinOp::Ge => {
+                match op {
+                    BinOp::Ne => self.asm.emit(Op::Equals, &[a, b, to]),
+                    BinOp::Le => self.asm.emit(Op::LessThan, &[b, a, to]),
+                    _ => self.asm.emit(Op::LessThan, &[a, b, to]),
+                }
+                self.asm.emit(Op::Equals, &[to, Arg::Imm(0), to]);
+            },
+            BinOp::And | BinOp::Or => unreachable!("logical operators short-circuit"),
+        }
+        to
+    }
+
+    fn call(&mut self, name: &str, args: &[Expr], depth: i64, line: usize) -> Result<Arg, CompileError> {
+        let (label, params) = match self.functions.get(name) {
+            Some(sig) => (sig.label, sig.params),
+            None if name == "output" => return error(line, "`output` doesn't return a value".to_string()),
+            None => return error(line, format!("`{}` isn't a function", name)),
+        };
+        if args.len() != params {
+            let plural = if params == 1 { "" } else { "s" };
+            return error(line, format!("`{}` takes {} argument{}, not {}", name, params, plural, args.len()));
+        }
+        // everything is evaluated before anything lands past the frame, where a nested call
+        // would overwrite it
+        let mut vals = Vec::new();
+        for (i, arg) in args.iter().enumerate() {
+            let slot = depth + i as i64;
+            let mut val = self.expr(arg, slot)?;
+            let later = args[i + 1..].iter().any(Expr::calls);
+            if later && !matches!(val, Arg::Imm(_)) && val != self.temp(slot) {
+                let to = self.temp(slot);
+                self.asm.mov(val, to);
+                val = to;
+            }
+            vals.push(val);
+        }
+        let frame = self.frame;
+        for (i, val) in vals.into_iter().enumerate() {
+            self.asm.mov(val, Arg::Rel(frame + 1 + i as i64));
+        }
+        let back = self.asm.label();
+        self.asm.mov(Arg::Label(back), Arg::Rel(frame));
+        self.asm.emit(Op::UpdateRb, &[Arg::Imm(frame)]);
+        self.asm.jump(Arg::Label(label));
+        self.asm.bind(back);
+        self.asm.emit(Op::UpdateRb, &[Arg::Imm(-frame)]);
+        let to = self.temp(depth);
+        self.asm.mov(Arg::Data(self.result), to);
+        Ok(to)
+    }
+
+    fn stmts(&mut self, stmts: &[Stmt]) -> Result<(), CompileError> {
+        for stmt in stmts {
+            self.stmt(stmt)?;
+        }
+        Ok(())
+    }
+
+    fn stmt(&mut self, stmt: &Stmt) -> Result<(), CompileError> {
+        match stmt {
+            Stmt::Var { name, init, line } => {
+                if self.locals.contains_key(name) {
+                    return error(*line, format!("`{}` is already declared", name));
+                }
+                let val = match init {
+                    Some(init) => self.expr(init, 0)?,
+                    None => Arg::Imm(0),
+                };
+                let offset = self.next_local;
+                self.next_local += 1;
+                self.locals.insert(name.clone(), offset);
+                self.asm.mov(val, Arg::Rel(offset));
+            },
+            Stmt::Assign { target, val } => match &target.kind {
+                ExprKind::Index(name, index) => {
+                    let base = self.array(name, target.line)?;
+                    let mut index = self.expr(index, 0)?;
+                    if val.calls() && !matches!(index, Arg::Imm(_)) {
+                        let to = self.temp(0);
+                        self.asm.mov(index, to);
+                        index = to;
+                    }
+                    let val = self.expr(val, 1)?;
+                    // the store's last operand is 7 cells on
+                    let operand = self.asm.here() + 7;
+                    self.asm.emit(Op::Add, &[index, Arg::DataAddr(base), Arg::Abs(operand)]);
+                    self.asm.mov(val, Arg::Abs(0));
+                },
+                ExprKind::Var(name) => {
+                    let to = self.var(name, target.line)?;
+                    let val = self.expr(val, 0)?;
+                    self.asm.mov(val, to);
+                },
+                _ => unreachable!("the parser only allows variables and elements"),
+            },
+            Stmt::If { cond, then, els } => {
+                let (otherwise, end) = (self.asm.label(), self.asm.label());
+                let cond = self.expr(cond, 0)?;
+                self.asm.emit(Op::JumpIfFalse, &[cond, Arg::Label(otherwise)]);
+                self.stmts(then)?;
+                if !els.is_empty() {
+                    self.asm.jump(Arg::Label(end));
+                }
+                self.asm.bind(otherwise);
+                self.stmts(els)?;
+                self.asm.bind(end);
+            },
+            Stmt::While { cond, body } => {
+                let (top, end) = (self.asm.label(), self.asm.label());
+                self.asm.bind(top);
+                let cond = self.expr(cond, 0)?;
+                self.asm.emit(Op::JumpIfFalse, &[cond, Arg::Label(end)]);
+                self.loops.push((top, end));
+                self.stmts(body)?;
+                self.loops.pop();
+                self.asm.jump(Arg::Label(top));
+                self.asm.bind(end);
+            },
+            Stmt::Break(line) | Stmt::Continue(line) => {
+                let (top, end) = match self.loops.last() {
+                    Some(labels) => *labels,
+                    None => return error(*line, "not inside a loop".to_string()),
+                };
+                let to = match stmt {
+                    Stmt::Break(_) => end,
+                    _ => top,
+                };
+                self.asm.jump(Arg::Label(to));
+            },
+            Stmt::Return(val) => {
+                let val = match val {
+                    Some(val) => self.expr(val, 0)?,
+                    None => Arg::Imm(0),
+                };
+                self.asm.mov(val, Arg::Data(self.result));
+                self.asm.jump(Arg::Rel(0));
+            },
+            Stmt::Output(val) => {
+                let val = self.expr(val, 0)?;
+                self.asm.emit(Op::Write, &[val]);
+            },
+            Stmt::Expr(expr) => {
+                self.expr(expr, 0)?;
+            },
+        }
+        Ok(())
+    }
+
+    // Compiles the body twice: once to count scratch slots and locals, and again, over the
+    // top of the first attempt, with the frame laid out properly.
+    fn function(&mut self, function: &Function) -> Result<(), CompileError> {
+        let label = self.functions[&function.name].label;
+        self.asm.bind(label);
+        let mark = (self.asm.words.len(), self.asm.fixups.len(), self.asm.labels.len());
+        let params = function.params.len() as i64;
+        let (mut scratch, mut locals) = (0, 0);
+        for pass in 0..2 {
+            self.locals.clear();
+            for (i, param) in function.params.iter().enumerate() {
+                if self.locals.insert(param.clone(), 1 + i as i64).is_some() {
+                    return error(function.line, format!("`{}` is declared twice", param));
+                }
+            }
+            self.scratch = 1 + params;
+            self.next_local = 1 + params + scratch;
+            self.frame = 1 + params + scratch + locals;
+            self.depth = 0;
+            self.stmts(&function.body)?;
+            self.stmt(&Stmt::Return(None))?;
+            if pass == 0 {
+                scratch = self.depth;
+                locals = self.next_local - (1 + params);
+                self.asm.words.truncate(mark.0);
+                self.asm.fixups.truncate(mark.1);
+                self.asm.labels.truncate(mark.2);
+            }
+        }
+        Ok(())
+    }
+}
+
+pub fn compile(source: &str) -> Result<Vec<i64>, CompileError> {
+    let tokens = lex(source)?;
+    let mut parser = Parser { tokens, pos: 0 };
+    let (globals, functions) = parser.program()?;
+    let end = parser.line();
+
+    let mut gen = Gen::default();
+    gen.result = gen.data.len();
+    gen.data.push(0);
+    for global in globals.iter() {
+        let cell = gen.data.len();
+        let symbol = match global.len {
+            Some(_) => Symbol::Array(cell),
+            None => Symbol::Scalar(cell),
+        };
+        if gen.globals.insert(global.name.clone(), symbol).is_some() {
+            return error(global.line, format!("`{}` is already declared", global.name));
+        }
+        let mut cells = global.init.clone();
+        cells.resize(global.len.unwrap_or(1), 0);
+        gen.data.extend(cells);
+    }
+    for function in functions.iter() {
+        let label = gen.asm.label();
+        let sig = Signature { label, params: function.params.len() };
+        if gen.functions.insert(function.name.clone(), sig).is_some() || gen.globals.contains_key(&function.name) {
+            return error(function.line, format!("`{}` is already declared", function.name));
+        }
+    }
+    let main = match functions.iter().find(|function| function.name == "main") {
+        Some(main) => main,
+        None => return error(end, "there's no `main` function".to_string()),
+    };
+    if !main.params.is_empty() {
+        return error(main.line, "`main` can't take arguments".to_string());
+    }
+
+    // set up the stack, call main, and halt when it returns
+    let (main, halt) = (gen.functions["main"].label, gen.asm.label());
+    gen.asm.emit(Op::UpdateRb, &[Arg::Stack]);
+    gen.asm.mov(Arg::Label(halt), Arg::Rel(0));
+    gen.asm.jump(Arg::Label(main));
+    gen.asm.bind(halt);
+    gen.asm.emit(Op::Halt, &[]);
+    for function in functions.iter() {
+        gen.function(function)?;
+    }
+    let data = std::mem::take(&mut gen.data);
+    Ok(gen.asm.link(&data))
+}
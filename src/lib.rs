@@ -1,24 +1,122 @@
 pub mod procedural_comp;
 pub mod polymorphic_comp;
+pub mod disasm;
+pub mod cfg;
+pub mod coverage;
+pub mod syscall;
+pub mod word;
+pub mod bigint;
+pub mod memory;
+pub mod history;
+pub mod replay;
+pub mod symbolic;
+pub mod sweep;
+pub mod optimize;
+pub mod decompile;
+pub mod aot;
+pub mod lang;
+pub mod instruction;
+pub mod oracle;
+pub mod maze;
+pub mod ascii;
+pub mod adventure;
+pub mod springscript;
+pub mod scaffold;
+pub mod beam;
 
-#[derive(Debug, PartialEq)]
+use std::ops::Range;
+
+pub use word::Word;
+pub use bigint::BigInt;
+pub use instruction::ParamMode;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Fault {
+    // the guest made a host call nobody registered a handler for
+    UnknownService { pc: i64, service: i64 },
+    // a host call handler reported an error
+    Service { pc: i64, service: i64, msg: String },
+    // arithmetic overflowed under `Overflow::Checked`. Only fixed-width words can
+    // overflow, and their operands always fit in an i128.
+    Overflow { pc: i64, op: Arith, a: i128, b: i128 },
+    // a word used as an address, jump target or relative base offset doesn't fit in an i64
+    Address { pc: i64 },
+    // a memory protection rule was broken under `OnViolation::Fault`
+    Protection(memory::Violation),
+    // the program went outside what its `memory::Sandbox` allows
+    Sandbox(memory::Breach),
+    // the program counter landed on something that isn't an instruction
+    Decode { pc: i64, err: instruction::DecodeError },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Arith {
+    Add,
+    Mul,
+}
+
+// What `Add` and `Mul` do when the result doesn't fit in the machine's word. Plain `+` and
+// `*` would panic in debug builds and wrap in release, so machines always go through one of
+// these.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Overflow {
+    #[default]
+    Wrapping,
+    // fault with the program counter and operands
+    Checked,
+    Saturating,
+}
+
+impl Overflow {
+    // `None` only ever comes back under `Overflow::Checked`
+    pub fn apply<W: Word>(self, op: Arith, a: &W, b: &W) -> Option<W> {
+        W::arith(op, self, a, b)
+    }
+
+    // the fault a machine raises when `apply` comes back empty
+    pub(crate) fn fault<W: Word>(pc: i64, op: Arith, a: &W, b: &W) -> Fault {
+        Fault::Overflow {
+            pc,
+            op,
+            a: a.to_i128().expect("only fixed-width words overflow"),
+            b: b.to_i128().expect("only fixed-width words overflow"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub enum State {
     Running,
     Waiting,
     Halted,
+    Faulted(Fault),
 }
 
-#[derive(Debug, Clone, Copy)]
-pub enum ParamMode {
-    Position,
-    Immediate,
-    Relative,
-}
-
-pub trait IntCodeComputer {
+pub trait IntCodeComputer<W: Word = i64> {
     fn run(&mut self) -> State;
-    fn out(&self) -> &Vec<i64>;
-    fn push(&mut self, val: i64);
-    fn mem(&self, at: i64) -> i64;
+    fn out(&self) -> &Vec<W>;
+    fn push(&mut self, val: W);
+    fn mem(&self, at: i64) -> W;
     fn state(&self) -> State;
+    // executes a single instruction
+    fn step(&mut self) -> State;
+    // instructions executed so far, not counting a halt
+    fn cycles(&self) -> u64;
+    // inputs pushed but not read yet
+    fn queued(&self) -> usize;
+    fn pc(&self) -> i64;
+    fn set_pc(&mut self, pc: i64);
+    fn rb(&self) -> i64;
+    fn set_rb(&mut self, rb: i64);
+    // writes straight to memory, skipping any protection or sandbox checks
+    fn poke(&mut self, at: i64, val: W);
+    fn peek(&self, range: Range<i64>) -> Vec<W> {
+        range.map(|at| self.mem(at)).collect()
+    }
+    // the inputs `queued` counts, oldest first
+    fn inputs(&self) -> &[W];
+    // number of cells the program was loaded with
+    fn image_size(&self) -> i64;
+    // every address that's part of the image or has been written since, ascending
+    fn touched(&self) -> Vec<i64>;
 }
\ No newline at end of file
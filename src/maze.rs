@@ -0,0 +1,258 @@
+// Mapping out a grid through a "repair droid" program. The program reads a move (1 north,
+// 2 south, 3 west, 4 east) and answers 0 if a wall was in the way and it stayed put, 1 if it
+// moved, or 2 if it moved and is now on the target.
+//
+// `Maze::explore` walks a single droid depth first, backing up the way it came whenever
+// everything around it is known, and leaves it where it started. `Maze::explore_forking`
+// never moves the droid it's given: it goes breadth first, cloning the droid at each cell to
+// try the next step, so nothing ever has to walk back. Either way every reachable cell ends
+// up on the map, with the droid's starting point at `START`.
+use fxhash::FxHashMap;
+use std::collections::VecDeque;
+use crate::{IntCodeComputer, State};
+
+// x grows eastwards and y southwards, so the map renders top to bottom
+pub type Pos = (i64, i64);
+
+pub const START: Pos = (0, 0);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Dir {
+    North,
+    South,
+    West,
+    East,
+}
+
+impl Dir {
+    pub const ALL: [Dir; 4] = [Dir::North, Dir::South, Dir::West, Dir::East];
+
+    // what the program reads for the move
+    pub fn code(self) -> i64 {
+        match self {
+            Dir::North => 1,
+            Dir::South => 2,
+            Dir::West => 3,
+            Dir::East => 4,
+        }
+    }
+
+    pub fn reverse(self) -> Dir {
+        match self {
+            Dir::North => Dir::South,
+            Dir::South => Dir::North,
+            Dir::West => Dir::East,
+            Dir::East => Dir::West,
+        }
+    }
+
+    pub fn left(self) -> Dir {
+        match self {
+            Dir::North => Dir::West,
+            Dir::West => Dir::South,
+            Dir::South => Dir::East,
+            Dir::East => Dir::North,
+        }
+    }
+
+    pub fn right(self) -> Dir {
+        self.left().reverse()
+    }
+
+    pub fn step(self, (x, y): Pos) -> Pos {
+        match self {
+            Dir::North => (x, y - 1),
+            Dir::South => (x, y + 1),
+            Dir::West => (x - 1, y),
+            Dir::East => (x + 1, y),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Tile {
+    Wall,
+    Open,
+    Target,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ExploreError {
+    // the program stopped instead of waiting for the next move
+    Stopped { pos: Pos, dir: Dir, state: State },
+    // anything but a single 0, 1 or 2 in answer to a move
+    BadReply { pos: Pos, dir: Dir, outputs: Vec<i64> },
+    // backing up into the cell the droid just came from hit a wall
+    Inconsistent { pos: Pos, dir: Dir },
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Maze {
+    tiles: FxHashMap<Pos, Tile>,
+    target: Option<Pos>,
+}
+
+// Makes one move from `pos` and reports what's in that direction. The droid only moves if
+// it isn't a wall.
+fn probe<C: IntCodeComputer>(comp: &mut C, pos: Pos, dir: Dir) -> Result<Tile, ExploreError> {
+    let seen = comp.out().len();
+    comp.push(dir.code());
+    let state = comp.run();
+    if state != State::Waiting {
+        return Err(ExploreError::Stopped { pos, dir, state });
+    }
+    match &comp.out()[seen..] {
+        [0] => Ok(Tile::Wall),
+        [1] => Ok(Tile::Open),
+        [2] => Ok(Tile::Target),
+        outputs => Err(ExploreError::BadReply { pos, dir, outputs: outputs.to_vec() }),
+    }
+}
+
+impl Maze {
+    pub fn explore<C: IntCodeComputer>(comp: &mut C) -> Result<Maze, ExploreError> {
+        let mut maze = Maze::default();
+        maze.tiles.insert(START, Tile::Open);
+        let mut pos = START;
+        // the moves that got the droid to `pos`
+        let mut trail: Vec<Dir> = Vec::new();
+        loop {
+            let unknown = Dir::ALL.iter().copied().find(|dir| maze.tile(dir.step(pos)).is_none());
+            if let Some(dir) = unknown {
+                let tile = probe(comp, pos, dir)?;
+                maze.insert(dir.step(pos), tile);
+                if tile != Tile::Wall {
+                    pos = dir.step(pos);
+                    trail.push(dir);
+                }
+                continue;
+            }
+            let back = match trail.pop() {
+                Some(came) => came.reverse(),
+                None => return Ok(maze),
+            };
+            if probe(comp, pos, back)? == Tile::Wall {
+                return Err(ExploreError::Inconsistent { pos, dir: back });
+            }
+            pos = back.step(pos);
+        }
+    }
+
+    pub fn explore_forking<C: IntCodeComputer + Clone>(comp: &C) -> Result<Maze, ExploreError> {
+        let mut maze = Maze::default();
+        maze.tiles.insert(START, Tile::Open);
+        let mut queue = VecDeque::new();
+        queue.push_back((START, comp.clone()));
+        while let Some((pos, droid)) = queue.pop_front() {
+            for dir in Dir::ALL.iter().copied() {
+                let next = dir.step(pos);
+                if maze.tile(next).is_some() {
+                    continue;
+                }
+                let mut fork = droid.clone();
+                let tile = probe(&mut fork, pos, dir)?;
+                maze.insert(next, tile);
+                if tile != Tile::Wall {
+                    queue.push_back((next, fork));
+                }
+            }
+        }
+        Ok(maze)
+    }
+
+    fn insert(&mut self, pos: Pos, tile: Tile) {
+        if tile == Tile::Target {
+            self.target = Some(pos);
+        }
+        self.tiles.insert(pos, tile);
+    }
+
+    // `None` for cells nothing has looked at
+    pub fn tile(&self, pos: Pos) -> Option<Tile> {
+        self.tiles.get(&pos).copied()
+    }
+
+    pub fn target(&self) -> Option<Pos> {
+        self.target
+    }
+
+    // a shortest route between two cells through ones known to be open
+    pub fn path(&self, from: Pos, to: Pos) -> Option<Vec<Dir>> {
+        let mut came: FxHashMap<Pos, Dir> = FxHashMap::default();
+        let mut queue = VecDeque::new();
+        queue.push_back(from);
+        while let Some(pos) = queue.pop_front() {
+            if pos == to {
+                let mut route = Vec::new();
+                let mut at = to;
+                while at != from {
+                    let dir = came[&at];
+                    route.push(dir);
+                    at = dir.reverse().step(at);
+                }
+                route.reverse();
+                return Some(route);
+            }
+            for dir in Dir::ALL.iter().copied() {
+                let next = dir.step(pos);
+                if self.passable(next) && next != from && !came.contains_key(&next) {
+                    came.insert(next, dir);
+                    queue.push_back(next);
+                }
+            }
+        }
+        None
+    }
+
+    pub fn path_to_target(&self) -> Option<Vec<Dir>> {
+        self.path(START, self.target?)
+    }
+
+    // flood fill: the number of moves from `from` to every open cell it can reach
+    pub fn distances(&self, from: Pos) -> FxHashMap<Pos, usize> {
+        let mut dist = FxHashMap::default();
+        if !self.passable(from) {
+            return dist;
+        }
+        dist.insert(from, 0);
+        let mut queue = VecDeque::new();
+        queue.push_back(from);
+        while let Some(pos) = queue.pop_front() {
+            let d = dist[&pos];
+            for dir in Dir::ALL.iter().copied() {
+                let next = dir.step(pos);
+                if self.passable(next) && !dist.contains_key(&next) {
+                    dist.insert(next, d + 1);
+                    queue.push_back(next);
+                }
+            }
+        }
+        dist
+    }
+
+    fn passable(&self, pos: Pos) -> bool {
+        matches!(self.tile(pos), Some(Tile::Open) | Some(Tile::Target))
+    }
+
+    // `#` walls, `.` open, `S` the start, `O` the target and blanks for anything unknown
+    pub fn render(&self) -> String {
+        let xs = self.tiles.keys().map(|(x, _)| *x);
+        let ys = self.tiles.keys().map(|(_, y)| *y);
+        let (min_x, max_x) = (xs.clone().min().unwrap_or(0), xs.max().unwrap_or(0));
+        let (min_y, max_y) = (ys.clone().min().unwrap_or(0), ys.max().unwrap_or(0));
+        let mut out = String::new();
+        for y in min_y..=max_y {
+            for x in min_x..=max_x {
+                out.push(match (self.tile((x, y)), (x, y) == START) {
+                    (Some(Tile::Target), _) => 'O',
+                    (_, true) => 'S',
+                    (Some(Tile::Wall), _) => '#',
+                    (Some(Tile::Open), _) => '.',
+                    (None, _) => ' ',
+                });
+            }
+            out.push('\n');
+        }
+        out
+    }
+}
@@ -0,0 +1,393 @@
+use fxhash::{FxHashMap, FxHashSet};
+use std::cell::RefCell;
+use std::ops::Range;
+use std::sync::Arc;
+use crate::{Fault, Word};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Perms {
+    pub read: bool,
+    pub write: bool,
+    pub exec: bool,
+}
+
+impl Perms {
+    pub const RWX: Perms = Perms { read: true, write: true, exec: true };
+    pub const RX: Perms = Perms { read: true, write: false, exec: true };
+    pub const RW: Perms = Perms { read: true, write: true, exec: false };
+    pub const R: Perms = Perms { read: true, write: false, exec: false };
+    pub const NONE: Perms = Perms { read: false, write: false, exec: false };
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Violation {
+    // data read from a region without read permission
+    Read { pc: i64, addr: i64 },
+    // data write to a region without write permission
+    Write { pc: i64, addr: i64 },
+    // instruction fetched from a region without execute permission
+    Exec { pc: i64 },
+    // a write landed on a cell that has already been executed as part of an instruction
+    SelfModify { pc: i64, addr: i64 },
+    // an instruction ran from a cell the program itself wrote
+    ExecData { pc: i64 },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OnViolation {
+    Fault,
+    Log,
+}
+
+// Optional permission regions plus self-modification tracking. Cells outside every region
+// are readable, writable and executable, so an empty `Protection` only watches for code
+// that rewrites itself or runs what it wrote.
+#[derive(Debug, Clone)]
+pub struct Protection {
+    regions: Vec<(Range<i64>, Perms)>,
+    on_violation: OnViolation,
+    executed: FxHashSet<i64>,
+    written: FxHashSet<i64>,
+    // reads happen through `&self`, so the log needs interior mutability
+    log: RefCell<Vec<Violation>>,
+    // what's been added to the tracking since the memory's journal was opened
+    journal: Option<Marks>,
+}
+
+// Cells newly marked executed or written while a journal was open, and how long the log was
+// when it opened, so stepping back over an instruction can forget what it did.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Marks {
+    executed: Vec<i64>,
+    written: Vec<i64>,
+    logged: usize,
+}
+
+impl Protection {
+    pub fn new(on_violation: OnViolation) -> Protection {
+        Protection {
+            regions: Vec::new(),
+            on_violation,
+            executed: FxHashSet::default(),
+            written: FxHashSet::default(),
+            log: RefCell::new(Vec::new()),
+            journal: None,
+        }
+    }
+
+    // later regions take precedence where they overlap earlier ones
+    pub fn region(mut self, range: Range<i64>, perms: Perms) -> Protection {
+        self.regions.push((range, perms));
+        self
+    }
+
+    pub fn perms(&self, addr: i64) -> Perms {
+        self.regions.iter()
+            .rev()
+            .find(|(range, _)| range.contains(&addr))
+            .map(|(_, perms)| *perms)
+            .unwrap_or(Perms::RWX)
+    }
+
+    pub fn violations(&self) -> Vec<Violation> {
+        self.log.borrow().clone()
+    }
+
+    // forgets what ran, what was written and what was logged, keeping the regions
+    fn reset(&mut self) {
+        self.executed.clear();
+        self.written.clear();
+        self.log.borrow_mut().clear();
+        self.journal = None;
+    }
+
+    fn unmark(&mut self, marks: Marks) {
+        for addr in marks.executed {
+            self.executed.remove(&addr);
+        }
+        for addr in marks.written {
+            self.written.remove(&addr);
+        }
+        self.log.borrow_mut().truncate(marks.logged);
+    }
+
+    fn violation(&self, violation: Violation) -> Result<(), Fault> {
+        match self.on_violation {
+            OnViolation::Fault => Err(Fault::Protection(violation)),
+            OnViolation::Log => {
+                self.log.borrow_mut().push(violation);
+                Ok(())
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Negative {
+    Fault,
+    Allow,
+}
+
+// Limits for running untrusted programs. The quotas count every cell the program has
+// touched, including its own image; reading an untouched cell is free, since it doesn't
+// allocate anything.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Sandbox {
+    pub negative: Negative,
+    pub max_cells: Option<usize>,
+    pub max_bytes: Option<usize>,
+}
+
+impl Default for Sandbox {
+    fn default() -> Sandbox {
+        Sandbox {
+            negative: Negative::Fault,
+            max_cells: None,
+            max_bytes: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Breach {
+    NegativeAddress { pc: i64, addr: i64 },
+    CellQuota { pc: i64, addr: i64, limit: usize },
+    ByteQuota { pc: i64, addr: i64, limit: usize },
+}
+
+// Backing store shared by both machines. `get`/`set` are raw accesses for the host; the
+// machines go through `load`, `store` and `exec` so protection gets a say.
+//
+// The image is an immutable base that any number of machines can share, and everything
+// written since lives in an overlay on top of it. Cloning (for a keyframe, say) only copies
+// the overlay, and `reset` just drops it.
+#[derive(Debug, Clone)]
+pub(crate) struct Memory<W> {
+    base: Arc<Vec<W>>,
+    // every cell written since the image was loaded, even if it holds the base value again
+    cells: FxHashMap<i64, W>,
+    protection: Option<Protection>,
+    sandbox: Option<Sandbox>,
+    // cells that have held a value, counting the whole image
+    len: usize,
+    // running total of the heap bytes owned by the words in those cells
+    heap_bytes: usize,
+    // `heap_bytes` for the base alone, so a reset doesn't have to walk it again
+    base_heap_bytes: usize,
+    // previous overlay contents of every cell written while a journal is open
+    journal: Option<Vec<(i64, Option<W>)>>,
+}
+
+impl<W: Word> Memory<W> {
+    pub(crate) fn shared(base: Arc<Vec<W>>) -> Memory<W> {
+        let heap_bytes = base.iter().map(Word::heap_bytes).sum();
+        Memory {
+            len: base.len(),
+            base,
+            cells: FxHashMap::default(),
+            protection: None,
+            sandbox: None,
+            heap_bytes,
+            base_heap_bytes: heap_bytes,
+            journal: None,
+        }
+    }
+
+    pub(crate) fn base(&self) -> &Arc<Vec<W>> {
+        &self.base
+    }
+
+    // back to the image as loaded, in time proportional to the cells written since
+    pub(crate) fn reset(&mut self) {
+        self.cells.clear();
+        self.len = self.base.len();
+        self.heap_bytes = self.base_heap_bytes;
+        self.journal = None;
+        if let Some(protection) = self.protection.as_mut() {
+            protection.reset();
+        }
+    }
+
+    pub(crate) fn image_size(&self) -> i64 {
+        self.base.len() as i64
+    }
+
+    // every cell that's held a value, ascending
+    pub(crate) fn touched(&self) -> Vec<i64> {
+        let image = self.base.len() as i64;
+        let mut addrs: Vec<i64> = (0..image)
+            .chain(self.cells.keys().copied().filter(|addr| !(0..image).contains(addr)))
+            .collect();
+        addrs.sort_unstable();
+        addrs
+    }
+
+    pub(crate) fn bytes(&self) -> usize {
+        self.len * std::mem::size_of::<(i64, W)>() + self.heap_bytes
+    }
+
+    // what the cell holds, or `None` if it's never held anything
+    fn cell(&self, addr: i64) -> Option<&W> {
+        match (self.cells.get(&addr), addr >= 0) {
+            (Some(val), _) => Some(val),
+            (None, true) => self.base.get(addr as usize),
+            (None, false) => None,
+        }
+    }
+
+    pub(crate) fn get(&self, addr: i64) -> W {
+        self.cell(addr).cloned().unwrap_or_default()
+    }
+
+    pub(crate) fn set(&mut self, addr: i64, val: W) {
+        self.heap_bytes += val.heap_bytes();
+        match self.cell(addr).map(Word::heap_bytes) {
+            Some(bytes) => self.heap_bytes -= bytes,
+            None => self.len += 1,
+        }
+        let old = self.cells.insert(addr, val);
+        if let Some(journal) = self.journal.as_mut() {
+            journal.push((addr, old));
+        }
+    }
+
+    // Puts back a cell's previous overlay contents from a journal, `None` meaning it hadn't
+    // been written and holds whatever the image has there.
+    pub(crate) fn restore(&mut self, addr: i64, old: Option<W>) {
+        let before = self.cell(addr).map(Word::heap_bytes);
+        match old {
+            Some(val) => self.cells.insert(addr, val),
+            None => self.cells.remove(&addr),
+        };
+        let after = self.cell(addr).map(Word::heap_bytes);
+        self.heap_bytes = self.heap_bytes + after.unwrap_or(0) - before.unwrap_or(0);
+        match (before, after) {
+            (None, Some(_)) => self.len += 1,
+            (Some(_), None) => self.len -= 1,
+            _ => (),
+        }
+    }
+
+    // what the image had at `addr`, whatever's been written there since
+    pub(crate) fn original(&self, addr: i64) -> W {
+        match addr >= 0 {
+            true => self.base.get(addr as usize).cloned().unwrap_or_default(),
+            false => W::default(),
+        }
+    }
+
+    pub(crate) fn begin_journal(&mut self) {
+        self.journal = Some(Vec::new());
+        if let Some(protection) = self.protection.as_mut() {
+            let logged = protection.log.borrow().len();
+            protection.journal = Some(Marks { logged, ..Marks::default() });
+        }
+    }
+
+    // the writes since `begin_journal`, plus whatever protection started tracking meanwhile
+    pub(crate) fn end_journal(&mut self) -> (Vec<(i64, Option<W>)>, Marks) {
+        let marks = self.protection.as_mut().and_then(|protection| protection.journal.take());
+        (self.journal.take().unwrap_or_default(), marks.unwrap_or_default())
+    }
+
+    // forgets the executed and written cells and the violations in `marks`
+    pub(crate) fn unmark(&mut self, marks: Marks) {
+        if let Some(protection) = self.protection.as_mut() {
+            protection.unmark(marks);
+        }
+    }
+
+    pub(crate) fn sandbox(&mut self, sandbox: Sandbox) {
+        self.sandbox = Some(sandbox);
+    }
+
+    fn check_negative(&self, pc: i64, addr: i64) -> Result<(), Fault> {
+        match self.sandbox {
+            Some(Sandbox { negative: Negative::Fault, .. }) if addr < 0 => {
+                Err(Fault::Sandbox(Breach::NegativeAddress { pc, addr }))
+            },
+            _ => Ok(()),
+        }
+    }
+
+    fn check_quota(&self, pc: i64, addr: i64, val: &W) -> Result<(), Fault> {
+        let sandbox = match &self.sandbox {
+            Some(sandbox) => sandbox,
+            None => return Ok(()),
+        };
+        let (cells, mut bytes) = match self.cell(addr) {
+            Some(old) => (self.len, self.bytes() - old.heap_bytes()),
+            None => (self.len + 1, self.bytes() + std::mem::size_of::<(i64, W)>()),
+        };
+        bytes += val.heap_bytes();
+        match (sandbox.max_cells, sandbox.max_bytes) {
+            (Some(limit), _) if cells > limit => {
+                Err(Fault::Sandbox(Breach::CellQuota { pc, addr, limit }))
+            },
+            (_, Some(limit)) if bytes > limit => {
+                Err(Fault::Sandbox(Breach::ByteQuota { pc, addr, limit }))
+            },
+            _ => Ok(()),
+        }
+    }
+
+    pub(crate) fn protect(&mut self, protection: Protection) {
+        self.protection = Some(protection);
+    }
+
+    pub(crate) fn protection(&self) -> Option<&Protection> {
+        self.protection.as_ref()
+    }
+
+    pub(crate) fn load(&self, pc: i64, addr: i64) -> Result<W, Fault> {
+        self.check_negative(pc, addr)?;
+        if let Some(protection) = &self.protection {
+            if !protection.perms(addr).read {
+                protection.violation(Violation::Read { pc, addr })?;
+            }
+        }
+        Ok(self.get(addr))
+    }
+
+    pub(crate) fn store(&mut self, pc: i64, addr: i64, val: W) -> Result<(), Fault> {
+        self.check_negative(pc, addr)?;
+        self.check_quota(pc, addr, &val)?;
+        if let Some(protection) = &mut self.protection {
+            if !protection.perms(addr).write {
+                protection.violation(Violation::Write { pc, addr })?;
+            }
+            if protection.executed.contains(&addr) {
+                protection.violation(Violation::SelfModify { pc, addr })?;
+            }
+            if protection.written.insert(addr) {
+                if let Some(marks) = protection.journal.as_mut() {
+                    marks.written.push(addr);
+                }
+            }
+        }
+        self.set(addr, val);
+        Ok(())
+    }
+
+    // called before the instruction at `pc`, occupying `size` cells, runs
+    pub(crate) fn exec(&mut self, pc: i64, size: i64) -> Result<(), Fault> {
+        self.check_negative(pc, pc)?;
+        if let Some(protection) = &mut self.protection {
+            let cells = pc..pc + size;
+            if cells.clone().any(|addr| !protection.perms(addr).exec) {
+                protection.violation(Violation::Exec { pc })?;
+            }
+            if cells.clone().any(|addr| protection.written.contains(&addr)) {
+                protection.violation(Violation::ExecData { pc })?;
+            }
+            for addr in cells {
+                if protection.executed.insert(addr) {
+                    if let Some(marks) = protection.journal.as_mut() {
+                        marks.executed.push(addr);
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
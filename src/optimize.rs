@@ -0,0 +1,212 @@
+// Peephole optimizer. Every rewrite is done in place and keeps the instruction's size, so no
+// address in the image moves and jump targets stored as data stay valid:
+//
+//   - `add`/`mul`/`lt`/`eq` with two immediate inputs (or a multiply by an immediate zero)
+//     become `add K, 0, out`, which saves the loads
+//   - `jt`/`jf` with an immediate condition become `jt 1, T` when always taken, and a jump
+//     straight to the next instruction when never taken
+//   - jumps to an unconditional jump (or a never-taken one) are retargeted to where the chain
+//     ends up
+//
+// Only statically reachable instructions are rewritten, and never one whose cells some
+// instruction reads or writes through a position-mode operand, since that code is data as
+// far as the program is concerned. Relative-mode accesses can't be resolved statically;
+// they're assumed to stay clear of the code, which is what `check` is for.
+use fxhash::FxHashSet;
+use crate::{IntCodeComputer, State};
+use crate::cfg::Cfg;
+use crate::disasm::Line;
+use crate::instruction::{self, Instruction, Op, Operand, ParamMode};
+use crate::procedural_comp::ProcIntCode;
+
+// sample runs that go on longer than this count as a mismatch
+const MAX_STEPS: usize = 10_000_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Report {
+    // arithmetic and comparisons folded to constants
+    pub folded: usize,
+    // conditional jumps with a constant condition made unconditional
+    pub jumps: usize,
+    // jump targets moved to the end of a chain
+    pub threaded: usize,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Mismatch {
+    // index into the samples
+    pub sample: usize,
+    pub what: String,
+}
+
+fn imm(val: i64) -> Operand {
+    Operand { mode: ParamMode::Immediate, val }
+}
+
+// `Some(true)` for a jump that's always taken to an immediate target, `Some(false)` for one
+// that's never taken, `None` for anything else
+fn constant_jump(instr: &Instruction) -> Option<bool> {
+    let taken = match (instr.op, &instr.operands[0]) {
+        (Op::JumpIfTrue, Operand { mode: ParamMode::Immediate, val }) => *val != 0,
+        (Op::JumpIfFalse, Operand { mode: ParamMode::Immediate, val }) => *val == 0,
+        _ => return None,
+    };
+    match (taken, instr.operands[1].mode) {
+        (true, ParamMode::Immediate) => Some(true),
+        (true, _) => None,
+        (false, _) => Some(false),
+    }
+}
+
+struct Optimizer<'a> {
+    image: &'a [i64],
+    // reachable instruction starts that are safe to rely on and rewrite
+    code: FxHashSet<i64>,
+}
+
+impl<'a> Optimizer<'a> {
+    fn new(image: &'a [i64]) -> Optimizer<'a> {
+        let cfg = Cfg::build(image);
+        let mut data = FxHashSet::default();
+        for instr in cfg.lines(image).iter().filter_map(Line::instr) {
+            for operand in instr.operands() {
+                if operand.mode == ParamMode::Position {
+                    data.insert(operand.val);
+                }
+            }
+        }
+        let code = cfg.blocks()
+            .flat_map(|block| block.lines.iter())
+            .filter(|line| (line.addr()..line.addr() + line.size()).all(|addr| !data.contains(&addr)))
+            .map(Line::addr)
+            .collect();
+        Optimizer { image, code }
+    }
+
+    // the instruction at `addr`, if it's code we can rely on
+    fn instr(&self, addr: i64) -> Option<Instruction> {
+        match self.code.contains(&addr) {
+            true => instruction::decode(self.image, addr).ok(),
+            false => None,
+        }
+    }
+
+    // where execution ends up after landing on `addr` and following unconditional jumps
+    fn thread(&self, mut addr: i64) -> i64 {
+        let mut seen = FxHashSet::default();
+        while seen.insert(addr) {
+            let instr = match self.instr(addr) {
+                Some(instr) => instr,
+                None => break,
+            };
+            addr = match constant_jump(&instr) {
+                Some(true) => instr.operands[1].val,
+                Some(false) => addr + instr.size(),
+                None => break,
+            };
+        }
+        addr
+    }
+
+    fn run(&self) -> (Vec<i64>, Report) {
+        let mut out = self.image.to_vec();
+        let mut report = Report::default();
+        let mut addrs: Vec<i64> = self.code.iter().copied().collect();
+        addrs.sort_unstable();
+        for addr in addrs {
+            let instr = match self.instr(addr) {
+                Some(instr) => instr,
+                None => continue,
+            };
+            let at = addr as usize;
+            let [a, b, to] = &instr.operands;
+            match instr.op {
+                Op::Add | Op::Mul | Op::LessThan | Op::Equals => {
+                    use ParamMode::Immediate;
+                    let val = match (instr.op, a.mode, b.mode) {
+                        (Op::Add, Immediate, Immediate) => a.val.wrapping_add(b.val),
+                        (Op::Mul, Immediate, Immediate) => a.val.wrapping_mul(b.val),
+                        (Op::LessThan, Immediate, Immediate) => (a.val < b.val) as i64,
+                        (Op::Equals, Immediate, Immediate) => (a.val == b.val) as i64,
+                        (Op::Mul, Immediate, _) if a.val == 0 => 0,
+                        (Op::Mul, _, Immediate) if b.val == 0 => 0,
+                        _ => continue,
+                    };
+                    let folded = Instruction::new(Op::Add, &[imm(val), imm(0), to.clone()]).encode();
+                    if out[at..at + 4] != folded[..] {
+                        out[at..at + 4].copy_from_slice(&folded);
+                        report.folded += 1;
+                    }
+                },
+                Op::JumpIfTrue | Op::JumpIfFalse => {
+                    let target = match constant_jump(&instr) {
+                        Some(true) => b.val,
+                        Some(false) => addr + instr.size(),
+                        None if b.mode == ParamMode::Immediate => b.val,
+                        None => continue,
+                    };
+                    let threaded = self.thread(target);
+                    let jump = match constant_jump(&instr) {
+                        Some(_) => Instruction::new(Op::JumpIfTrue, &[imm(1), imm(threaded)]),
+                        None => Instruction::new(instr.op, &[a.clone(), imm(threaded)]),
+                    };
+                    let jump = jump.encode();
+                    if jump[..2] != out[at..at + 2] {
+                        report.jumps += 1;
+                    }
+                    if threaded != target {
+                        report.threaded += 1;
+                    }
+                    out[at..at + 3].copy_from_slice(&jump);
+                },
+                _ => (),
+            }
+        }
+        (out, report)
+    }
+}
+
+pub fn optimize(image: &[i64]) -> (Vec<i64>, Report) {
+    Optimizer::new(image).run()
+}
+
+// Runs both images on every sample input with `ProcIntCode` and compares how they stop, what
+// they output, and every cell of the original image the optimizer left alone.
+pub fn check(original: &[i64], optimized: &[i64], samples: &[Vec<i64>]) -> Result<(), Mismatch> {
+    let rewritten: Vec<bool> = original.iter().zip(optimized.iter()).map(|(a, b)| a != b).collect();
+    for (sample, inputs) in samples.iter().enumerate() {
+        let mismatch = |what: String| Mismatch { sample, what };
+        let mut before = ProcIntCode::new(original.to_vec(), inputs.clone());
+        let mut after = ProcIntCode::new(optimized.to_vec(), inputs.clone());
+        let (a, b) = (settle(&mut before), settle(&mut after));
+        if a != b {
+            return Err(mismatch(format!("stopped {:?}, optimized stopped {:?}", a, b)));
+        }
+        if before.out() != after.out() {
+            return Err(mismatch(format!("output {:?}, optimized output {:?}", before.out(), after.out())));
+        }
+        let cells = (0..original.len()).filter(|addr| !rewritten[*addr]);
+        if let Some(addr) = cells.map(|addr| addr as i64).find(|addr| before.mem(*addr) != after.mem(*addr)) {
+            return Err(mismatch(format!("memory differs at {}", addr)));
+        }
+    }
+    Ok(())
+}
+
+// `optimize`, then `check` the result against the samples
+pub fn optimize_checked(image: &[i64], samples: &[Vec<i64>]) -> Result<(Vec<i64>, Report), Mismatch> {
+    let (optimized, report) = optimize(image);
+    check(image, &optimized, samples)?;
+    Ok((optimized, report))
+}
+
+// runs until the machine stops, or `None` if it never does
+fn settle(comp: &mut ProcIntCode) -> Option<State> {
+    for _ in 0..MAX_STEPS {
+        match comp.step() {
+            State::Running => continue,
+            state => return Some(state),
+        }
+    }
+    None
+}
@@ -0,0 +1,120 @@
+// A program that only maps its inputs to its outputs, wrapped up as a plain function. Each
+// call borrows a machine from a pool (or starts one on the shared image), feeds it the
+// inputs, runs it to a halt, and resets it for the next caller, so repeated calls cost
+// about as much as the program's run and nothing more.
+//
+// Calls can come from any number of threads. The pool and the cache are only locked to take
+// or put something back; programs always run unlocked. Two threads asking the same new
+// question at once will both run it.
+use fxhash::FxHashMap;
+use std::sync::{Arc, Mutex};
+use crate::procedural_comp::ProcIntCode;
+use crate::{Fault, IntCodeComputer, State, Word};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum CallError<W> {
+    // the program read more inputs than it was given; `outputs` is what it wrote first
+    Starved { outputs: Vec<W> },
+    Faulted(Fault),
+    // still running after the oracle's cycle limit
+    CycleLimit,
+}
+
+type Answer<W> = Result<Vec<W>, CallError<W>>;
+
+#[derive(Debug)]
+pub struct Oracle<W: Word = i64> {
+    image: Arc<Vec<W>>,
+    // idle machines, all reset to the image
+    pool: Mutex<Vec<ProcIntCode<W>>>,
+    // never evicts, so only worth turning on when the set of questions is bounded
+    cache: Option<Mutex<FxHashMap<Vec<W>, Answer<W>>>>,
+    max_cycles: Option<u64>,
+}
+
+impl<W: Word> Oracle<W> {
+    pub fn new(image: Vec<W>) -> Oracle<W> {
+        Oracle::shared(Arc::new(image))
+    }
+
+    pub fn shared(image: Arc<Vec<W>>) -> Oracle<W> {
+        Oracle { image, pool: Mutex::new(Vec::new()), cache: None, max_cycles: None }
+    }
+
+    // remember every answer, errors included, since the program is assumed to be pure
+    pub fn with_cache(mut self) -> Oracle<W> {
+        self.cache = Some(Mutex::new(FxHashMap::default()));
+        self
+    }
+
+    // give up on a call after this many instructions
+    pub fn with_max_cycles(mut self, max_cycles: u64) -> Oracle<W> {
+        self.max_cycles = Some(max_cycles);
+        self
+    }
+
+    pub fn call(&self, inputs: &[W]) -> Result<Vec<W>, CallError<W>> {
+        if let Some(cache) = &self.cache {
+            if let Some(answer) = cache.lock().unwrap().get(inputs) {
+                return answer.clone();
+            }
+        }
+        let answer = self.evaluate(inputs);
+        if let Some(cache) = &self.cache {
+            cache.lock().unwrap().insert(inputs.to_vec(), answer.clone());
+        }
+        answer
+    }
+
+    // `call` as a closure, for code that wants a plain function
+    pub fn as_fn(&self) -> impl Fn(&[W]) -> Result<Vec<W>, CallError<W>> + Sync + '_ {
+        move |inputs| self.call(inputs)
+    }
+
+    pub fn into_fn(self) -> impl Fn(&[W]) -> Result<Vec<W>, CallError<W>> + Send + Sync {
+        move |inputs| self.call(inputs)
+    }
+
+    // answers in the cache, zero without one
+    pub fn cached(&self) -> usize {
+        self.cache.as_ref().map(|cache| cache.lock().unwrap().len()).unwrap_or(0)
+    }
+
+    // machines sitting idle; between calls, that's the most that ever ran at once
+    pub fn pooled(&self) -> usize {
+        self.pool.lock().unwrap().len()
+    }
+
+    fn evaluate(&self, inputs: &[W]) -> Answer<W> {
+        let idle = self.pool.lock().unwrap().pop();
+        let mut comp = idle.unwrap_or_else(|| ProcIntCode::shared(self.image.clone(), Vec::new()));
+        for val in inputs.iter() {
+            comp.push(val.clone());
+        }
+        let answer = match self.run(&mut comp) {
+            State::Halted => Ok(comp.out().clone()),
+            State::Waiting => Err(CallError::Starved { outputs: comp.out().clone() }),
+            State::Faulted(fault) => Err(CallError::Faulted(fault)),
+            State::Running => Err(CallError::CycleLimit),
+        };
+        comp.reset();
+        self.pool.lock().unwrap().push(comp);
+        answer
+    }
+
+    // `State::Running` means the cycle limit ran out
+    fn run(&self, comp: &mut ProcIntCode<W>) -> State {
+        let max_cycles = match self.max_cycles {
+            Some(max_cycles) => max_cycles,
+            None => return comp.run(),
+        };
+        while comp.cycles() < max_cycles {
+            match comp.step() {
+                State::Running => continue,
+                state => return state,
+            }
+        }
+        // a halt doesn't count as a cycle, so one right at the limit still gets through
+        comp.state()
+    }
+}
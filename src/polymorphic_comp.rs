@@ -1,197 +1,340 @@
-use fxhash::FxHashMap;
+use fxhash::{FxHashMap, FxHashSet};
 use crate::*;
-use std::fmt::Debug;
-
-pub(crate) enum Value {
-    Literal(i64),
+use std::fmt::{self, Debug};
+use std::sync::Arc;
+use crate::syscall::{self, Frame, Syscalls, SYSCALL};
+use crate::memory::{Memory, Protection, Sandbox, Violation};
+use crate::instruction::{self, DecodeError, Op, Operand};
+
+pub enum Value<W> {
+    Literal(W),
     Pointer(i64)
 }
 
-pub(crate) trait Arg: Debug {
-    fn get(&self, rb: i64) -> Value;
-    fn out_addr(&self, rb: i64) -> Box<dyn Arg>;
+pub trait Arg<W: Word = i64>: Debug {
+    // `None` if the operand can't be turned into an address that fits in an i64
+    fn get(&self, rb: i64) -> Option<Value<W>>;
+    fn out_addr(&self, rb: i64) -> Box<dyn Arg<W>>;
 }
 
-mod param_mode {
+pub mod param_mode {
     use super::*;
 
-    pub(crate) fn new(modes: i64, val: i64, pos: u32) -> Box<dyn Arg> {
-        // get the digit in position `pos` (zero-indexed)
-        // i.e. mask(12345, 4) -> `5`
-        let mask = (modes / 10i64.pow(pos)) % 10;
-        match mask {
-            0 => Box::new(Position { val }),
-            1 => Box::new(Immediate { val }),
-            2 => Box::new(Relative { val }),
-            _ => panic!("Unsupported Parameter Mode")
+    // `DecodeError::BadModes` with the opcode word it was given if the mode digit for
+    // parameter `pos` isn't a mode, for the decoder to pass on with `?` so the machine faults
+    // instead of panicking
+    pub fn new<W: Word>(word: i64, val: W, pos: u32) -> Result<Box<dyn Arg<W>>, DecodeError> {
+        // get the mode digit for parameter `pos` (zero-indexed), past the two opcode digits
+        // i.e. mask(12345, 2) -> `1`
+        let mask = (word / 10i64.pow(pos + 2)) % 10;
+        match ParamMode::from_digit(mask) {
+            Some(mode) => Ok(from_operand(Operand { mode, val })),
+            None => Err(DecodeError::BadModes(word)),
+        }
+    }
+
+    pub fn from_operand<W: Word>(operand: Operand<W>) -> Box<dyn Arg<W>> {
+        let val = operand.val;
+        match operand.mode {
+            ParamMode::Position => Box::new(Position { val }),
+            ParamMode::Immediate => Box::new(Immediate { val }),
+            ParamMode::Relative => Box::new(Relative { val }),
         }
     }
 
     #[derive(Clone, Debug)]
-    struct Immediate {
-        val: i64,
+    struct Immediate<W> {
+        val: W,
     }
 
-    impl Arg for Immediate {
-        fn get(&self, _rb: i64) -> Value {
-            Value::Literal(self.val)
+    impl<W: Word> Arg<W> for Immediate<W> {
+        fn get(&self, _rb: i64) -> Option<Value<W>> {
+            Some(Value::Literal(self.val.clone()))
         }
 
-        fn out_addr(&self, _rb: i64) -> Box<dyn Arg> {
+        fn out_addr(&self, _rb: i64) -> Box<dyn Arg<W>> {
             Box::new(self.clone())
         }
     }
 
     #[derive(Clone, Debug)]
-    struct Position {
-        val: i64
+    struct Position<W> {
+        val: W
     }
 
-    impl Arg for Position {
-        fn get(&self, _rb: i64) -> Value {
-            Value::Pointer(self.val)
+    impl<W: Word> Arg<W> for Position<W> {
+        fn get(&self, _rb: i64) -> Option<Value<W>> {
+            self.val.to_i64().map(Value::Pointer)
         }
 
-        fn out_addr(&self, _rb: i64) -> Box<dyn Arg> {
-            Box::new(Immediate { val: self.val })
+        fn out_addr(&self, _rb: i64) -> Box<dyn Arg<W>> {
+            Box::new(Immediate { val: self.val.clone() })
         }
     }
 
     #[derive(Clone, Debug)]
-    struct Relative {
-        val: i64
+    struct Relative<W> {
+        val: W
     }
 
-    impl Arg for Relative {
-        fn get(&self, rb: i64) -> Value {
-            Value::Pointer(self.val + rb)
+    impl<W: Word> Arg<W> for Relative<W> {
+        fn get(&self, rb: i64) -> Option<Value<W>> {
+            self.val.to_i64()?.checked_add(rb).map(Value::Pointer)
         }
 
-        fn out_addr(&self, rb: i64) -> Box<dyn Arg> {
-            Box::new(Immediate { val: self.val + rb })
+        fn out_addr(&self, rb: i64) -> Box<dyn Arg<W>> {
+            Box::new(Resolved {
+                addr: self.val.to_i64().and_then(|val| val.checked_add(rb)),
+            })
+        }
+    }
+
+    // a relative output address with the relative base already applied
+    #[derive(Clone, Debug)]
+    struct Resolved {
+        addr: Option<i64>
+    }
+
+    impl<W: Word> Arg<W> for Resolved {
+        fn get(&self, _rb: i64) -> Option<Value<W>> {
+            self.addr.map(|addr| Value::Literal(W::from(addr)))
+        }
+
+        fn out_addr(&self, _rb: i64) -> Box<dyn Arg<W>> {
+            Box::new(self.clone())
         }
     }
 }
 
 #[derive(Debug)]
-pub(crate) enum Action {
-    Set { val: i64, addr: i64, },
+pub enum Action<W = i64> {
+    Set { val: W, addr: i64, },
     SetRb { val: i64, },
     Read { to: i64, },
-    Write {  val: i64 },
+    Write {  val: W },
     Jump { to: i64, },
+    // hand the frame at the relative base to a host call handler
+    Syscall,
+    // leave the machine alone apart from advancing the program counter
+    Nop,
     Halt
 }
 
-pub(crate) trait OpCode: Debug {
+pub trait OpCode<W: Word = i64>: Debug {
     // we *could* pass in a mutable copy of the whole computer, but that would mean exposing
     // a *lot* of internal state. Something about that just smells wrong... you shouldn't
     // be able to get access to the _whole_ system just by implementing this trait.
     // So instead, we need to compromise and let the trait implementor *read* the whole system,
     // but pass back an instruction on how to modify it rather than doing so directly.
-    fn execute(&self, comp: &PolyIntCode) -> Action;
+    // An `Err` stops the machine without advancing.
+    fn execute(&self, comp: &PolyIntCode<W>) -> Result<Action<W>, Fault>;
 
     // since we can't directly modify the program counter, we need to have a separate function
     // telling the computer how far to advance.
     fn advance(&self) -> i64 {
         4
     }
+
+    // how many cells the instruction occupies, which only differs from `advance` for
+    // instructions that move the program counter themselves
+    fn size(&self) -> i64 {
+        self.advance()
+    }
+}
+
+// Turns the four words at the program counter (opcode first) plus the current relative
+// base into an executable instruction. An error faults the machine with `Fault::Decode`.
+pub type Decoder<W = i64> = Arc<dyn Fn([W; 4], i64) -> Result<Box<dyn OpCode<W>>, DecodeError> + Send + Sync>;
+
+// The opcode numbers a PolyIntCode understands. Everything starts from the standard set,
+// and downstream code can add new opcodes (or replace existing ones) with `register`.
+#[derive(Clone)]
+pub struct InstructionSet<W: Word = i64> {
+    decoders: FxHashMap<i64, Decoder<W>>,
+    // opcodes still decoded by `opcode::new`, i.e. not replaced with `register`
+    standard: FxHashSet<i64>,
+}
+
+impl<W: Word> InstructionSet<W> {
+    pub fn empty() -> InstructionSet<W> {
+        InstructionSet { decoders: FxHashMap::default(), standard: FxHashSet::default() }
+    }
+
+    pub fn standard() -> InstructionSet<W> {
+        let mut set = InstructionSet::empty();
+        for op in [1, 2, 3, 4, 5, 6, 7, 8, 9, SYSCALL, 99].iter() {
+            set.decoders.insert(*op, Arc::new(opcode::new));
+            set.standard.insert(*op);
+        }
+        set
+    }
+
+    pub fn register<F>(&mut self, opcode: i64, decoder: F) -> &mut InstructionSet<W>
+    where F: Fn([W; 4], i64) -> Result<Box<dyn OpCode<W>>, DecodeError> + Send + Sync + 'static {
+        assert!((0..100).contains(&opcode), "opcodes occupy the lowest two digits");
+        self.decoders.insert(opcode, Arc::new(decoder));
+        self.standard.remove(&opcode);
+        self
+    }
+
+    pub fn contains(&self, opcode: i64) -> bool {
+        self.decoders.contains_key(&opcode)
+    }
+
+    fn decode(&self, data: [W; 4], rb: i64) -> Box<dyn OpCode<W>> {
+        let opcode = match data[0].to_i64().filter(|word| *word >= 0) {
+            Some(word) => word % 100,
+            None => return opcode::invalid(DecodeError::BadWord),
+        };
+        match self.decoders.get(&opcode) {
+            Some(decoder) => decoder(data, rb).unwrap_or_else(opcode::invalid),
+            None => opcode::invalid(DecodeError::UnknownOpcode(opcode)),
+        }
+    }
+
+    // which standard op `decode` would build, without executing anything; `None` for an
+    // opcode that's been registered by hand, since only running it says what it does
+    fn op(&self, data: [W; 4], rb: i64) -> Result<Option<Op>, DecodeError> {
+        let opcode = data[0].to_i64().filter(|word| *word >= 0).ok_or(DecodeError::BadWord)? % 100;
+        match self.decoders.get(&opcode) {
+            Some(_) if self.standard.contains(&opcode) => Ok(Some(instruction::decode(&data[..], 0)?.op)),
+            Some(decoder) => decoder(data, rb).map(|_| None),
+            None => Err(DecodeError::UnknownOpcode(opcode)),
+        }
+    }
+}
+
+impl<W: Word> Default for InstructionSet<W> {
+    fn default() -> InstructionSet<W> {
+        InstructionSet::standard()
+    }
+}
+
+impl<W: Word> Debug for InstructionSet<W> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut ops: Vec<&i64> = self.decoders.keys().collect();
+        ops.sort();
+        f.debug_struct("InstructionSet").field("opcodes", &ops).finish()
+    }
 }
 
 mod opcode {
     use super::*;
 
-    pub(crate) fn new(data: [i64; 4], rb: i64) -> Box<dyn OpCode> {
-        let opcode = data[0] % 100;
-        let modes = data[0] / 100;
-        let v0 = param_mode::new(modes, data[1], 0);
-        let v1 = param_mode::new(modes, data[2], 1);
-        let v2 = param_mode::new(modes, data[3], 2);
-        match opcode {
-            1 => Box::new(Add {
+    // the decoder behind every opcode in `InstructionSet::standard`
+    pub(crate) fn new<W: Word>(data: [W; 4], rb: i64) -> Result<Box<dyn OpCode<W>>, DecodeError> {
+        let instr = instruction::decode(&data[..], 0)?;
+        let [v0, v1, v2] = instr.operands;
+        let (v0, v1, v2) = (
+            param_mode::from_operand(v0),
+            param_mode::from_operand(v1),
+            param_mode::from_operand(v2),
+        );
+        Ok(match instr.op {
+            Op::Add => Box::new(Add {
                 a: v0,
                 b: v1,
                 out: v2.out_addr(rb),
             }),
-            2 => Box::new(Mul {
+            Op::Mul => Box::new(Mul {
                 a: v0,
                 b: v1,
                 out: v2.out_addr(rb),
             }),
-            3 => Box::new(Read {
+            Op::Read => Box::new(Read {
                 to: v0.out_addr(rb),
             }),
-            4 => Box::new(Write {
+            Op::Write => Box::new(Write {
                 val: v0,
             }),
-            5 => Box::new(JumpIfTrue {
+            Op::JumpIfTrue => Box::new(JumpIfTrue {
                 cond: v0,
                 to: v1,
             }),
-            6 => Box::new(JumpIfFalse {
+            Op::JumpIfFalse => Box::new(JumpIfFalse {
                 cond: v0,
                 to: v1,
             }),
-            7 => Box::new(LessThan {
+            Op::LessThan => Box::new(LessThan {
                 a: v0,
                 b: v1,
                 out: v2.out_addr(rb),
             }),
-            8 => Box::new(Equals {
+            Op::Equals => Box::new(Equals {
                 a: v0,
                 b: v1,
                 out: v2.out_addr(rb),
             }),
-            9 => Box::new(UpdateRb {
+            Op::UpdateRb => Box::new(UpdateRb {
                 to_add: v0,
             }),
-            99 => Box::new(Halt {}),
-            _ => panic!("Unsupported OpCode")
-        }
+            Op::Syscall => Box::new(Syscall {}),
+            Op::Halt => Box::new(Halt {}),
+        })
     }
 
+    pub(crate) fn invalid<W: Word>(err: DecodeError) -> Box<dyn OpCode<W>> {
+        Box::new(Invalid { err })
+    }
+
+    // faults as soon as it's executed, without touching the machine
     #[derive(Debug)]
-    struct Add {
-        a: Box<dyn Arg>,
-        b: Box<dyn Arg>,
-        out: Box<dyn Arg>,
+    struct Invalid {
+        err: DecodeError,
     }
 
-    impl OpCode for Add {
-        fn execute(&self, comp: &PolyIntCode) -> Action {
-            Action::Set {
-                val: comp.fetch(&self.a) + comp.fetch(&self.b),
-                addr: comp.fetch(&self.out)
-            }
+    impl<W: Word> OpCode<W> for Invalid {
+        fn execute(&self, comp: &PolyIntCode<W>) -> Result<Action<W>, Fault> {
+            Err(Fault::Decode { pc: comp.pc, err: self.err })
+        }
+
+        fn size(&self) -> i64 {
+            1
         }
     }
 
     #[derive(Debug)]
-    struct Mul {
-        a: Box<dyn Arg>,
-        b: Box<dyn Arg>,
-        out: Box<dyn Arg>,
+    struct Add<W: Word> {
+        a: Box<dyn Arg<W>>,
+        b: Box<dyn Arg<W>>,
+        out: Box<dyn Arg<W>>,
+    }
+
+    impl<W: Word> OpCode<W> for Add<W> {
+        fn execute(&self, comp: &PolyIntCode<W>) -> Result<Action<W>, Fault> {
+            Ok(Action::Set {
+                val: comp.arith(Arith::Add, self.a.as_ref(), self.b.as_ref())?,
+                addr: comp.addr(self.out.as_ref())?,
+            })
+        }
     }
 
-    impl OpCode for Mul {
-        fn execute(&self, comp: &PolyIntCode) -> Action {
-            Action::Set {
-                val: comp.fetch(&self.a) * comp.fetch(&self.b),
-                addr: comp.fetch(&self.out)
-            }
+    #[derive(Debug)]
+    struct Mul<W: Word> {
+        a: Box<dyn Arg<W>>,
+        b: Box<dyn Arg<W>>,
+        out: Box<dyn Arg<W>>,
+    }
+
+    impl<W: Word> OpCode<W> for Mul<W> {
+        fn execute(&self, comp: &PolyIntCode<W>) -> Result<Action<W>, Fault> {
+            Ok(Action::Set {
+                val: comp.arith(Arith::Mul, self.a.as_ref(), self.b.as_ref())?,
+                addr: comp.addr(self.out.as_ref())?,
+            })
         }
     }
 
     #[derive(Debug)]
-    struct Read {
-        to: Box<dyn Arg>,
+    struct Read<W: Word> {
+        to: Box<dyn Arg<W>>,
     }
 
-    impl OpCode for Read {
-        fn execute(&self, comp: &PolyIntCode) -> Action {
-            Action::Read {
-                to: comp.fetch(&self.to),
-            }
+    impl<W: Word> OpCode<W> for Read<W> {
+        fn execute(&self, comp: &PolyIntCode<W>) -> Result<Action<W>, Fault> {
+            Ok(Action::Read {
+                to: comp.addr(self.to.as_ref())?,
+            })
         }
 
         fn advance(&self) -> i64 {
@@ -200,15 +343,15 @@ mod opcode {
     }
 
     #[derive(Debug)]
-    struct Write {
-        val: Box<dyn Arg>,
+    struct Write<W: Word> {
+        val: Box<dyn Arg<W>>,
     }
 
-    impl OpCode for Write {
-        fn execute(&self, comp: &PolyIntCode) -> Action {
-            Action::Write {
-                val: comp.fetch(&self.val),
-            }
+    impl<W: Word> OpCode<W> for Write<W> {
+        fn execute(&self, comp: &PolyIntCode<W>) -> Result<Action<W>, Fault> {
+            Ok(Action::Write {
+                val: comp.fetch(self.val.as_ref())?,
+            })
         }
 
         fn advance(&self) -> i64 {
@@ -217,95 +360,98 @@ mod opcode {
     }
 
     #[derive(Debug)]
-    struct JumpIfTrue {
-        cond: Box<dyn Arg>,
-        to: Box<dyn Arg>
+    struct JumpIfTrue<W: Word> {
+        cond: Box<dyn Arg<W>>,
+        to: Box<dyn Arg<W>>
     }
 
-    impl OpCode for JumpIfTrue {
-        fn execute(&self, comp: &PolyIntCode) -> Action {
-            Action::Jump {
-                to: match comp.fetch(&self.cond) {
-                    0 => comp.pc + 3,
-                    _ => comp.fetch(&self.to)
+    impl<W: Word> OpCode<W> for JumpIfTrue<W> {
+        fn execute(&self, comp: &PolyIntCode<W>) -> Result<Action<W>, Fault> {
+            Ok(Action::Jump {
+                to: match comp.fetch(self.cond.as_ref())?.is_zero() {
+                    true => comp.pc + 3,
+                    false => comp.addr(self.to.as_ref())?
                 },
-            }
+            })
         }
 
         fn advance(&self) -> i64 {
             0
         }
+
+        fn size(&self) -> i64 {
+            3
+        }
     }
 
     #[derive(Debug)]
-    struct JumpIfFalse {
-        cond: Box<dyn Arg>,
-        to: Box<dyn Arg>
+    struct JumpIfFalse<W: Word> {
+        cond: Box<dyn Arg<W>>,
+        to: Box<dyn Arg<W>>
     }
 
-    impl OpCode for JumpIfFalse {
-        fn execute(&self, comp: &PolyIntCode) -> Action {
-            Action::Jump {
-                to: match comp.fetch(&self.cond) {
-                    0 => comp.fetch(&self.to),
-                    _ => comp.pc + 3
+    impl<W: Word> OpCode<W> for JumpIfFalse<W> {
+        fn execute(&self, comp: &PolyIntCode<W>) -> Result<Action<W>, Fault> {
+            Ok(Action::Jump {
+                to: match comp.fetch(self.cond.as_ref())?.is_zero() {
+                    true => comp.addr(self.to.as_ref())?,
+                    false => comp.pc + 3
                 },
-            }
+            })
         }
 
         fn advance(&self) -> i64 {
             0
         }
+
+        fn size(&self) -> i64 {
+            3
+        }
     }
 
     #[derive(Debug)]
-    struct LessThan {
-        a: Box<dyn Arg>,
-        b: Box<dyn Arg>,
-        out: Box<dyn Arg>,
-    }
-
-    impl OpCode for LessThan {
-        fn execute(&self, comp: &PolyIntCode) -> Action {
-            Action::Set {
-                val: match comp.fetch(&self.a) < comp.fetch(&self.b) {
-                    true => 1,
-                    false => 0,
-                },
-                addr: comp.fetch(&self.out),
-            }
+    struct LessThan<W: Word> {
+        a: Box<dyn Arg<W>>,
+        b: Box<dyn Arg<W>>,
+        out: Box<dyn Arg<W>>,
+    }
+
+    impl<W: Word> OpCode<W> for LessThan<W> {
+        fn execute(&self, comp: &PolyIntCode<W>) -> Result<Action<W>, Fault> {
+            Ok(Action::Set {
+                val: W::bool(comp.fetch(self.a.as_ref())? < comp.fetch(self.b.as_ref())?),
+                addr: comp.addr(self.out.as_ref())?,
+            })
         }
     }
 
     #[derive(Debug)]
-    struct Equals {
-        a: Box<dyn Arg>,
-        b: Box<dyn Arg>,
-        out: Box<dyn Arg>,
-    }
-
-    impl OpCode for Equals {
-        fn execute(&self, comp: &PolyIntCode) -> Action {
-            Action::Set {
-                val: match comp.fetch(&self.a) == comp.fetch(&self.b) {
-                    true => 1,
-                    false => 0,
-                },
-                addr: comp.fetch(&self.out),
-            }
+    struct Equals<W: Word> {
+        a: Box<dyn Arg<W>>,
+        b: Box<dyn Arg<W>>,
+        out: Box<dyn Arg<W>>,
+    }
+
+    impl<W: Word> OpCode<W> for Equals<W> {
+        fn execute(&self, comp: &PolyIntCode<W>) -> Result<Action<W>, Fault> {
+            Ok(Action::Set {
+                val: W::bool(comp.fetch(self.a.as_ref())? == comp.fetch(self.b.as_ref())?),
+                addr: comp.addr(self.out.as_ref())?,
+            })
         }
     }
 
     #[derive(Debug)]
-    struct UpdateRb {
-        to_add: Box<dyn Arg>
+    struct UpdateRb<W: Word> {
+        to_add: Box<dyn Arg<W>>
     }
 
-    impl OpCode for UpdateRb {
-        fn execute(&self, comp: &PolyIntCode) -> Action {
-            Action::SetRb {
-                val: comp.rb + comp.fetch(&self.to_add),
-            }
+    impl<W: Word> OpCode<W> for UpdateRb<W> {
+        fn execute(&self, comp: &PolyIntCode<W>) -> Result<Action<W>, Fault> {
+            Ok(Action::SetRb {
+                val: comp.rb.checked_add(comp.addr(self.to_add.as_ref())?)
+                    .ok_or(Fault::Address { pc: comp.pc })?,
+            })
         }
 
         fn advance(&self) -> i64 {
@@ -313,81 +459,185 @@ mod opcode {
         }
     }
 
+    #[derive(Debug)]
+    struct Syscall {}
+
+    impl<W: Word> OpCode<W> for Syscall {
+        fn execute(&self, _comp: &PolyIntCode<W>) -> Result<Action<W>, Fault> {
+            Ok(Action::Syscall)
+        }
+
+        fn advance(&self) -> i64 {
+            1
+        }
+    }
+
     #[derive(Debug)]
     struct Halt {}
 
-    impl OpCode for Halt {
-        fn execute(&self, _comp: &PolyIntCode) -> Action {
-            Action::Halt
+    impl<W: Word> OpCode<W> for Halt {
+        fn execute(&self, _comp: &PolyIntCode<W>) -> Result<Action<W>, Fault> {
+            Ok(Action::Halt)
         }
 
         fn advance(&self) -> i64 {
             0
         }
+
+        fn size(&self) -> i64 {
+            1
+        }
     }
 }
 
-#[derive(Debug)]
-pub struct PolyIntCode {
-    mem: FxHashMap<i64,i64>,
+#[derive(Debug, Clone)]
+pub struct PolyIntCode<W: Word = i64> {
+    mem: Memory<W>,
     pc: i64,
     rb: i64,
-    inputs: Vec<i64>,
-    outputs: Vec<i64>,
+    inputs: Vec<W>,
+    outputs: Vec<W>,
+    instructions: Arc<InstructionSet<W>>,
+    syscalls: Syscalls<W>,
+    overflow: Overflow,
+    fault: Option<Fault>,
+    cycles: u64,
 }
 
-impl PolyIntCode {
-    pub fn new(image: Vec<i64>, inputs: Vec<i64>) -> PolyIntCode {
-        let mut mem: FxHashMap<i64,i64> = FxHashMap::default();
-        for (k,v) in image.iter().enumerate() {
-            mem.insert(k as i64, *v);
-        }
+impl<W: Word> PolyIntCode<W> {
+    pub fn new(image: Vec<W>, inputs: Vec<W>) -> PolyIntCode<W> {
+        PolyIntCode::shared(Arc::new(image), inputs)
+    }
+
+    // see `ProcIntCode::shared`
+    pub fn shared(image: Arc<Vec<W>>, inputs: Vec<W>) -> PolyIntCode<W> {
         PolyIntCode {
-            mem,
+            mem: Memory::shared(image),
             pc: 0,
             rb: 0,
             inputs,
             outputs: Vec::new(),
+            instructions: Arc::new(InstructionSet::standard()),
+            syscalls: Syscalls::default(),
+            overflow: Overflow::default(),
+            fault: None,
+            cycles: 0,
         }
     }
 
-    fn fetch(&self, arg: &Box<dyn Arg>) -> i64 {
+    pub fn image(&self) -> Arc<Vec<W>> {
+        self.mem.base().clone()
+    }
+
+    // back to how `shared` left it, keeping the instruction set and handlers
+    pub fn reset(&mut self) {
+        self.mem.reset();
+        self.pc = 0;
+        self.rb = 0;
+        self.inputs.clear();
+        self.outputs.clear();
+        self.fault = None;
+        self.cycles = 0;
+    }
+
+    pub fn with_overflow(mut self, overflow: Overflow) -> PolyIntCode<W> {
+        self.overflow = overflow;
+        self
+    }
+
+    pub fn overflow(&self) -> Overflow {
+        self.overflow
+    }
+
+    // applies the machine's overflow policy, faulting at the current program counter
+    pub fn arith(&self, op: Arith, a: &dyn Arg<W>, b: &dyn Arg<W>) -> Result<W, Fault> {
+        let (a, b) = (self.fetch(a)?, self.fetch(b)?);
+        self.overflow.apply(op, &a, &b).ok_or_else(|| Overflow::fault(self.pc, op, &a, &b))
+    }
+
+    pub fn with_protection(mut self, protection: Protection) -> PolyIntCode<W> {
+        self.mem.protect(protection);
+        self
+    }
+
+    pub fn with_sandbox(mut self, sandbox: Sandbox) -> PolyIntCode<W> {
+        self.mem.sandbox(sandbox);
+        self
+    }
+
+    // everything logged under `OnViolation::Log` so far
+    pub fn violations(&self) -> Vec<Violation> {
+        self.mem.protection().map(Protection::violations).unwrap_or_default()
+    }
+
+    pub fn register_syscall<F>(&mut self, service: i64, handler: F)
+    where F: Fn(&mut Frame<W>) -> Result<W, String> + Send + Sync + 'static {
+        self.syscalls.register(service, handler);
+    }
+
+    pub fn with_instructions(mut self, instructions: InstructionSet<W>) -> PolyIntCode<W> {
+        self.instructions = Arc::new(instructions);
+        self
+    }
+
+    pub fn instructions(&self) -> &InstructionSet<W> {
+        &self.instructions
+    }
+
+    pub fn register<F>(&mut self, opcode: i64, decoder: F)
+    where F: Fn([W; 4], i64) -> Result<Box<dyn OpCode<W>>, DecodeError> + Send + Sync + 'static {
+        Arc::make_mut(&mut self.instructions).register(opcode, decoder);
+    }
+
+    pub fn fetch(&self, arg: &dyn Arg<W>) -> Result<W, Fault> {
         match arg.get(self.rb) {
-            Value::Literal(literal) => literal,
-            Value::Pointer(address) => self.mem(address),
+            Some(Value::Literal(literal)) => Ok(literal),
+            Some(Value::Pointer(address)) => self.mem.load(self.pc, address),
+            None => Err(Fault::Address { pc: self.pc }),
         }
     }
 
-    fn set(&mut self, addr: i64, val: i64) {
-        self.mem.insert(addr, val);
+    // fetches a word that's going to be used as an address (or jump target), so it has to
+    // fit in an i64
+    pub fn addr(&self, arg: &dyn Arg<W>) -> Result<i64, Fault> {
+        self.fetch(arg)?.to_i64().ok_or(Fault::Address { pc: self.pc })
+    }
+
+    fn set(&mut self, addr: i64, val: W) -> Result<(), Fault> {
+        self.mem.store(self.pc, addr, val)
     }
 
-    fn decode(&self) -> Box<dyn OpCode> {
-        let data = [
+    fn data(&self) -> [W; 4] {
+        [
             self.mem(self.pc),
             self.mem(self.pc + 1),
             self.mem(self.pc + 2),
             self.mem(self.pc + 3),
-        ];
-        opcode::new(data, self.rb)
+        ]
+    }
+
+    fn decode(&self) -> Box<dyn OpCode<W>> {
+        self.instructions.decode(self.data(), self.rb)
     }
 
-    fn execute(&mut self, op: Box<dyn OpCode>) -> State {
-        let action = op.execute(self);
+    // on a fault the program counter isn't advanced, so it still points at the culprit
+    fn execute(&mut self, op: Box<dyn OpCode<W>>) -> Result<State, Fault> {
+        self.mem.exec(self.pc, op.size())?;
+        let action = op.execute(self)?;
         match action {
             Action::Set {val, addr} => {
-                self.set(addr, val);
+                self.set(addr, val)?;
             },
             Action::SetRb {val} => {
                 self.rb = val;
             },
             Action::Read {to} => {
-                if self.inputs.get(0) == None {
+                if self.inputs.is_empty() {
                     // don't advance, instruction needs to be replayed
-                    return State::Waiting;
+                    return Ok(State::Waiting);
                 }
-                let data = self.inputs.remove(0);
-                self.set(to, data);
+                self.set(to, self.inputs[0].clone())?;
+                self.inputs.remove(0);
             },
             Action::Write {val} => {
                 self.outputs.push(val);
@@ -395,20 +645,26 @@ impl PolyIntCode {
             Action::Jump {to} => {
                 self.pc = to;
             },
+            Action::Syscall => {
+                syscall::dispatch(&self.syscalls, &mut self.mem, self.pc, self.rb)?;
+            },
+            Action::Nop => {},
             Action::Halt => {
-                return State::Halted;
+                return Ok(State::Halted);
             }
         };
         self.pc += op.advance();
-        State::Running
+        Ok(State::Running)
     }
 }
 
-impl IntCodeComputer for PolyIntCode {
+impl<W: Word> IntCodeComputer<W> for PolyIntCode<W> {
     fn run(&mut self) -> State {
+        if let Some(fault) = &self.fault {
+            return State::Faulted(fault.clone());
+        }
         loop {
-            let opcode = self.decode();
-            let state = self.execute(opcode);
+            let state = self.step();
             if let State::Running = state {
                 continue;
             } else {
@@ -417,28 +673,87 @@ impl IntCodeComputer for PolyIntCode {
         }
     }
 
-    fn out(&self) -> &Vec<i64> {
+    fn out(&self) -> &Vec<W> {
         &self.outputs
     }
 
-    fn push(&mut self, val: i64) {
+    fn step(&mut self) -> State {
+        if let Some(fault) = &self.fault {
+            return State::Faulted(fault.clone());
+        }
+        let opcode = self.decode();
+        let state = self.execute(opcode).unwrap_or_else(State::Faulted);
+        match &state {
+            State::Running => self.cycles += 1,
+            State::Faulted(fault) => self.fault = Some(fault.clone()),
+            _ => (),
+        }
+        state
+    }
+
+    fn cycles(&self) -> u64 {
+        self.cycles
+    }
+
+    fn queued(&self) -> usize {
+        self.inputs.len()
+    }
+
+    fn push(&mut self, val: W) {
         self.inputs.push(val)
     }
 
-    fn mem(&self, at: i64) -> i64 {
-        *self.mem.get(&at).unwrap_or(&0)
+    fn mem(&self, at: i64) -> W {
+        self.mem.get(at)
+    }
+
+    fn pc(&self) -> i64 {
+        self.pc
+    }
+
+    fn set_pc(&mut self, pc: i64) {
+        self.pc = pc;
+    }
+
+    fn rb(&self) -> i64 {
+        self.rb
+    }
+
+    fn set_rb(&mut self, rb: i64) {
+        self.rb = rb;
+    }
+
+    fn poke(&mut self, at: i64, val: W) {
+        self.mem.set(at, val);
+    }
+
+    fn inputs(&self) -> &[W] {
+        &self.inputs
+    }
+
+    fn image_size(&self) -> i64 {
+        self.mem.image_size()
+    }
+
+    fn touched(&self) -> Vec<i64> {
+        self.mem.touched()
     }
 
     fn state(&self) -> State {
-        match self.decode().execute(self) {
-            Action::Halt => State::Halted,
-            Action::Read {to: _} => {
+        if let Some(fault) = &self.fault {
+            return State::Faulted(fault.clone());
+        }
+        // only decode: executing would load the operands, which protection may log
+        match self.instructions.op(self.data(), self.rb) {
+            Ok(Some(Op::Halt)) => State::Halted,
+            Ok(Some(Op::Read)) => {
                 match self.inputs.len() {
                     0 => State::Waiting,
                     _ => State::Running,
                 }
             }
+            Err(err) => State::Faulted(Fault::Decode { pc: self.pc, err }),
             _ => State::Running,
         }
     }
-}
\ No newline at end of file
+}
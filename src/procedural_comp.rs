@@ -1,209 +1,473 @@
-use fxhash::FxHashMap;
+use fxhash::FxHashSet;
+use std::sync::Arc;
 use crate::*;
+use crate::coverage::Coverage;
+use crate::history::{History, Step};
+use crate::instruction::{self, Instruction, Op, Operand};
+use crate::memory::{Memory, Protection, Sandbox, Violation};
+use crate::syscall::{self, Frame, Syscalls};
 
-#[derive(Debug, Clone, Copy)]
-pub enum Arg {
-    Immediate(i64),
-    Position(i64),
-    Relative(i64)
+#[derive(Debug, Clone)]
+pub enum Arg<W> {
+    Immediate(W),
+    Position(W),
+    Relative(W)
 }
 
-impl Arg {
-    fn val(&self) -> i64 {
-        *match self {
+impl<W: Word> Arg<W> {
+    fn val(&self) -> &W {
+        match self {
             Arg::Immediate(val) => val,
             Arg::Position(val) => val,
             Arg::Relative(val) => val
         }
     }
+}
 
-    fn new(modes: i64, pos: u32, val: i64) -> Arg {
-        // get the digit in position `pos` (zero-indexed)
-        // i.e. mask(12345, 4) -> `5`
-        let mask = (modes / 10i64.pow(pos)) % 10;
-        match mask {
-            0 => Arg::Position(val),
-            1 => Arg::Immediate(val),
-            2 => Arg::Relative(val),
-            _ => panic!("Unsupported Parameter Mode")
+impl<W> From<Operand<W>> for Arg<W> {
+    fn from(operand: Operand<W>) -> Arg<W> {
+        match operand.mode {
+            ParamMode::Position => Arg::Position(operand.val),
+            ParamMode::Immediate => Arg::Immediate(operand.val),
+            ParamMode::Relative => Arg::Relative(operand.val),
         }
     }
 }
 
 #[derive(Debug)]
-enum OpCode {
-    Add { a: Arg, b: Arg, out: Arg },
-    Mul { a: Arg, b: Arg, out: Arg },
-    Read { to: Arg },
-    Write { val: Arg },
+enum OpCode<W> {
+    Add { a: Arg<W>, b: Arg<W>, out: Arg<W> },
+    Mul { a: Arg<W>, b: Arg<W>, out: Arg<W> },
+    Read { to: Arg<W> },
+    Write { val: Arg<W> },
     // jump to jaddr if cond is nonzero
-    JumpIfTrue { cond: Arg, to: Arg },
+    JumpIfTrue { cond: Arg<W>, to: Arg<W> },
     // jump to jaddr if cond is zero
-    JumpIfFalse { cond: Arg, to: Arg },
+    JumpIfFalse { cond: Arg<W>, to: Arg<W> },
     // writes 1 to resloc if arg1 < arg2, else 0
-    LessThan { a: Arg, b: Arg, out: Arg },
+    LessThan { a: Arg<W>, b: Arg<W>, out: Arg<W> },
     // writes 1 to resloc if arg1 == arg2, else 0
-    Equals { a: Arg, b: Arg, out: Arg },
+    Equals { a: Arg<W>, b: Arg<W>, out: Arg<W> },
     // add value to IntCodeComputer.rb
-    UpdateRb { val: Arg },
+    UpdateRb { val: Arg<W> },
+    // host call, see the `syscall` module for the calling convention
+    Syscall,
     Halt,
 }
 
-impl OpCode {
-    fn new(data: [i64; 4]) -> OpCode {
-        let opcode = data[0] % 100;
-        let modes = data[0] / 100;
-        let args: Vec<Arg> = data[1..=3].iter()
-            .enumerate()
-            .map(|(i, val)| Arg::new(modes, i as u32, *val))
-            .collect();
-        match opcode {
-            1 => OpCode::Add { a: args[0], b: args[1], out: args[2] },
-            2 => OpCode::Mul { a: args[0], b: args[1], out: args[2] },
-            3 => OpCode::Read { to: args[0] },
-            4 => OpCode::Write { val: args[0] },
-            5 => OpCode::JumpIfTrue { cond: args[0], to: args[1] },
-            6 => OpCode::JumpIfFalse { cond: args[0], to: args[1] },
-            7 => OpCode::LessThan { a: args[0], b: args[1], out: args[2] },
-            8 => OpCode::Equals { a: args[0], b: args[1], out: args[2] },
-            9 => OpCode::UpdateRb { val: args[0] },
-            99 => OpCode::Halt,
-            _ => panic!("Unsupported OpCode")
+impl<W: Word> OpCode<W> {
+    fn new(instr: Instruction<W>) -> OpCode<W> {
+        let [a0, a1, a2] = instr.operands;
+        let (a0, a1, a2) = (Arg::from(a0), Arg::from(a1), Arg::from(a2));
+        match instr.op {
+            Op::Add => OpCode::Add { a: a0, b: a1, out: a2 },
+            Op::Mul => OpCode::Mul { a: a0, b: a1, out: a2 },
+            Op::Read => OpCode::Read { to: a0 },
+            Op::Write => OpCode::Write { val: a0 },
+            Op::JumpIfTrue => OpCode::JumpIfTrue { cond: a0, to: a1 },
+            Op::JumpIfFalse => OpCode::JumpIfFalse { cond: a0, to: a1 },
+            Op::LessThan => OpCode::LessThan { a: a0, b: a1, out: a2 },
+            Op::Equals => OpCode::Equals { a: a0, b: a1, out: a2 },
+            Op::UpdateRb => OpCode::UpdateRb { val: a0 },
+            Op::Syscall => OpCode::Syscall,
+            Op::Halt => OpCode::Halt,
         }
     }
 }
 
-#[derive(Debug)]
-pub struct ProcIntCode {
-    mem: FxHashMap<i64,i64>,
-    pc: i64,
-    rb: i64,
-    inputs: Vec<i64>,
-    outputs: Vec<i64>,
+#[derive(Debug, Clone)]
+pub struct ProcIntCode<W: Word = i64> {
+    // `aot::Runtime` drives these directly when running compiled code
+    pub(crate) mem: Memory<W>,
+    pub(crate) pc: i64,
+    pub(crate) rb: i64,
+    pub(crate) inputs: Vec<W>,
+    pub(crate) outputs: Vec<W>,
+    coverage: Option<Coverage>,
+    syscalls: Syscalls<W>,
+    overflow: Overflow,
+    pub(crate) fault: Option<Fault>,
+    pub(crate) cycles: u64,
+    history: Option<Box<History<W>>>,
+    breakpoints: FxHashSet<i64>,
 }
 
-impl ProcIntCode {
-    pub fn new(image: Vec<i64>, inputs: Vec<i64>) -> ProcIntCode {
-        let mut mem: FxHashMap<i64,i64> = FxHashMap::default();
-        for (k,v) in image.iter().enumerate() {
-            mem.insert(k as i64, *v);
-        }
+impl<W: Word> ProcIntCode<W> {
+    pub fn new(image: Vec<W>, inputs: Vec<W>) -> ProcIntCode<W> {
+        ProcIntCode::shared(Arc::new(image), inputs)
+    }
+
+    // Runs an image other machines can load too. Nothing is copied up front; each machine
+    // only keeps the cells it has written.
+    pub fn shared(image: Arc<Vec<W>>, inputs: Vec<W>) -> ProcIntCode<W> {
         ProcIntCode {
-            mem,
+            mem: Memory::shared(image),
             pc: 0,
             rb: 0,
             inputs,
             outputs: Vec::new(),
+            coverage: None,
+            syscalls: Syscalls::default(),
+            overflow: Overflow::default(),
+            fault: None,
+            cycles: 0,
+            history: None,
+            breakpoints: FxHashSet::default(),
+        }
+    }
+
+    // the image the machine was loaded with, for starting others on it
+    pub fn image(&self) -> Arc<Vec<W>> {
+        self.mem.base().clone()
+    }
+
+    // Puts the machine back how `shared` left it, with empty input and output queues. Only
+    // the cells written since get thrown away, so this is cheap for short runs. Coverage,
+    // breakpoints and handlers are kept, and recording starts over from here.
+    pub fn reset(&mut self) {
+        self.mem.reset();
+        self.pc = 0;
+        self.rb = 0;
+        self.inputs.clear();
+        self.outputs.clear();
+        self.fault = None;
+        self.cycles = 0;
+        if let Some(history) = self.history.as_ref() {
+            let (interval, keep) = (history.interval, history.keep);
+            self.enable_recording(interval, keep);
+        }
+    }
+
+    pub fn with_overflow(mut self, overflow: Overflow) -> ProcIntCode<W> {
+        self.overflow = overflow;
+        self
+    }
+
+    pub fn with_protection(mut self, protection: Protection) -> ProcIntCode<W> {
+        self.mem.protect(protection);
+        self
+    }
+
+    pub fn with_sandbox(mut self, sandbox: Sandbox) -> ProcIntCode<W> {
+        self.mem.sandbox(sandbox);
+        self
+    }
+
+    // everything logged under `OnViolation::Log` so far
+    pub fn violations(&self) -> Vec<Violation> {
+        self.mem.protection().map(Protection::violations).unwrap_or_default()
+    }
+
+    pub fn register_syscall<F>(&mut self, service: i64, handler: F)
+    where F: Fn(&mut Frame<W>) -> Result<W, String> + Send + Sync + 'static {
+        self.syscalls.register(service, handler);
+    }
+
+    // start recording which instructions execute and which way each conditional jump goes
+    pub fn enable_coverage(&mut self) {
+        if self.coverage.is_none() {
+            self.coverage = Some(Coverage::new());
+        }
+    }
+
+    pub fn coverage(&self) -> Option<&Coverage> {
+        self.coverage.as_ref()
+    }
+
+    pub fn take_coverage(&mut self) -> Option<Coverage> {
+        self.coverage.take()
+    }
+
+    // Start keeping an undo log so the machine can be stepped backwards. A keyframe is taken
+    // every `interval` instructions and only the newest `keep` are held on to, which bounds
+    // how far back `step_back` can go.
+    // Only instructions are recorded: `poke`, `set_pc` and `set_rb` bypass the log, so
+    // stepping back over one of them leaves the host's change in place.
+    pub fn enable_recording(&mut self, interval: u64, keep: usize) {
+        let mut history = History::new(interval, keep);
+        history.keyframe(&self.mem, self.pc, self.rb, self.outputs.len());
+        self.history = Some(Box::new(history));
+    }
+
+    pub fn history(&self) -> Option<&History<W>> {
+        self.history.as_deref()
+    }
+
+    pub fn add_breakpoint(&mut self, pc: i64) {
+        self.breakpoints.insert(pc);
+    }
+
+    pub fn remove_breakpoint(&mut self, pc: i64) {
+        self.breakpoints.remove(&pc);
+    }
+
+    // Undoes the last instruction, clearing any fault. Returns false once the oldest
+    // keyframe is reached or if the machine isn't recording.
+    pub fn step_back(&mut self) -> bool {
+        let mut history = match self.history.take() {
+            Some(history) => history,
+            None => return false,
+        };
+        let stepped = self.undo(&mut history);
+        self.history = Some(history);
+        stepped
+    }
+
+    // Steps backwards until the program counter lands on a breakpoint. Returns false if the
+    // start of the recorded history was reached first.
+    pub fn reverse_continue(&mut self) -> bool {
+        while self.step_back() {
+            if self.breakpoints.contains(&self.pc) {
+                return true;
+            }
+        }
+        false
+    }
+
+    fn tick(&mut self) -> State {
+        let pc = self.pc;
+        let state = self.decode()
+            .and_then(|instr| self.execute(instr))
+            .unwrap_or_else(State::Faulted);
+        if let State::Running = state {
+            self.cycles += 1;
+        }
+        match &state {
+            State::Running | State::Halted => {
+                if let Some(coverage) = self.coverage.as_mut() {
+                    coverage.hit(pc);
+                }
+            },
+            State::Faulted(fault) => self.fault = Some(fault.clone()),
+            State::Waiting => (),
+        }
+        state
+    }
+
+    fn record(&mut self, history: &mut History<W>) -> State {
+        let (pc, rb) = (self.pc, self.rb);
+        let (queued, outputs) = (self.inputs.len(), self.outputs.len());
+        let input = self.inputs.first().cloned();
+        self.mem.begin_journal();
+        let state = self.tick();
+        let (writes, marks) = self.mem.end_journal();
+        match state {
+            State::Running => {
+                let step = Step {
+                    pc,
+                    rb,
+                    writes,
+                    marks,
+                    writers: Vec::new(),
+                    input: input.filter(|_| self.inputs.len() < queued),
+                    output: self.outputs.len() > outputs,
+                };
+                if history.push(step) {
+                    history.keyframe(&self.mem, self.pc, self.rb, self.outputs.len());
+                }
+            },
+            // a faulting instruction is rolled back so the fault is reported against the
+            // state it started from
+            _ => {
+                for (addr, old) in writes.into_iter().rev() {
+                    self.mem.restore(addr, old);
+                }
+                self.mem.unmark(marks);
+            },
+        }
+        state
+    }
+
+    fn undo(&mut self, history: &mut History<W>) -> bool {
+        if history.undo.is_empty() {
+            return self.rewind(history);
+        }
+        let step = match history.pop() {
+            Some(step) => step,
+            None => return false,
+        };
+        for (addr, old) in step.writes.into_iter().rev() {
+            self.mem.restore(addr, old);
+        }
+        self.mem.unmark(step.marks);
+        if let Some(input) = step.input {
+            self.inputs.insert(0, input);
         }
+        if step.output {
+            self.outputs.pop();
+        }
+        self.pc = step.pc;
+        self.rb = step.rb;
+        self.cycles -= 1;
+        self.fault = None;
+        true
     }
 
-    fn set(&mut self, arg: Arg, val: i64) {
+    // We're sitting exactly on the newest keyframe with nothing left to undo. Drop it,
+    // restore the one before, and replay up to one instruction short of where we were.
+    fn rewind(&mut self, history: &mut History<W>) -> bool {
+        if history.keyframes.len() < 2 {
+            return false;
+        }
+        history.keyframes.pop_back();
+        let keyframe = match history.keyframes.back_mut() {
+            Some(keyframe) => keyframe,
+            None => return false,
+        };
+        let fed = std::mem::take(&mut keyframe.fed);
+        self.mem = keyframe.mem.clone();
+        self.pc = keyframe.pc;
+        self.rb = keyframe.rb;
+        self.outputs.truncate(keyframe.outputs);
+        history.writers = keyframe.writers.clone();
+        self.cycles -= history.steps - keyframe.step;
+        history.steps = keyframe.step;
+        self.inputs.splice(0..0, fed);
+        self.fault = None;
+        // replay shouldn't count towards coverage a second time
+        let coverage = self.coverage.take();
+        for _ in 1..history.interval {
+            if self.record(history) != State::Running {
+                break;
+            }
+        }
+        self.coverage = coverage;
+        true
+    }
+
+    fn cover_branch(&mut self, taken: bool) {
+        if let Some(coverage) = self.coverage.as_mut() {
+            coverage.branch(self.pc, taken);
+        }
+    }
+
+    // a word used as an address has to fit in an i64
+    fn to_addr(&self, word: &W, base: i64) -> Result<i64, Fault> {
+        word.to_i64()
+            .and_then(|offset| offset.checked_add(base))
+            .ok_or(Fault::Address { pc: self.pc })
+    }
+
+    fn addr(&self, arg: &Arg<W>) -> Result<i64, Fault> {
         let base = match arg {
             Arg::Relative(_) => self.rb,
             _ => 0,
         };
-        let address = base as i64 + arg.val();
-        self.mem.insert(address, val);
+        self.to_addr(arg.val(), base)
+    }
+
+    fn set(&mut self, arg: &Arg<W>, val: W) -> Result<(), Fault> {
+        let address = self.addr(arg)?;
+        self.mem.store(self.pc, address, val)
     }
 
-    fn fetch(&self, arg: Arg) -> i64 {
+    fn fetch(&self, arg: &Arg<W>) -> Result<W, Fault> {
         match arg {
-            Arg::Immediate(val) => val,
-            Arg::Position(val) => {
-                let address = val;
-                self.mem(address)
-            },
-            Arg::Relative(val) => {
-                let offset = val;
-                let address = self.rb + offset;
-                self.mem(address)
+            Arg::Immediate(val) => Ok(val.clone()),
+            _ => {
+                let address = self.addr(arg)?;
+                self.mem.load(self.pc, address)
             }
         }
     }
 
-    fn decode(&self) -> OpCode {
-        let data = [
-            self.mem(self.pc),
-            self.mem(self.pc + 1),
-            self.mem(self.pc + 2),
-            self.mem(self.pc + 3),
-        ];
-        OpCode::new(data)
+    // jump targets and relative base adjustments have to fit in an i64 too
+    fn fetch_i64(&self, arg: &Arg<W>) -> Result<i64, Fault> {
+        let val = self.fetch(arg)?;
+        self.to_addr(&val, 0)
+    }
+
+    fn decode(&self) -> Result<Instruction<W>, Fault> {
+        instruction::decode(&self.mem, self.pc)
+            .map_err(|err| Fault::Decode { pc: self.pc, err })
     }
 
-    fn execute(&mut self, opcode: OpCode) -> State {
-        match opcode {
+    fn arith(&self, op: Arith, a: &Arg<W>, b: &Arg<W>) -> Result<W, Fault> {
+        let (a, b) = (self.fetch(a)?, self.fetch(b)?);
+        self.overflow.apply(op, &a, &b).ok_or_else(|| Overflow::fault(self.pc, op, &a, &b))
+    }
+
+    fn execute(&mut self, instr: Instruction<W>) -> Result<State, Fault> {
+        self.mem.exec(self.pc, instr.size())?;
+        match OpCode::new(instr) {
             OpCode::Add {a, b, out} => {
-                let val = self.fetch(a) + self.fetch(b);
-                self.set(out, val);
+                let val = self.arith(Arith::Add, &a, &b)?;
+                self.set(&out, val)?;
                 self.pc += 4;
             },
             OpCode::Mul {a, b, out} => {
-                let val = self.fetch(a) * self.fetch(b);
-                self.set(out, val);
+                let val = self.arith(Arith::Mul, &a, &b)?;
+                self.set(&out, val)?;
                 self.pc += 4;
             },
             OpCode::Read {to} => {
-                if self.inputs.get(0) == None {
-                    return State::Waiting;
+                if self.inputs.is_empty() {
+                    return Ok(State::Waiting);
                 }
-                let data = self.inputs.remove(0);
-                self.set(to, data);
+                let address = self.addr(&to)?;
+                let data = self.inputs[0].clone();
+                self.mem.store(self.pc, address, data)?;
+                self.inputs.remove(0);
                 self.pc += 2;
             },
             OpCode::Write { val } => {
-                self.outputs.push(self.fetch(val));
+                let val = self.fetch(&val)?;
+                self.outputs.push(val);
                 self.pc += 2;
             },
             OpCode::JumpIfTrue {cond, to} => {
-                if self.fetch(cond) != 0 {
-                    self.pc = self.fetch(to);
-                } else {
-                    self.pc += 3;
-                }
+                let taken = !self.fetch(&cond)?.is_zero();
+                // a target that faults doesn't count as either outcome
+                let next = match taken {
+                    true => self.fetch_i64(&to)?,
+                    false => self.pc + 3,
+                };
+                self.cover_branch(taken);
+                self.pc = next;
             }
             OpCode::JumpIfFalse {cond, to} => {
-                if self.fetch(cond) == 0 {
-                    self.pc = self.fetch(to);
-                } else {
-                    self.pc += 3;
-                }
+                let taken = self.fetch(&cond)?.is_zero();
+                let next = match taken {
+                    true => self.fetch_i64(&to)?,
+                    false => self.pc + 3,
+                };
+                self.cover_branch(taken);
+                self.pc = next;
             },
             OpCode::LessThan {a, b, out} => {
-                let res = match self.fetch(a) < self.fetch(b) {
-                    true => 1,
-                    false => 0,
-                };
-                self.set(out, res);
+                let res = W::bool(self.fetch(&a)? < self.fetch(&b)?);
+                self.set(&out, res)?;
                 self.pc += 4;
             },
             OpCode::Equals {a, b, out} => {
-                let res = match self.fetch(a) == self.fetch(b) {
-                    true => 1,
-                    false => 0,
-                };
-                self.set(out, res);
+                let res = W::bool(self.fetch(&a)? == self.fetch(&b)?);
+                self.set(&out, res)?;
                 self.pc += 4;
             },
             OpCode::UpdateRb {val} => {
-                let rb = self.rb as i64;
-                let updated_rb = rb + self.fetch(val);
-                self.rb = updated_rb;
+                let offset = self.fetch(&val)?;
+                self.rb = self.to_addr(&offset, self.rb)?;
                 self.pc += 2;
             }
+            OpCode::Syscall => {
+                syscall::dispatch(&self.syscalls, &mut self.mem, self.pc, self.rb)?;
+                self.pc += 1;
+            }
             OpCode::Halt => {
-                return State::Halted
+                return Ok(State::Halted)
             }
         }
-        State::Running
+        Ok(State::Running)
     }
 }
 
-impl IntCodeComputer for ProcIntCode {
+impl<W: Word> IntCodeComputer<W> for ProcIntCode<W> {
     fn run(&mut self) -> State {
+        if let Some(fault) = &self.fault {
+            return State::Faulted(fault.clone());
+        }
         loop {
-            let opcode = self.decode();
-            let state = self.execute(opcode);
+            let state = self.step();
             match state {
                 State::Running => continue,
                 _ => return state,
@@ -216,27 +480,86 @@ impl IntCodeComputer for ProcIntCode {
         }
     }
 
-    fn out(&self) -> &Vec<i64> {
+    fn out(&self) -> &Vec<W> {
         &self.outputs
     }
 
-    fn push(&mut self, val: i64) {
+    // records the instruction if `enable_recording` was called
+    fn step(&mut self) -> State {
+        if let Some(fault) = &self.fault {
+            return State::Faulted(fault.clone());
+        }
+        match self.history.take() {
+            Some(mut history) => {
+                let state = self.record(&mut history);
+                self.history = Some(history);
+                state
+            },
+            None => self.tick(),
+        }
+    }
+
+    fn cycles(&self) -> u64 {
+        self.cycles
+    }
+
+    fn queued(&self) -> usize {
+        self.inputs.len()
+    }
+
+    fn push(&mut self, val: W) {
         self.inputs.push(val)
     }
 
-    fn mem(&self, at: i64) -> i64 {
-        *self.mem.get(&at).unwrap_or(&0)
+    fn mem(&self, at: i64) -> W {
+        self.mem.get(at)
+    }
+
+    fn pc(&self) -> i64 {
+        self.pc
+    }
+
+    fn set_pc(&mut self, pc: i64) {
+        self.pc = pc;
+    }
+
+    fn rb(&self) -> i64 {
+        self.rb
+    }
+
+    fn set_rb(&mut self, rb: i64) {
+        self.rb = rb;
+    }
+
+    fn poke(&mut self, at: i64, val: W) {
+        self.mem.set(at, val);
+    }
+
+    fn inputs(&self) -> &[W] {
+        &self.inputs
+    }
+
+    fn image_size(&self) -> i64 {
+        self.mem.image_size()
+    }
+
+    fn touched(&self) -> Vec<i64> {
+        self.mem.touched()
     }
 
     fn state(&self) -> State {
-        match self.decode() {
-            OpCode::Halt => State::Halted,
-            OpCode::Read {to: _} => {
+        if let Some(fault) = &self.fault {
+            return State::Faulted(fault.clone());
+        }
+        match self.decode().map(|instr| instr.op) {
+            Ok(Op::Halt) => State::Halted,
+            Ok(Op::Read) => {
                 match self.inputs.len() {
                     0 => State::Waiting,
                     _ => State::Running,
                 }
             }
+            Err(fault) => State::Faulted(fault),
             _ => State::Running,
         }
     }
@@ -0,0 +1,299 @@
+// Deterministic record and replay for interactive sessions. A `Recorder` wraps any machine
+// and logs every input it reads and every output it writes, each stamped with the machine's
+// cycle count at the instruction that did it. The log saves to a plain text file:
+//
+//   intcode-replay
+//   image 9f3c2a61d0e4b7a8
+//   in 12 5
+//   out 40 -3
+//
+// `replay` feeds a fresh machine the same inputs at the same cycles and checks it produces
+// the same outputs, stopping at the first event that doesn't line up.
+use fxhash::FxHasher64;
+use std::fmt;
+use std::fs;
+use std::hash::Hasher;
+use std::io;
+use std::path::Path;
+use std::str::FromStr;
+use crate::{IntCodeComputer, State, Word};
+
+// stable across runs and platforms, unlike the standard library's seeded hashers: lengths
+// go in as u64s and words as their decimal text, so neither the pointer width nor the word
+// type changes the result
+pub fn image_hash<W: Word>(image: &[W]) -> u64 {
+    let mut hasher = FxHasher64::default();
+    hasher.write_u64(image.len() as u64);
+    for word in image.iter() {
+        let text = word.to_string();
+        hasher.write_u64(text.len() as u64);
+        hasher.write(text.as_bytes());
+    }
+    hasher.finish()
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Event<W> {
+    Input { cycle: u64, val: W },
+    Output { cycle: u64, val: W },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Replay<W> {
+    pub image: u64,
+    pub events: Vec<Event<W>>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseReplayError {
+    // one-based, like an editor would show it
+    pub line: usize,
+}
+
+impl fmt::Display for ParseReplayError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "malformed replay at line {}", self.line)
+    }
+}
+
+impl<W: Word> Replay<W> {
+    pub fn new(image: &[W]) -> Replay<W> {
+        Replay { image: image_hash(image), events: Vec::new() }
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        fs::write(path, self.to_string())
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Replay<W>> {
+        fs::read_to_string(path)?
+            .parse()
+            .map_err(|err: ParseReplayError| io::Error::new(io::ErrorKind::InvalidData, err.to_string()))
+    }
+}
+
+impl<W: Word> fmt::Display for Replay<W> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "intcode-replay")?;
+        writeln!(f, "image {:016x}", self.image)?;
+        for event in self.events.iter() {
+            match event {
+                Event::Input { cycle, val } => writeln!(f, "in {} {}", cycle, val)?,
+                Event::Output { cycle, val } => writeln!(f, "out {} {}", cycle, val)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<W: Word> FromStr for Replay<W> {
+    type Err = ParseReplayError;
+
+    fn from_str(s: &str) -> Result<Replay<W>, ParseReplayError> {
+        let mut lines = s.lines().enumerate().filter(|(_, line)| !line.trim().is_empty());
+        match lines.next() {
+            Some((_, line)) if line.trim() == "intcode-replay" => (),
+            other => return Err(ParseReplayError { line: other.map(|(n, _)| n + 1).unwrap_or(1) }),
+        }
+        let image = match lines.next() {
+            Some((n, line)) => line.trim()
+                .strip_prefix("image ")
+                .and_then(|hash| u64::from_str_radix(hash.trim(), 16).ok())
+                .ok_or(ParseReplayError { line: n + 1 })?,
+            None => return Err(ParseReplayError { line: 2 }),
+        };
+        let mut events = Vec::new();
+        for (n, line) in lines {
+            let err = ParseReplayError { line: n + 1 };
+            let parts: Vec<&str> = line.split_whitespace().collect();
+            let (kind, cycle, val) = match parts[..] {
+                [kind, cycle, val] => (kind, cycle, val),
+                _ => return Err(err),
+            };
+            let cycle = cycle.parse().map_err(|_| err.clone())?;
+            let val = val.parse().map_err(|_| err.clone())?;
+            events.push(match kind {
+                "in" => Event::Input { cycle, val },
+                "out" => Event::Output { cycle, val },
+                _ => return Err(err),
+            });
+        }
+        Ok(Replay { image, events })
+    }
+}
+
+// Wraps a machine and logs everything going in and out of it. Drive it like the machine
+// itself; `run` goes one instruction at a time so every read gets its exact cycle.
+#[derive(Debug)]
+pub struct Recorder<C, W> {
+    comp: C,
+    replay: Replay<W>,
+}
+
+impl<C: IntCodeComputer<W>, W: Word> Recorder<C, W> {
+    // `image` should be what `comp` was loaded with
+    pub fn new(comp: C, image: &[W]) -> Recorder<C, W> {
+        Recorder { comp, replay: Replay::new(image) }
+    }
+
+    pub fn replay(&self) -> &Replay<W> {
+        &self.replay
+    }
+
+    pub fn into_replay(self) -> Replay<W> {
+        self.replay
+    }
+
+    pub fn inner(&self) -> &C {
+        &self.comp
+    }
+}
+
+impl<C: IntCodeComputer<W>, W: Word> IntCodeComputer<W> for Recorder<C, W> {
+    fn run(&mut self) -> State {
+        loop {
+            match self.step() {
+                State::Running => continue,
+                state => return state,
+            }
+        }
+    }
+
+    fn out(&self) -> &Vec<W> {
+        self.comp.out()
+    }
+
+    fn push(&mut self, val: W) {
+        self.comp.push(val);
+    }
+
+    fn mem(&self, at: i64) -> W {
+        self.comp.mem(at)
+    }
+
+    fn state(&self) -> State {
+        self.comp.state()
+    }
+
+    fn step(&mut self) -> State {
+        let cycle = self.comp.cycles();
+        let (queued, outputs) = (self.comp.queued(), self.comp.out().len());
+        let next = self.comp.inputs().first().cloned();
+        let state = self.comp.step();
+        if let Some(val) = next.filter(|_| self.comp.queued() < queued) {
+            self.replay.events.push(Event::Input { cycle, val });
+        }
+        for val in self.comp.out()[outputs..].iter() {
+            self.replay.events.push(Event::Output { cycle, val: val.clone() });
+        }
+        state
+    }
+
+    fn cycles(&self) -> u64 {
+        self.comp.cycles()
+    }
+
+    fn queued(&self) -> usize {
+        self.comp.queued()
+    }
+
+    fn pc(&self) -> i64 {
+        self.comp.pc()
+    }
+
+    fn set_pc(&mut self, pc: i64) {
+        self.comp.set_pc(pc);
+    }
+
+    fn rb(&self) -> i64 {
+        self.comp.rb()
+    }
+
+    fn set_rb(&mut self, rb: i64) {
+        self.comp.set_rb(rb);
+    }
+
+    // a poke isn't something the program did, so it isn't recorded
+    fn poke(&mut self, at: i64, val: W) {
+        self.comp.poke(at, val);
+    }
+
+    fn inputs(&self) -> &[W] {
+        self.comp.inputs()
+    }
+
+    fn image_size(&self) -> i64 {
+        self.comp.image_size()
+    }
+
+    fn touched(&self) -> Vec<i64> {
+        self.comp.touched()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Divergence<W> {
+    // the replay was recorded against a different program
+    Image { expected: u64, actual: u64 },
+    // Event `index` of the replay didn't happen as recorded. `actual` is what the machine
+    // did instead (an input here means it tried to read at that cycle), or `None` if it
+    // stopped or didn't read when it should have.
+    Event { index: usize, expected: Option<Event<W>>, actual: Option<Event<W>> },
+}
+
+// Feeds `comp`, freshly loaded with `image` and no inputs, the inputs from `replay` and
+// checks its outputs against the recording. Succeeds once every event has been matched and
+// the machine is halted or waiting for input again.
+pub fn replay<C, W>(comp: &mut C, image: &[W], replay: &Replay<W>) -> Result<(), Divergence<W>>
+where C: IntCodeComputer<W>, W: Word {
+    let actual = image_hash(image);
+    if actual != replay.image {
+        return Err(Divergence::Image { expected: replay.image, actual });
+    }
+    let mut index = 0;
+    let diverged = |index: usize, actual: Option<Event<W>>| Divergence::Event {
+        index,
+        expected: replay.events.get(index).cloned(),
+        actual,
+    };
+    loop {
+        let cycle = comp.cycles();
+        let fed = match replay.events.get(index) {
+            Some(Event::Input { cycle: at, val }) if *at == cycle => {
+                comp.push(val.clone());
+                index += 1;
+                Some(index - 1)
+            },
+            _ => None,
+        };
+        let (queued, outputs) = (comp.queued(), comp.out().len());
+        let state = comp.step();
+        // an input we just pushed has to be read by this very instruction
+        if let Some(fed) = fed {
+            if comp.queued() == queued {
+                return Err(diverged(fed, None));
+            }
+        }
+        for val in comp.out()[outputs..].iter() {
+            let event = Event::Output { cycle, val: val.clone() };
+            if replay.events.get(index) != Some(&event) {
+                return Err(diverged(index, Some(event)));
+            }
+            index += 1;
+        }
+        match state {
+            State::Running => continue,
+            State::Waiting if index == replay.events.len() => return Ok(()),
+            // the machine wants input the recording didn't give it yet
+            State::Waiting => {
+                let val = match &replay.events[index] {
+                    Event::Input { val, .. } => val.clone(),
+                    Event::Output { .. } => W::default(),
+                };
+                return Err(diverged(index, Some(Event::Input { cycle, val })));
+            },
+            _ if index == replay.events.len() => return Ok(()),
+            _ => return Err(diverged(index, None)),
+        }
+    }
+}
@@ -0,0 +1,266 @@
+// For the vacuum robot's camera program. Run as it comes, it prints what the camera sees and
+// halts:
+//
+//   ..#..........
+//   ..#..........
+//   #######...###
+//   #.#...#...#.#
+//   #############
+//   ..#...#...#..
+//   ..#####...^..
+//
+// `#` is scaffold and `^`, `v`, `<` or `>` the robot on it, facing that way. With 2 written to
+// address 0 the robot wakes up: after the picture the program asks for a main routine of calls
+// to A, B and C, then each of those functions as turns (`L`, `R`) and moves, then whether to
+// show a continuous video feed. Every line is at most 20 characters. Once the robot has moved
+// it prints the dust it collected, the one value that isn't ASCII.
+use fxhash::FxHashSet;
+use std::fmt;
+use crate::maze::{Dir, Pos};
+use crate::{ascii, IntCodeComputer, State};
+
+// characters in a routine, not counting the newline
+pub const MAX_LEN: usize = 20;
+
+pub const FUNCTIONS: usize = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Step {
+    Left,
+    Right,
+    Forward(usize),
+}
+
+impl fmt::Display for Step {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Step::Left => write!(f, "L"),
+            Step::Right => write!(f, "R"),
+            Step::Forward(n) => write!(f, "{}", n),
+        }
+    }
+}
+
+// steps as the program reads them, `R,8,L,10`
+pub fn render(steps: &[Step]) -> String {
+    steps.iter().map(Step::to_string).collect::<Vec<_>>().join(",")
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Camera {
+    rows: Vec<Vec<u8>>,
+    robot: Option<(Pos, Dir)>,
+}
+
+impl Camera {
+    // the picture up to the first blank line after it, so a prompt that follows is left out
+    pub fn parse(text: &str) -> Camera {
+        let mut camera = Camera::default();
+        for line in text.lines().skip_while(|line| line.trim().is_empty()) {
+            if line.trim().is_empty() {
+                break;
+            }
+            let y = camera.rows.len() as i64;
+            for (x, c) in line.bytes().enumerate() {
+                let dir = match c {
+                    b'^' => Dir::North,
+                    b'v' => Dir::South,
+                    b'<' => Dir::West,
+                    b'>' => Dir::East,
+                    _ => continue,
+                };
+                camera.robot = Some(((x as i64, y), dir));
+            }
+            camera.rows.push(line.bytes().collect());
+        }
+        camera
+    }
+
+    pub fn read(out: &[i64]) -> Camera {
+        Camera::parse(&ascii::decode(out))
+    }
+
+    // where the robot is and which way it faces, unless it's nowhere or tumbling through space
+    pub fn robot(&self) -> Option<(Pos, Dir)> {
+        self.robot
+    }
+
+    pub fn height(&self) -> usize {
+        self.rows.len()
+    }
+
+    // the widest row; rows can be ragged
+    pub fn width(&self) -> usize {
+        self.rows.iter().map(Vec::len).max().unwrap_or(0)
+    }
+
+    // the robot stands on scaffold, so it counts
+    pub fn scaffold(&self, (x, y): Pos) -> bool {
+        if x < 0 || y < 0 {
+            return false;
+        }
+        let cell = self.rows.get(y as usize).and_then(|row| row.get(x as usize));
+        matches!(cell, Some(b'#') | Some(b'^') | Some(b'v') | Some(b'<') | Some(b'>'))
+    }
+
+    // scaffold with scaffold on all four sides, top to bottom then left to right
+    pub fn intersections(&self) -> Vec<Pos> {
+        let mut found = Vec::new();
+        for y in 0..self.height() as i64 {
+            for x in 0..self.width() as i64 {
+                if self.scaffold((x, y)) && Dir::ALL.iter().all(|dir| self.scaffold(dir.step((x, y)))) {
+                    found.push((x, y));
+                }
+            }
+        }
+        found
+    }
+
+    // the sum of x times y over the intersections
+    pub fn alignment(&self) -> i64 {
+        self.intersections().iter().map(|(x, y)| x * y).sum()
+    }
+
+    // Follows the scaffold from the robot to its far end, going straight over intersections
+    // and turning only where it has to. `None` without a robot.
+    pub fn path(&self) -> Option<Vec<Step>> {
+        let (mut pos, mut dir) = self.robot?;
+        let mut steps = Vec::new();
+        // a scaffold that loops back on itself would go round forever
+        let mut turned: FxHashSet<(Pos, Dir)> = FxHashSet::default();
+        loop {
+            let mut moved = 0;
+            while self.scaffold(dir.step(pos)) {
+                pos = dir.step(pos);
+                moved += 1;
+            }
+            if moved > 0 {
+                steps.push(Step::Forward(moved));
+            }
+            let turn = match (self.scaffold(dir.left().step(pos)), self.scaffold(dir.right().step(pos))) {
+                (true, _) => Step::Left,
+                (_, true) => Step::Right,
+                _ => return Some(steps),
+            };
+            if !turned.insert((pos, dir)) {
+                return Some(steps);
+            }
+            dir = if turn == Step::Left { dir.left() } else { dir.right() };
+            steps.push(turn);
+        }
+    }
+}
+
+// A main routine of calls, each an index into `functions` (0 is A), and the functions.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Routines {
+    pub main: Vec<usize>,
+    pub functions: Vec<Vec<Step>>,
+}
+
+impl Routines {
+    pub fn main_text(&self) -> String {
+        let calls: Vec<String> = self.main.iter().map(|call| ((b'A' + *call as u8) as char).to_string()).collect();
+        calls.join(",")
+    }
+
+    // The lines to send: the main routine then A, B and C. Functions that aren't needed go
+    // out empty, since the main routine never calls them.
+    pub fn lines(&self) -> Vec<String> {
+        let mut lines = vec![self.main_text()];
+        lines.extend((0..FUNCTIONS).map(|i| self.functions.get(i).map(|steps| render(steps)).unwrap_or_default()));
+        lines
+    }
+
+    // every step the robot takes, function calls spelled out
+    pub fn expand(&self) -> Vec<Step> {
+        self.main.iter().flat_map(|call| self.functions[*call].iter().copied()).collect()
+    }
+}
+
+// Splits `path` into a main routine and up to three functions, none longer than `MAX_LEN`
+// characters. Functions may break the path anywhere, even between a turn and its move.
+pub fn compress(path: &[Step]) -> Option<Routines> {
+    let mut routines = Routines::default();
+    match split(path, &mut routines) {
+        true => Some(routines),
+        false => None,
+    }
+}
+
+// depth first, trying the longest new function first
+fn split(path: &[Step], routines: &mut Routines) -> bool {
+    if path.is_empty() {
+        return true;
+    }
+    // another call adds its letter and a comma
+    if 2 * routines.main.len() + 1 > MAX_LEN {
+        return false;
+    }
+    for call in 0..routines.functions.len() {
+        let len = routines.functions[call].len();
+        if path.starts_with(&routines.functions[call]) {
+            routines.main.push(call);
+            if split(&path[len..], routines) {
+                return true;
+            }
+            routines.main.pop();
+        }
+    }
+    if routines.functions.len() == FUNCTIONS {
+        return false;
+    }
+    let longest = (1..=path.len()).take_while(|len| render(&path[..*len]).len() <= MAX_LEN).last().unwrap_or(0);
+    for len in (1..=longest).rev() {
+        routines.main.push(routines.functions.len());
+        routines.functions.push(path[..len].to_vec());
+        if split(&path[len..], routines) {
+            return true;
+        }
+        routines.functions.pop();
+        routines.main.pop();
+    }
+    false
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ScaffoldError {
+    // the picture has no robot in it, or it's tumbling off
+    NoRobot,
+    // no way to fit the path into the routines
+    Uncompressible { path: Vec<Step> },
+    // the program wasn't waiting for routines, or didn't halt after them
+    Stopped { state: State, text: String },
+    // halted without reporting any dust, most likely after the robot fell off
+    NoDust { text: String },
+}
+
+// Sends `routines` and the answer about the video feed to a program at its first prompt, and
+// returns the dust the robot collected.
+pub fn feed<C: IntCodeComputer>(comp: &mut C, routines: &Routines, video: bool) -> Result<i64, ScaffoldError> {
+    let seen = comp.out().len();
+    for line in routines.lines() {
+        ascii::push_line(comp, &line);
+    }
+    ascii::push_line(comp, if video { "y" } else { "n" });
+    let state = comp.run();
+    let out = &comp.out()[seen..];
+    if state != State::Halted {
+        return Err(ScaffoldError::Stopped { state, text: ascii::decode(out) });
+    }
+    ascii::answer(out).ok_or_else(|| ScaffoldError::NoDust { text: ascii::decode(out) })
+}
+
+// Wakes the robot on a fresh machine, reads the picture, and walks the whole scaffold.
+pub fn collect_dust<C: IntCodeComputer>(comp: &mut C) -> Result<i64, ScaffoldError> {
+    comp.poke(0, 2);
+    let seen = comp.out().len();
+    let state = comp.run();
+    if state != State::Waiting {
+        return Err(ScaffoldError::Stopped { state, text: ascii::decode(&comp.out()[seen..]) });
+    }
+    let camera = Camera::read(&comp.out()[seen..]);
+    let path = camera.path().ok_or(ScaffoldError::NoRobot)?;
+    let routines = compress(&path).ok_or(ScaffoldError::Uncompressible { path })?;
+    feed(comp, &routines, false)
+}
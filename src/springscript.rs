@@ -0,0 +1,604 @@
+// Springscript, the language a springdroid program reads as ASCII before it sets off across
+// the hull. There are two writable registers, T (scratch) and J (jump if true at the end),
+// both false at the start of every step, and read-only sensors A to I that say whether there's
+// hull 1 to 9 tiles ahead. Instructions are
+//
+//   AND X Y    Y = X and Y
+//   OR X Y     Y = X or Y
+//   NOT X Y    Y = not X
+//
+// with X any register and Y only T or J, at most 15 of them, and then `WALK` (sensors A to D)
+// or `RUN` (all nine). The program prints the hull damage it reported if the droid made it
+// across, or frames of the droid falling in if it didn't.
+//
+// `Expr::compile` turns a formula over the sensors into the shortest script it can find: a
+// breadth-first search over what T and J hold on every combination of the sensors involved,
+// which is exact when it finishes within its budget, and otherwise a direct translation of the
+// formula.
+use fxhash::FxHashMap;
+use std::fmt;
+use crate::{ascii, IntCodeComputer, State};
+
+pub const MAX_INSTRUCTIONS: usize = 15;
+
+// the most states the search for a shortest script looks at before settling
+const SEARCH_BUDGET: usize = 200_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Reg {
+    // how far ahead it looks, 1 (A) to 9 (I)
+    Sensor(u8),
+    T,
+    J,
+}
+
+impl Reg {
+    pub fn parse(name: &str) -> Option<Reg> {
+        match name {
+            "T" => Some(Reg::T),
+            "J" => Some(Reg::J),
+            _ if name.len() == 1 && ("A"..="I").contains(&name) => Some(Reg::Sensor(name.as_bytes()[0] - b'A' + 1)),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> char {
+        match self {
+            Reg::Sensor(dist) => (b'A' + dist - 1) as char,
+            Reg::T => 'T',
+            Reg::J => 'J',
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Gate {
+    And,
+    Or,
+    Not,
+}
+
+impl Gate {
+    const ALL: [Gate; 3] = [Gate::And, Gate::Or, Gate::Not];
+
+    pub fn mnemonic(self) -> &'static str {
+        match self {
+            Gate::And => "AND",
+            Gate::Or => "OR",
+            Gate::Not => "NOT",
+        }
+    }
+
+    fn apply(self, x: bool, y: bool) -> bool {
+        match self {
+            Gate::And => x && y,
+            Gate::Or => x || y,
+            Gate::Not => !x,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Instr {
+    pub gate: Gate,
+    pub x: Reg,
+    pub y: Reg,
+}
+
+impl fmt::Display for Instr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} {} {}", self.gate.mnemonic(), self.x.name(), self.y.name())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    Walk,
+    Run,
+}
+
+impl Mode {
+    // how many sensors the droid has, A onwards
+    pub fn sensors(self) -> u8 {
+        match self {
+            Mode::Walk => 4,
+            Mode::Run => 9,
+        }
+    }
+
+    pub fn command(self) -> &'static str {
+        match self {
+            Mode::Walk => "WALK",
+            Mode::Run => "RUN",
+        }
+    }
+}
+
+// `line` counts from 1, and is the instruction's index plus one for scripts that weren't parsed
+#[derive(Debug, Clone, PartialEq)]
+pub enum ScriptError {
+    Syntax { line: usize, text: String },
+    BadRegister { line: usize, name: String },
+    // only T and J can be written
+    ReadOnly { line: usize, reg: Reg },
+    // a sensor the droid doesn't have in this mode
+    OutOfRange { line: usize, reg: Reg, mode: Mode },
+    TooLong { len: usize },
+    // `at` is the byte offset into the formula
+    BadExpression { at: usize },
+    // the formula needs more than the two registers there are
+    TooComplex,
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Script {
+    instrs: Vec<Instr>,
+}
+
+impl Script {
+    pub fn new(instrs: Vec<Instr>) -> Script {
+        Script { instrs }
+    }
+
+    // one instruction per line, blank lines skipped
+    pub fn parse(text: &str) -> Result<Script, ScriptError> {
+        let mut instrs = Vec::new();
+        for (i, text) in text.lines().enumerate().filter(|(_, text)| !text.trim().is_empty()) {
+            let line = i + 1;
+            let words: Vec<&str> = text.split_whitespace().collect();
+            let gate = match words.first() {
+                Some(&"AND") => Gate::And,
+                Some(&"OR") => Gate::Or,
+                Some(&"NOT") => Gate::Not,
+                _ => return Err(ScriptError::Syntax { line, text: text.to_string() }),
+            };
+            if words.len() != 3 {
+                return Err(ScriptError::Syntax { line, text: text.to_string() });
+            }
+            let reg = |name: &str| Reg::parse(name).ok_or_else(|| ScriptError::BadRegister { line, name: name.to_string() });
+            let (x, y) = (reg(words[1])?, reg(words[2])?);
+            if let Reg::Sensor(_) = y {
+                return Err(ScriptError::ReadOnly { line, reg: y });
+            }
+            instrs.push(Instr { gate, x, y });
+        }
+        Ok(Script { instrs })
+    }
+
+    pub fn instrs(&self) -> &[Instr] {
+        &self.instrs
+    }
+
+    // whether the program would take this script in `mode`
+    pub fn validate(&self, mode: Mode) -> Result<(), ScriptError> {
+        for (i, instr) in self.instrs.iter().enumerate() {
+            if let Reg::Sensor(_) = instr.y {
+                return Err(ScriptError::ReadOnly { line: i + 1, reg: instr.y });
+            }
+            for reg in [instr.x, instr.y] {
+                match reg {
+                    Reg::Sensor(dist) if !(1..=mode.sensors()).contains(&dist) => {
+                        return Err(ScriptError::OutOfRange { line: i + 1, reg, mode });
+                    },
+                    _ => (),
+                }
+            }
+        }
+        if self.instrs.len() > MAX_INSTRUCTIONS {
+            return Err(ScriptError::TooLong { len: self.instrs.len() });
+        }
+        Ok(())
+    }
+
+    // whether the droid jumps, `ground[0]` being sensor A
+    pub fn eval(&self, ground: &[bool; 9]) -> bool {
+        let (mut t, mut j) = (false, false);
+        for instr in self.instrs.iter() {
+            let x = match instr.x {
+                Reg::Sensor(dist) => ground[dist as usize - 1],
+                Reg::T => t,
+                Reg::J => j,
+            };
+            match instr.y {
+                Reg::T => t = instr.gate.apply(x, t),
+                Reg::J => j = instr.gate.apply(x, j),
+                Reg::Sensor(_) => (),
+            }
+        }
+        j
+    }
+}
+
+impl fmt::Display for Script {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for instr in self.instrs.iter() {
+            writeln!(f, "{}", instr)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    Sensor(u8),
+    Not(Box<Expr>),
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+}
+
+impl Expr {
+    // Letters `A` to `I` for the sensors, `!`, `&`, `|` loosest, and parentheses:
+    // `!(A & B & C) & D`.
+    pub fn parse(text: &str) -> Result<Expr, ScriptError> {
+        let mut parser = Parser { text: text.as_bytes(), at: 0 };
+        let expr = parser.or()?;
+        parser.skip();
+        match parser.at < text.len() {
+            true => Err(ScriptError::BadExpression { at: parser.at }),
+            false => Ok(expr),
+        }
+    }
+
+    pub fn eval(&self, ground: &[bool; 9]) -> bool {
+        match self {
+            Expr::Sensor(dist) => ground[*dist as usize - 1],
+            Expr::Not(a) => !a.eval(ground),
+            Expr::And(a, b) => a.eval(ground) && b.eval(ground),
+            Expr::Or(a, b) => a.eval(ground) || b.eval(ground),
+        }
+    }
+
+    // the sensors it reads, nearest first
+    pub fn sensors(&self) -> Vec<u8> {
+        let mut sensors = Vec::new();
+        self.collect(&mut sensors);
+        sensors.sort_unstable();
+        sensors.dedup();
+        sensors
+    }
+
+    fn collect(&self, sensors: &mut Vec<u8>) {
+        match self {
+            Expr::Sensor(dist) => sensors.push(*dist),
+            Expr::Not(a) => a.collect(sensors),
+            Expr::And(a, b) | Expr::Or(a, b) => {
+                a.collect(sensors);
+                b.collect(sensors);
+            },
+        }
+    }
+
+    // a script that leaves J set exactly when the formula holds
+    pub fn compile(&self) -> Result<Script, ScriptError> {
+        let direct = translate(self, Reg::J, Some(Reg::T), &[Reg::T, Reg::J], true);
+        let bound = direct.as_ref().map(Vec::len).unwrap_or(MAX_INSTRUCTIONS + 1);
+        match search(self, bound).or(direct) {
+            Some(instrs) => Ok(Script { instrs }),
+            None => Err(ScriptError::TooComplex),
+        }
+    }
+
+    // the shortest script of at most `max` instructions for the formula, if the search can
+    // find one within its budget
+    pub fn shortest(&self, max: usize) -> Option<Script> {
+        search(self, max + 1).map(|instrs| Script { instrs })
+    }
+}
+
+struct Parser<'a> {
+    text: &'a [u8],
+    at: usize,
+}
+
+impl Parser<'_> {
+    fn skip(&mut self) {
+        while self.text.get(self.at).map(u8::is_ascii_whitespace).unwrap_or(false) {
+            self.at += 1;
+        }
+    }
+
+    fn eat(&mut self, c: u8) -> bool {
+        self.skip();
+        let found = self.text.get(self.at) == Some(&c);
+        if found {
+            self.at += 1;
+        }
+        found
+    }
+
+    fn or(&mut self) -> Result<Expr, ScriptError> {
+        let mut expr = self.and()?;
+        while self.eat(b'|') {
+            expr = Expr::Or(Box::new(expr), Box::new(self.and()?));
+        }
+        Ok(expr)
+    }
+
+    fn and(&mut self) -> Result<Expr, ScriptError> {
+        let mut expr = self.unary()?;
+        while self.eat(b'&') {
+            expr = Expr::And(Box::new(expr), Box::new(self.unary()?));
+        }
+        Ok(expr)
+    }
+
+    fn unary(&mut self) -> Result<Expr, ScriptError> {
+        if self.eat(b'!') {
+            return Ok(Expr::Not(Box::new(self.unary()?)));
+        }
+        if self.eat(b'(') {
+            let expr = self.or()?;
+            return match self.eat(b')') {
+                true => Ok(expr),
+                false => Err(ScriptError::BadExpression { at: self.at }),
+            };
+        }
+        match self.text.get(self.at) {
+            Some(c) if (b'A'..=b'I').contains(c) => {
+                self.at += 1;
+                Ok(Expr::Sensor(c - b'A' + 1))
+            },
+            _ => Err(ScriptError::BadExpression { at: self.at }),
+        }
+    }
+}
+
+// the formula with one less `Not` on top, pushed down a level by De Morgan if need be
+fn negate(expr: &Expr) -> Expr {
+    match expr {
+        Expr::Not(a) => (**a).clone(),
+        Expr::And(a, b) => Expr::Or(Box::new(not(a)), Box::new(not(b))),
+        Expr::Or(a, b) => Expr::And(Box::new(not(a)), Box::new(not(b))),
+        Expr::Sensor(_) => not(expr),
+    }
+}
+
+// `Not`, cancelling out one that's already there
+fn not(expr: &Expr) -> Expr {
+    match expr {
+        Expr::Not(a) => (**a).clone(),
+        _ => Expr::Not(Box::new(expr.clone())),
+    }
+}
+
+fn shortest(options: impl IntoIterator<Item = Option<Vec<Instr>>>) -> Option<Vec<Instr>> {
+    options.into_iter().flatten().min_by_key(Vec::len)
+}
+
+// Computes `expr` into `dest`, clobbering `scratch` if there is one. `clean` are the
+// registers still false from the start, which a sensor can just be ORed into. With `flip` it
+// also tries computing the negation and inverting it. `None` if it can't be done without a
+// third register.
+fn translate(expr: &Expr, dest: Reg, scratch: Option<Reg>, clean: &[Reg], flip: bool) -> Option<Vec<Instr>> {
+    let direct = match expr {
+        Expr::Sensor(dist) if clean.contains(&dest) => Some(vec![Instr { gate: Gate::Or, x: Reg::Sensor(*dist), y: dest }]),
+        Expr::Sensor(dist) => Some(vec![
+            Instr { gate: Gate::Not, x: Reg::Sensor(*dist), y: dest },
+            Instr { gate: Gate::Not, x: dest, y: dest },
+        ]),
+        Expr::Not(a) => match &**a {
+            Expr::Sensor(dist) => Some(vec![Instr { gate: Gate::Not, x: Reg::Sensor(*dist), y: dest }]),
+            _ => translate(&negate(a), dest, scratch, clean, flip),
+        },
+        Expr::And(a, b) | Expr::Or(a, b) => {
+            let gate = match expr {
+                Expr::And(..) => Gate::And,
+                _ => Gate::Or,
+            };
+            shortest([(a, b), (b, a)].iter().map(|(first, second)| {
+                let mut instrs = translate(first, dest, scratch, clean, true)?;
+                let clean: Vec<Reg> = clean.iter().copied().filter(|reg| instrs.iter().all(|instr| instr.y != *reg)).collect();
+                instrs.extend(combine(gate, second, dest, scratch, &clean)?);
+                Some(instrs)
+            }))
+        },
+    };
+    let flipped = match flip {
+        true => translate(&negate(expr), dest, scratch, clean, false).map(|mut instrs| {
+            instrs.push(Instr { gate: Gate::Not, x: dest, y: dest });
+            instrs
+        }),
+        false => None,
+    };
+    shortest([direct, flipped])
+}
+
+// folds `expr` into what `dest` already holds with `gate`
+fn combine(gate: Gate, expr: &Expr, dest: Reg, scratch: Option<Reg>, clean: &[Reg]) -> Option<Vec<Instr>> {
+    if let Some(dist) = sensor(expr) {
+        return Some(vec![Instr { gate, x: Reg::Sensor(dist), y: dest }]);
+    }
+    let scratch = scratch?;
+    let mut instrs = translate(expr, scratch, None, clean, true)?;
+    instrs.push(Instr { gate, x: scratch, y: dest });
+    Some(instrs)
+}
+
+fn sensor(expr: &Expr) -> Option<u8> {
+    match expr {
+        Expr::Sensor(dist) => Some(*dist),
+        _ => None,
+    }
+}
+
+// Breadth first over (T, J), each a truth table with a bit for every combination of the
+// sensors `expr` reads, for a script shorter than `bound` that leaves J equal to `expr`.
+// Gives up past six sensors, since the tables have to fit in a u64, or once the budget runs out.
+fn search(expr: &Expr, bound: usize) -> Option<Vec<Instr>> {
+    let sensors = expr.sensors();
+    if sensors.len() > 6 {
+        return None;
+    }
+    let rows = 1usize << sensors.len();
+    let full = if rows == 64 { u64::MAX } else { (1u64 << rows) - 1 };
+    let table = |f: &dyn Fn(&[bool; 9]) -> bool| (0..rows).fold(0u64, |table, row| {
+        let mut ground = [false; 9];
+        for (i, dist) in sensors.iter().enumerate() {
+            ground[*dist as usize - 1] = row >> i & 1 == 1;
+        }
+        table | (f(&ground) as u64) << row
+    });
+    let target = table(&|ground| expr.eval(ground));
+    let inputs: Vec<(Reg, u64)> = sensors.iter()
+        .map(|dist| (Reg::Sensor(*dist), table(&|ground| ground[*dist as usize - 1])))
+        .collect();
+    let start = (0u64, 0u64);
+    if target == 0 {
+        return Some(Vec::new());
+    }
+    let mut came: FxHashMap<(u64, u64), ((u64, u64), Instr)> = FxHashMap::default();
+    let mut frontier = vec![start];
+    for _ in 1..bound {
+        let mut next = Vec::new();
+        for (t, j) in frontier {
+            let sources = inputs.iter().copied().chain([(Reg::T, t), (Reg::J, j)]);
+            for (x, val) in sources {
+                for gate in Gate::ALL {
+                    for y in [Reg::T, Reg::J] {
+                        let old = if y == Reg::T { t } else { j };
+                        let new = match gate {
+                            Gate::And => val & old,
+                            Gate::Or => val | old,
+                            Gate::Not => !val & full,
+                        };
+                        let state = if y == Reg::T { (new, j) } else { (t, new) };
+                        if state == start || came.contains_key(&state) {
+                            continue;
+                        }
+                        came.insert(state, ((t, j), Instr { gate, x, y }));
+                        if state.1 == target {
+                            let mut instrs = Vec::new();
+                            let mut at = state;
+                            while at != start {
+                                let (prev, instr) = came[&at];
+                                instrs.push(instr);
+                                at = prev;
+                            }
+                            instrs.reverse();
+                            return Some(instrs);
+                        }
+                        if came.len() > SEARCH_BUDGET {
+                            return None;
+                        }
+                        next.push(state);
+                    }
+                }
+            }
+        }
+        frontier = next;
+    }
+    None
+}
+
+// One frame of a failed run: the rows of the view top to bottom, the last being the hull.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Frame {
+    // whether there's hull under each column
+    pub hull: Vec<bool>,
+    // (column, row) of the droid, row 0 being the top
+    pub droid: (usize, usize),
+    pub rows: usize,
+}
+
+impl Frame {
+    fn parse(lines: &[&str]) -> Option<Frame> {
+        let bottom = lines.last()?;
+        let droid = lines.iter().enumerate().find_map(|(row, line)| Some((line.find('@')?, row)))?;
+        let hull = bottom.chars().map(|c| c == '#').collect();
+        Some(Frame { hull, droid, rows: lines.len() })
+    }
+
+    // down in a hole, which is the last frame of any failure
+    pub fn fallen(&self) -> bool {
+        self.droid.1 == self.rows - 1
+    }
+
+    pub fn on_ground(&self) -> bool {
+        self.droid.1 + 2 == self.rows && self.hull.get(self.droid.0) == Some(&true)
+    }
+
+    // what the sensors would read from here, as far as the view goes
+    pub fn sensors(&self) -> Vec<bool> {
+        self.hull.iter().skip(self.droid.0 + 1).take(9).copied().collect()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Failure {
+    // everything the program printed
+    pub text: String,
+    pub frames: Vec<Frame>,
+}
+
+impl Failure {
+    // Picks the frames out of a failure report, one block of lines per frame. `None` if the
+    // text isn't one.
+    pub fn parse(text: &str) -> Option<Failure> {
+        let start = text.find("Didn't make it across")?;
+        let mut frames = Vec::new();
+        let mut block = Vec::new();
+        for line in text[start..].lines().skip(1).map(str::trim).chain(Some("")) {
+            if !line.is_empty() {
+                block.push(line);
+                continue;
+            }
+            if !block.is_empty() {
+                frames.push(Frame::parse(&block)?);
+                block.clear();
+            }
+        }
+        Some(Failure { text: text.to_string(), frames })
+    }
+
+    // the column of the hole the droid fell into
+    pub fn hole(&self) -> Option<usize> {
+        self.frames.last().filter(|frame| frame.fallen()).map(|frame| frame.droid.0)
+    }
+
+    // the last frame with the droid on the ground, where it chose wrong
+    pub fn last_decision(&self) -> Option<&Frame> {
+        self.frames.iter().rev().find(|frame| frame.on_ground())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Outcome {
+    Across { damage: i64 },
+    Fell(Failure),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum RunError {
+    Invalid(ScriptError),
+    // the program wasn't at its prompt to begin with, or didn't halt after the script
+    Stopped { state: State, text: String },
+    // it halted without a damage value or a failure, most likely complaining about the script
+    Rejected { text: String },
+}
+
+// Sends `script` and the mode's command to a springdroid program and reports how it went.
+pub fn run<C: IntCodeComputer>(comp: &mut C, script: &Script, mode: Mode) -> Result<Outcome, RunError> {
+    script.validate(mode).map_err(RunError::Invalid)?;
+    let seen = comp.out().len();
+    let state = comp.run();
+    if state != State::Waiting {
+        return Err(RunError::Stopped { state, text: ascii::decode(&comp.out()[seen..]) });
+    }
+    for instr in script.instrs() {
+        ascii::push_line(comp, &instr.to_string());
+    }
+    ascii::push_line(comp, mode.command());
+    let state = comp.run();
+    let out = &comp.out()[seen..];
+    let text = ascii::decode(out);
+    if state != State::Halted {
+        return Err(RunError::Stopped { state, text });
+    }
+    if let Some(damage) = ascii::answer(out) {
+        return Ok(Outcome::Across { damage });
+    }
+    match Failure::parse(&text) {
+        Some(failure) => Ok(Outcome::Fell(failure)),
+        None => Err(RunError::Rejected { text }),
+    }
+}
@@ -0,0 +1,176 @@
+// Parameter sweeps over one base image. Each candidate is a set of memory patches plus an
+// input vector; candidates are handed out to a pool of scoped worker threads, each of which
+// builds a fresh machine through the caller's constructor (so any backend works, e.g.
+// `ProcIntCode::new`), runs it until it stops, and hands the final machine to a predicate or
+// scoring function.
+//
+// `find` cancels outstanding work once a match turns up. It still returns the lowest-index
+// match, so results don't depend on thread timing: a worker that finds one only stops the
+// candidates after it.
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+use crate::{IntCodeComputer, Word};
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Candidate<W> {
+    pub patches: Vec<(i64, W)>,
+    pub inputs: Vec<W>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SweepError {
+    // a patch aimed below address zero
+    NegativePatch { addr: i64 },
+}
+
+#[derive(Debug)]
+pub struct Match<W, C> {
+    // position of the candidate in the sweep
+    pub index: usize,
+    pub candidate: Candidate<W>,
+    pub comp: C,
+}
+
+#[derive(Debug, Clone)]
+pub struct Sweep<W> {
+    image: Vec<W>,
+    candidates: Vec<Candidate<W>>,
+    threads: usize,
+}
+
+impl<W: Word> Sweep<W> {
+    pub fn new(image: Vec<W>) -> Sweep<W> {
+        let threads = thread::available_parallelism().map(|n| n.get()).unwrap_or(1);
+        Sweep { image, candidates: Vec::new(), threads }
+    }
+
+    pub fn with_threads(mut self, threads: usize) -> Sweep<W> {
+        self.threads = threads.max(1);
+        self
+    }
+
+    pub fn candidate(mut self, candidate: Candidate<W>) -> Sweep<W> {
+        self.candidates.push(candidate);
+        self
+    }
+
+    // one candidate per patch set, with no inputs
+    pub fn patches<I: IntoIterator<Item = Vec<(i64, W)>>>(mut self, patches: I) -> Sweep<W> {
+        self.candidates.extend(patches.into_iter().map(|patches| Candidate { patches, inputs: Vec::new() }));
+        self
+    }
+
+    // one candidate per input vector, running the image unpatched
+    pub fn inputs<I: IntoIterator<Item = Vec<W>>>(mut self, inputs: I) -> Sweep<W> {
+        self.candidates.extend(inputs.into_iter().map(|inputs| Candidate { patches: Vec::new(), inputs }));
+        self
+    }
+
+    pub fn candidates(&self) -> &[Candidate<W>] {
+        &self.candidates
+    }
+
+    // the base image with a candidate's patches applied; patches past the end grow it
+    pub fn image(&self, candidate: &Candidate<W>) -> Result<Vec<W>, SweepError> {
+        let mut image = self.image.clone();
+        for (addr, val) in candidate.patches.iter() {
+            if *addr < 0 {
+                return Err(SweepError::NegativePatch { addr: *addr });
+            }
+            let addr = *addr as usize;
+            if addr >= image.len() {
+                image.resize(addr + 1, W::default());
+            }
+            image[addr] = val.clone();
+        }
+        Ok(image)
+    }
+
+    // The first candidate (by position) whose final machine satisfies `pred`. Like `find_all`
+    // and `best`, nothing runs if any candidate has a patch that can't be applied.
+    pub fn find<C, F, P>(&self, make: F, pred: P) -> Result<Option<Match<W, C>>, SweepError>
+    where C: IntCodeComputer<W> + Send, F: Fn(Vec<W>, Vec<W>) -> C + Sync, P: Fn(&C) -> bool + Sync {
+        self.check()?;
+        let job = |candidate: &Candidate<W>| Some(self.run(&make, candidate)).filter(|comp| pred(comp));
+        Ok(self.execute(job, true).into_iter().next().map(|(index, comp)| self.found(index, comp)))
+    }
+
+    // every matching candidate, in sweep order
+    pub fn find_all<C, F, P>(&self, make: F, pred: P) -> Result<Vec<Match<W, C>>, SweepError>
+    where C: IntCodeComputer<W> + Send, F: Fn(Vec<W>, Vec<W>) -> C + Sync, P: Fn(&C) -> bool + Sync {
+        self.check()?;
+        let job = |candidate: &Candidate<W>| Some(self.run(&make, candidate)).filter(|comp| pred(comp));
+        Ok(self.execute(job, false).into_iter().map(|(index, comp)| self.found(index, comp)).collect())
+    }
+
+    // the candidate with the highest score, ties going to the earlier one; `None` from
+    // `score` rules a candidate out
+    pub fn best<C, F, S, G>(&self, make: F, score: G) -> Result<Option<(Candidate<W>, S)>, SweepError>
+    where C: IntCodeComputer<W>, F: Fn(Vec<W>, Vec<W>) -> C + Sync, S: Ord + Send, G: Fn(&C) -> Option<S> + Sync {
+        self.check()?;
+        Ok(self.best_by(|candidate| score(&self.run(&make, candidate))))
+    }
+
+    // `best` for candidates that need more than one machine, like a chain of amplifiers:
+    // the scoring function gets the candidate itself and can build whatever it likes
+    pub fn best_by<S, G>(&self, score: G) -> Option<(Candidate<W>, S)>
+    where S: Ord + Send, G: Fn(&Candidate<W>) -> Option<S> + Sync {
+        self.execute(score, false)
+            .into_iter()
+            .rev()
+            .max_by(|(_, a), (_, b)| a.cmp(b))
+            .map(|(index, score)| (self.candidates[index].clone(), score))
+    }
+
+    fn run<C, F>(&self, make: &F, candidate: &Candidate<W>) -> C
+    where C: IntCodeComputer<W>, F: Fn(Vec<W>, Vec<W>) -> C {
+        let image = self.image(candidate).expect("patches are checked before a sweep runs");
+        let mut comp = make(image, candidate.inputs.clone());
+        comp.run();
+        comp
+    }
+
+    fn check(&self) -> Result<(), SweepError> {
+        let patches = self.candidates.iter().flat_map(|candidate| candidate.patches.iter());
+        match patches.map(|(addr, _)| *addr).find(|addr| *addr < 0) {
+            Some(addr) => Err(SweepError::NegativePatch { addr }),
+            None => Ok(()),
+        }
+    }
+
+    fn found<C>(&self, index: usize, comp: C) -> Match<W, C> {
+        Match { index, candidate: self.candidates[index].clone(), comp }
+    }
+
+    // Runs `job` over every candidate and collects the hits sorted by index. With `first`
+    // set, candidates after the earliest hit so far are skipped.
+    fn execute<R, J>(&self, job: J, first: bool) -> Vec<(usize, R)>
+    where R: Send, J: Fn(&Candidate<W>) -> Option<R> + Sync {
+        let next = AtomicUsize::new(0);
+        let cutoff = AtomicUsize::new(usize::MAX);
+        let hits = Mutex::new(Vec::new());
+        thread::scope(|scope| {
+            for _ in 0..self.threads.min(self.candidates.len()) {
+                scope.spawn(|| loop {
+                    let index = next.fetch_add(1, Ordering::Relaxed);
+                    if index >= self.candidates.len() || index > cutoff.load(Ordering::Relaxed) {
+                        break;
+                    }
+                    if let Some(hit) = job(&self.candidates[index]) {
+                        if first {
+                            cutoff.fetch_min(index, Ordering::Relaxed);
+                        }
+                        hits.lock().unwrap().push((index, hit));
+                    }
+                });
+            }
+        });
+        let mut hits = hits.into_inner().unwrap();
+        hits.sort_by_key(|(index, _)| *index);
+        if first {
+            hits.truncate(1);
+        }
+        hits
+    }
+}
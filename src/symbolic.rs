@@ -0,0 +1,687 @@
+// Symbolic execution for input synthesis. Chosen memory cells and inputs become symbols with
+// a bounded range, and the interpreter tracks every value as a polynomial over them with
+// exact (unbounded by the machine word) integer coefficients. A conditional jump on a
+// symbolic value forks the path and records which way it went as a constraint; so do
+// `LessThan` and `Equals`, so that their results stay plain 0 or 1 on each side. Once a path
+// ends, the goal is added as one more constraint and a small solver looks for values that
+// satisfy them all. Every candidate is checked by running a real `ProcIntCode` before it's
+// returned, so anything the model gets wrong (wrapping arithmetic, say) costs a retry rather
+// than a wrong answer.
+//
+// Reading through a symbolic address gives an opaque value the solver can't reason about;
+// that's fine as long as it never feeds the goal or a branch, which is the case for day 2's
+// noun and verb. Writing through one, or jumping or moving the relative base by a symbolic
+// amount, abandons the path.
+use fxhash::FxHashMap;
+use std::collections::BTreeMap;
+use std::ops::RangeInclusive;
+use crate::{IntCodeComputer, State};
+use crate::instruction::{self, Op, ParamMode};
+use crate::procedural_comp::ProcIntCode;
+
+// values larger than this are never enumerated; the solver has to pin them down some other way
+const ENUMERATION_LIMIT: i128 = 1 << 16;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Sym(usize);
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Goal {
+    // memory at `addr` holds `val` once the program halts or waits for input
+    Memory { addr: i64, val: i64 },
+    // output number `index` (from zero) is `val`
+    Output { index: usize, val: i64 },
+    // the instruction at `pc` is about to run
+    Reach { pc: i64 },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Solution {
+    values: Vec<i64>,
+}
+
+impl Solution {
+    pub fn get(&self, sym: Sym) -> i64 {
+        self.values[sym.0]
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SolveError {
+    // every path was explored and none of them can reach the goal
+    Unsatisfiable,
+    // nothing was found, but some paths were abandoned or too big to search
+    Unknown(String),
+}
+
+// monomial (sorted variable ids, repeated for powers) -> coefficient
+#[derive(Debug, Clone, PartialEq, Default)]
+struct Poly(BTreeMap<Vec<usize>, i128>);
+
+impl Poly {
+    fn constant(val: i128) -> Poly {
+        let mut terms = BTreeMap::new();
+        if val != 0 {
+            terms.insert(Vec::new(), val);
+        }
+        Poly(terms)
+    }
+
+    fn var(id: usize) -> Poly {
+        let mut terms = BTreeMap::new();
+        terms.insert(vec![id], 1);
+        Poly(terms)
+    }
+
+    fn as_constant(&self) -> Option<i128> {
+        match self.0.len() {
+            0 => Some(0),
+            1 => self.0.get(&Vec::new()).copied(),
+            _ => None,
+        }
+    }
+
+    fn as_i64(&self) -> Option<i64> {
+        self.as_constant().and_then(|val| std::convert::TryFrom::try_from(val).ok())
+    }
+
+    fn insert(&mut self, mono: Vec<usize>, coef: i128) -> Option<()> {
+        let entry = self.0.entry(mono.clone()).or_insert(0);
+        *entry = entry.checked_add(coef)?;
+        if *entry == 0 {
+            self.0.remove(&mono);
+        }
+        Some(())
+    }
+
+    fn add(&self, other: &Poly) -> Option<Poly> {
+        let mut out = self.clone();
+        for (mono, coef) in other.0.iter() {
+            out.insert(mono.clone(), *coef)?;
+        }
+        Some(out)
+    }
+
+    fn sub(&self, other: &Poly) -> Option<Poly> {
+        let mut out = self.clone();
+        for (mono, coef) in other.0.iter() {
+            out.insert(mono.clone(), coef.checked_neg()?)?;
+        }
+        Some(out)
+    }
+
+    fn mul(&self, other: &Poly) -> Option<Poly> {
+        let mut out = Poly::default();
+        for (a, x) in self.0.iter() {
+            for (b, y) in other.0.iter() {
+                let mut mono = a.clone();
+                mono.extend_from_slice(b);
+                mono.sort_unstable();
+                out.insert(mono, x.checked_mul(*y)?)?;
+            }
+        }
+        Some(out)
+    }
+
+    fn vars(&self) -> impl Iterator<Item = usize> + '_ {
+        self.0.keys().flatten().copied()
+    }
+
+    // substitutes every assigned variable
+    fn partial(&self, assigned: &[Option<i128>]) -> Option<Poly> {
+        let mut out = Poly::default();
+        for (mono, coef) in self.0.iter() {
+            let mut coef = *coef;
+            let mut rest = Vec::new();
+            for var in mono {
+                match assigned[*var] {
+                    Some(val) => coef = coef.checked_mul(val)?,
+                    None => rest.push(*var),
+                }
+            }
+            out.insert(rest, coef)?;
+        }
+        Some(out)
+    }
+
+    // `Some((var, a, c))` if the polynomial is a * var + c
+    fn linear(&self) -> Option<(usize, i128, i128)> {
+        let mut linear = None;
+        let mut c = 0;
+        for (mono, coef) in self.0.iter() {
+            match (mono.len(), linear) {
+                (0, _) => c = *coef,
+                (1, None) => linear = Some((mono[0], *coef)),
+                _ => return None,
+            }
+        }
+        linear.map(|(var, a)| (var, a, c))
+    }
+
+    // interval the polynomial can range over given each variable's domain
+    fn bounds(&self, domains: &[(i128, i128)]) -> Option<(i128, i128)> {
+        let (mut lo, mut hi) = (0i128, 0i128);
+        for (mono, coef) in self.0.iter() {
+            let (mut a, mut b) = (*coef, *coef);
+            for var in mono {
+                let (c, d) = domains[*var];
+                let products = [a.checked_mul(c)?, a.checked_mul(d)?, b.checked_mul(c)?, b.checked_mul(d)?];
+                a = *products.iter().min()?;
+                b = *products.iter().max()?;
+            }
+            lo = lo.checked_add(a)?;
+            hi = hi.checked_add(b)?;
+        }
+        Some((lo, hi))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Rel {
+    Eq,
+    Ne,
+    Lt,
+    Ge,
+}
+
+impl Rel {
+    fn holds(self, val: i128) -> bool {
+        match self {
+            Rel::Eq => val == 0,
+            Rel::Ne => val != 0,
+            Rel::Lt => val < 0,
+            Rel::Ge => val >= 0,
+        }
+    }
+
+    fn possible(self, lo: i128, hi: i128) -> bool {
+        match self {
+            Rel::Eq => lo <= 0 && hi >= 0,
+            Rel::Ne => !(lo == 0 && hi == 0),
+            Rel::Lt => lo < 0,
+            Rel::Ge => hi >= 0,
+        }
+    }
+}
+
+// `poly rel 0`
+#[derive(Debug, Clone, PartialEq)]
+struct Constraint {
+    poly: Poly,
+    rel: Rel,
+}
+
+fn div_floor(a: i128, b: i128) -> i128 {
+    let q = a / b;
+    if (a % b != 0) && ((a < 0) != (b < 0)) { q - 1 } else { q }
+}
+
+fn div_ceil(a: i128, b: i128) -> i128 {
+    let q = a / b;
+    if (a % b != 0) && ((a < 0) == (b < 0)) { q + 1 } else { q }
+}
+
+// Backtracking search over bounded integer domains. Constraints that are linear in a single
+// unassigned variable narrow its domain directly (which is all day 2 needs), interval
+// arithmetic prunes the rest, and whatever is left gets enumerated smallest domain first.
+// `accept` gets every full assignment and says whether to stop.
+struct Solver<'a> {
+    constraints: &'a [Constraint],
+    // nodes left before giving up
+    budget: usize,
+    exhausted: bool,
+}
+
+impl<'a> Solver<'a> {
+    fn search<F>(&mut self, mut domains: Vec<(i128, i128)>, accept: &mut F) -> Option<Vec<i128>>
+    where F: FnMut(&[i128]) -> bool {
+        if self.budget == 0 {
+            self.exhausted = true;
+            return None;
+        }
+        self.budget -= 1;
+        let mut changed = true;
+        while changed {
+            changed = false;
+            let assigned: Vec<Option<i128>> = domains.iter()
+                .map(|(lo, hi)| if lo == hi { Some(*lo) } else { None })
+                .collect();
+            for constraint in self.constraints {
+                let poly = match constraint.poly.partial(&assigned) {
+                    Some(poly) => poly,
+                    None => continue,
+                };
+                if let Some((lo, hi)) = poly.bounds(&domains) {
+                    if !constraint.rel.possible(lo, hi) {
+                        return None;
+                    }
+                }
+                let (var, a, c) = match poly.linear() {
+                    Some(linear) => linear,
+                    None => continue,
+                };
+                let (lo, hi) = domains[var];
+                let (new_lo, new_hi) = match constraint.rel {
+                    Rel::Eq if c % a != 0 => return None,
+                    Rel::Eq => (-c / a, -c / a),
+                    // a * x + c < 0, i.e. a * x <= -c - 1
+                    Rel::Lt if a > 0 => (lo, hi.min(div_floor(-c - 1, a))),
+                    Rel::Lt => (lo.max(div_ceil(-c - 1, a)), hi),
+                    Rel::Ge if a > 0 => (lo.max(div_ceil(-c, a)), hi),
+                    Rel::Ge => (lo, hi.min(div_floor(-c, a))),
+                    Rel::Ne if c % a != 0 => (lo, hi),
+                    Rel::Ne if lo == -c / a => (lo + 1, hi),
+                    Rel::Ne if hi == -c / a => (lo, hi - 1),
+                    Rel::Ne => (lo, hi),
+                };
+                let (new_lo, new_hi) = (new_lo.max(lo), new_hi.min(hi));
+                if new_lo > new_hi {
+                    return None;
+                }
+                if (new_lo, new_hi) != (lo, hi) {
+                    domains[var] = (new_lo, new_hi);
+                    changed = true;
+                }
+            }
+        }
+
+        // variables no constraint mentions can be anything; stay close to zero
+        let mut free: Vec<bool> = vec![true; domains.len()];
+        for constraint in self.constraints {
+            for var in constraint.poly.vars() {
+                free[var] = false;
+            }
+        }
+        let next = (0..domains.len())
+            .filter(|var| !free[*var] && domains[*var].0 != domains[*var].1)
+            .min_by_key(|var| domains[*var].1 - domains[*var].0);
+        let var = match next {
+            Some(var) => var,
+            None => {
+                let values: Vec<i128> = domains.iter().map(|(lo, hi)| 0.clamp(*lo, *hi)).collect();
+                let satisfied = self.constraints.iter().all(|constraint| {
+                    let assigned: Vec<Option<i128>> = values.iter().copied().map(Some).collect();
+                    constraint.poly.partial(&assigned)
+                        .and_then(|poly| poly.as_constant())
+                        .is_some_and(|val| constraint.rel.holds(val))
+                });
+                return match satisfied && accept(&values) {
+                    true => Some(values),
+                    false => None,
+                };
+            },
+        };
+        let (lo, hi) = domains[var];
+        if hi - lo >= ENUMERATION_LIMIT {
+            self.exhausted = true;
+            return None;
+        }
+        for val in lo..=hi {
+            let mut domains = domains.clone();
+            domains[var] = (val, val);
+            if let Some(values) = self.search(domains, accept) {
+                return Some(values);
+            }
+            if self.budget == 0 {
+                break;
+            }
+        }
+        None
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Input {
+    Value(i64),
+    Symbol(Sym),
+}
+
+#[derive(Debug, Clone)]
+struct Path {
+    mem: FxHashMap<i64, Poly>,
+    pc: i64,
+    rb: i64,
+    // how many inputs have been read
+    read: usize,
+    outputs: Vec<Poly>,
+    constraints: Vec<Constraint>,
+    steps: usize,
+}
+
+enum End {
+    Stopped,
+    // a fork left this side of it infeasible
+    Dead,
+    Abandoned(String),
+}
+
+pub struct Symbolic {
+    image: Vec<i64>,
+    symbols: Vec<(String, RangeInclusive<i64>)>,
+    cells: Vec<(i64, Sym)>,
+    inputs: Vec<Input>,
+    max_steps: usize,
+    max_paths: usize,
+}
+
+impl Symbolic {
+    pub fn new(image: Vec<i64>) -> Symbolic {
+        Symbolic {
+            image,
+            symbols: Vec::new(),
+            cells: Vec::new(),
+            inputs: Vec::new(),
+            max_steps: 100_000,
+            max_paths: 10_000,
+        }
+    }
+
+    // per-path instruction limit, so a loop on a symbolic bound can't run forever
+    pub fn with_max_steps(mut self, max_steps: usize) -> Symbolic {
+        self.max_steps = max_steps;
+        self
+    }
+
+    pub fn with_max_paths(mut self, max_paths: usize) -> Symbolic {
+        self.max_paths = max_paths;
+        self
+    }
+
+    fn symbol(&mut self, name: &str, range: RangeInclusive<i64>) -> Sym {
+        self.symbols.push((name.to_string(), range));
+        Sym(self.symbols.len() - 1)
+    }
+
+    // replaces the memory cell at `addr` with a symbol
+    pub fn symbolic_cell(&mut self, addr: i64, name: &str, range: RangeInclusive<i64>) -> Sym {
+        let sym = self.symbol(name, range);
+        self.cells.push((addr, sym));
+        sym
+    }
+
+    pub fn input(&mut self, val: i64) {
+        self.inputs.push(Input::Value(val));
+    }
+
+    pub fn symbolic_input(&mut self, name: &str, range: RangeInclusive<i64>) -> Sym {
+        let sym = self.symbol(name, range);
+        self.inputs.push(Input::Symbol(sym));
+        sym
+    }
+
+    pub fn name(&self, sym: Sym) -> &str {
+        &self.symbols[sym.0].0
+    }
+
+    pub fn solve(&self, goal: Goal) -> Result<Solution, SolveError> {
+        let mut mem: FxHashMap<i64, Poly> = self.image.iter()
+            .enumerate()
+            .map(|(addr, val)| (addr as i64, Poly::constant(*val as i128)))
+            .collect();
+        for (addr, sym) in self.cells.iter() {
+            mem.insert(*addr, Poly::var(sym.0));
+        }
+        let mut work = vec![Path { mem, pc: 0, rb: 0, read: 0, outputs: Vec::new(), constraints: Vec::new(), steps: 0 }];
+        // opaque values get variable ids after the symbols
+        let mut opaque = self.symbols.len();
+        let mut reason = None;
+        let mut explored = 0;
+        while let Some(mut path) = work.pop() {
+            explored += 1;
+            if explored > self.max_paths {
+                reason = Some(format!("gave up after {} paths", self.max_paths));
+                break;
+            }
+            match self.explore(&mut path, goal, &mut work, &mut opaque) {
+                End::Dead => (),
+                End::Abandoned(why) => {
+                    reason.get_or_insert(why);
+                },
+                End::Stopped => {
+                    match self.satisfy(&path, goal, opaque) {
+                        Ok(solution) => return Ok(solution),
+                        Err(Some(why)) => {
+                            reason.get_or_insert(why);
+                        },
+                        Err(None) => (),
+                    }
+                },
+            }
+        }
+        Err(reason.map(SolveError::Unknown).unwrap_or(SolveError::Unsatisfiable))
+    }
+
+    // runs a path until it stops, pushing the other side of every fork onto `work`
+    fn explore(&self, path: &mut Path, goal: Goal, work: &mut Vec<Path>, opaque: &mut usize) -> End {
+        loop {
+            if let Goal::Reach { pc } = goal {
+                if path.pc == pc {
+                    return End::Stopped;
+                }
+            }
+            if path.steps >= self.max_steps {
+                return End::Abandoned(format!("path ran past {} steps", self.max_steps));
+            }
+            path.steps += 1;
+            let word = match self.load(path, path.pc).as_i64() {
+                Some(word) => word,
+                None => return End::Abandoned(format!("symbolic instruction at {}", path.pc)),
+            };
+            // operands can be symbolic, so only the opcode word goes through the decoder
+            let instr = match instruction::decode(&[word, 0, 0, 0][..], 0) {
+                Ok(instr) => instr,
+                Err(_) => return End::Abandoned(format!("unsupported opcode {} at {}", word, path.pc)),
+            };
+            let (op, size) = (instr.op, instr.size());
+            let modes: Vec<ParamMode> = instr.operands.iter().map(|operand| operand.mode).collect();
+            let params: Vec<Poly> = (1..4).map(|i| self.load(path, path.pc + i)).collect();
+            // resolves parameter `i` to its value, or its address if it's written to
+            let addr = |path: &Path, i: usize| -> Option<i64> {
+                let raw = params[i].as_i64()?;
+                match modes[i] {
+                    ParamMode::Position => Some(raw),
+                    ParamMode::Relative => raw.checked_add(path.rb),
+                    ParamMode::Immediate => None,
+                }
+            };
+            let fetch = |path: &Path, i: usize, opaque: &mut usize| -> Poly {
+                if modes[i] == ParamMode::Immediate {
+                    return params[i].clone();
+                }
+                match addr(path, i) {
+                    Some(at) => self.load(path, at),
+                    None => {
+                        *opaque += 1;
+                        Poly::var(*opaque - 1)
+                    },
+                }
+            };
+            match op {
+                Op::Add | Op::Mul | Op::LessThan | Op::Equals => {
+                    let (a, b) = (fetch(path, 0, opaque), fetch(path, 1, opaque));
+                    let out = match addr(path, 2) {
+                        Some(out) => out,
+                        None => return End::Abandoned(format!("symbolic write address at {}", path.pc)),
+                    };
+                    let val = match op {
+                        Op::Add => a.add(&b),
+                        Op::Mul => a.mul(&b),
+                        _ => {
+                            let diff = match a.sub(&b) {
+                                Some(diff) => diff,
+                                None => return End::Abandoned("coefficient overflow".to_string()),
+                            };
+                            let (yes, no) = match op {
+                                Op::LessThan => (Rel::Lt, Rel::Ge),
+                                _ => (Rel::Eq, Rel::Ne),
+                            };
+                            match diff.as_constant() {
+                                Some(diff) => Some(Poly::constant(yes.holds(diff) as i128)),
+                                None => {
+                                    let mut other = path.clone();
+                                    other.mem.insert(out, Poly::constant(0));
+                                    other.pc += size;
+                                    if self.fork(&mut other, Constraint { poly: diff.clone(), rel: no }, *opaque) {
+                                        work.push(other);
+                                    }
+                                    if !self.fork(path, Constraint { poly: diff, rel: yes }, *opaque) {
+                                        return End::Dead;
+                                    }
+                                    Some(Poly::constant(1))
+                                },
+                            }
+                        },
+                    };
+                    match val {
+                        Some(val) => path.mem.insert(out, val),
+                        None => return End::Abandoned("coefficient overflow".to_string()),
+                    };
+                    path.pc += size;
+                },
+                Op::Read => {
+                    let to = match addr(path, 0) {
+                        Some(to) => to,
+                        None => return End::Abandoned(format!("symbolic write address at {}", path.pc)),
+                    };
+                    let val = match self.inputs.get(path.read) {
+                        Some(Input::Value(val)) => Poly::constant(*val as i128),
+                        Some(Input::Symbol(sym)) => Poly::var(sym.0),
+                        None => return End::Stopped,
+                    };
+                    path.read += 1;
+                    path.mem.insert(to, val);
+                    path.pc += size;
+                },
+                Op::Write => {
+                    let val = fetch(path, 0, opaque);
+                    path.outputs.push(val);
+                    path.pc += size;
+                },
+                Op::JumpIfTrue | Op::JumpIfFalse => {
+                    let cond = fetch(path, 0, opaque);
+                    let to = match fetch(path, 1, opaque).as_i64() {
+                        Some(to) => to,
+                        None => return End::Abandoned(format!("symbolic jump target at {}", path.pc)),
+                    };
+                    let (taken, skipped) = match op {
+                        Op::JumpIfTrue => (Rel::Ne, Rel::Eq),
+                        _ => (Rel::Eq, Rel::Ne),
+                    };
+                    match cond.as_constant() {
+                        Some(cond) if taken.holds(cond) => path.pc = to,
+                        Some(_) => path.pc += size,
+                        None => {
+                            let mut other = path.clone();
+                            other.pc += size;
+                            if self.fork(&mut other, Constraint { poly: cond.clone(), rel: skipped }, *opaque) {
+                                work.push(other);
+                            }
+                            if !self.fork(path, Constraint { poly: cond, rel: taken }, *opaque) {
+                                return End::Dead;
+                            }
+                            path.pc = to;
+                        },
+                    }
+                },
+                Op::UpdateRb => {
+                    match fetch(path, 0, opaque).as_i64().and_then(|offset| offset.checked_add(path.rb)) {
+                        Some(rb) => path.rb = rb,
+                        None => return End::Abandoned(format!("symbolic relative base at {}", path.pc)),
+                    }
+                    path.pc += size;
+                },
+                Op::Halt => return End::Stopped,
+                Op::Syscall => return End::Abandoned(format!("unsupported opcode {} at {}", word, path.pc)),
+            }
+        }
+    }
+
+    fn load(&self, path: &Path, addr: i64) -> Poly {
+        path.mem.get(&addr).cloned().unwrap_or_default()
+    }
+
+    // adds a constraint to a path, false if the path is then obviously infeasible
+    fn fork(&self, path: &mut Path, constraint: Constraint, vars: usize) -> bool {
+        let feasible = constraint.poly.bounds(&self.domains(vars))
+            .is_none_or(|(lo, hi)| constraint.rel.possible(lo, hi));
+        path.constraints.push(constraint);
+        feasible
+    }
+
+    // domains for `vars` variables: the symbols' ranges, then full width for opaque values
+    fn domains(&self, vars: usize) -> Vec<(i128, i128)> {
+        let full = (i64::MIN as i128, i64::MAX as i128);
+        let mut domains: Vec<(i128, i128)> = self.symbols.iter()
+            .map(|(_, range)| (*range.start() as i128, *range.end() as i128))
+            .collect();
+        domains.resize(vars.max(domains.len()), full);
+        domains
+    }
+
+    // Err(None) means no solution on this path, Err(Some(reason)) that the search gave up
+    fn satisfy(&self, path: &Path, goal: Goal, vars: usize) -> Result<Solution, Option<String>> {
+        let mut constraints = path.constraints.clone();
+        let target = match goal {
+            Goal::Memory { addr, val } => self.load(path, addr).sub(&Poly::constant(val as i128)),
+            Goal::Output { index, val } => match path.outputs.get(index) {
+                Some(out) => out.sub(&Poly::constant(val as i128)),
+                None => return Err(None),
+            },
+            Goal::Reach { .. } => Some(Poly::default()),
+        };
+        match target {
+            Some(poly) => constraints.push(Constraint { poly, rel: Rel::Eq }),
+            None => return Err(Some("coefficient overflow".to_string())),
+        }
+        let mut solver = Solver { constraints: &constraints, budget: 1_000_000, exhausted: false };
+        let found = solver.search(self.domains(vars), &mut |values| self.verify(values, goal));
+        match found {
+            Some(values) => Ok(Solution {
+                values: values[..self.symbols.len()].iter().map(|val| *val as i64).collect(),
+            }),
+            None if solver.exhausted => Err(Some("solver search space too large".to_string())),
+            None => Err(None),
+        }
+    }
+
+    // runs the candidate for real
+    fn verify(&self, values: &[i128], goal: Goal) -> bool {
+        let val = |sym: &Sym| values[sym.0] as i64;
+        let mut image = self.image.clone();
+        for (addr, sym) in self.cells.iter() {
+            match image.get_mut(*addr as usize) {
+                Some(cell) => *cell = val(sym),
+                None => return false,
+            }
+        }
+        let inputs = self.inputs.iter()
+            .map(|input| match input {
+                Input::Value(v) => *v,
+                Input::Symbol(sym) => val(sym),
+            })
+            .collect();
+        let mut comp = ProcIntCode::new(image, inputs);
+        let mut steps = 0;
+        loop {
+            if let Goal::Reach { pc } = goal {
+                if comp.pc() == pc {
+                    return true;
+                }
+            }
+            if steps > self.max_steps {
+                return false;
+            }
+            steps += 1;
+            match comp.step() {
+                State::Running => continue,
+                State::Halted | State::Waiting => break,
+                State::Faulted(_) => return false,
+            }
+        }
+        match goal {
+            Goal::Memory { addr, val } => comp.mem(addr) == val,
+            Goal::Output { index, val } => comp.out().get(index) == Some(&val),
+            Goal::Reach { .. } => false,
+        }
+    }
+}
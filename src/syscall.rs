@@ -0,0 +1,125 @@
+// Host calls ("syscalls") let a guest program ask the machine it runs on for services
+// without going through the input/output queues.
+//
+// Calling convention:
+//   - opcode 80 (`SYSCALL`) takes no parameters and occupies a single cell
+//   - [rb+0] holds the service number
+//   - [rb+1] holds the number of arguments, n
+//   - [rb+2] .. [rb+2+n] hold the arguments
+//   - the handler's return value is written back to [rb+0]
+//
+// A guest typically bumps the relative base to a scratch frame, fills it in, executes 80,
+// reads [rb+0] and restores the relative base. Handlers can also read and write guest
+// memory directly through the `Frame` (e.g. to copy a named blob into a buffer), and
+// report failure with an `Err`, which faults the machine. Calling a service no handler is
+// registered for faults too. The service number and argument count have to fit in an i64,
+// the same as an address, and so does the frame's last cell; the arguments and return
+// value are full machine words. Arguments are read from guest memory when the handler asks
+// for them, so a bogus argument count costs nothing until something iterates over it.
+use fxhash::FxHashMap;
+use std::fmt::{self, Debug};
+use std::sync::Arc;
+use crate::{Fault, Word};
+use crate::memory::Memory;
+
+pub const SYSCALL: i64 = 80;
+
+pub struct Frame<'a, W: Word = i64> {
+    mem: &'a mut Memory<W>,
+    service: i64,
+    rb: i64,
+    argc: i64,
+}
+
+impl<'a, W: Word> Frame<'a, W> {
+    // `None` if the service number or argument count don't fit in an i64, or the frame
+    // runs past the end of the address space
+    pub(crate) fn new(mem: &'a mut Memory<W>, rb: i64) -> Option<Frame<'a, W>> {
+        let service = mem.get(rb).to_i64()?;
+        let argc = mem.get(rb.checked_add(1)?).to_i64()?.max(0);
+        rb.checked_add(2)?.checked_add(argc)?;
+        Some(Frame { mem, service, rb, argc })
+    }
+
+    pub fn service(&self) -> i64 {
+        self.service
+    }
+
+    pub fn argc(&self) -> i64 {
+        self.argc
+    }
+
+    pub fn args(&self) -> impl Iterator<Item = W> + '_ {
+        (0..self.argc).map(move |i| self.read(self.rb + 2 + i))
+    }
+
+    // missing arguments read as zero, same as untouched memory
+    pub fn arg(&self, i: usize) -> W {
+        if (i as u64) < self.argc as u64 {
+            self.read(self.rb + 2 + i as i64)
+        } else {
+            W::default()
+        }
+    }
+
+    // host accesses bypass memory protection
+    pub fn read(&self, addr: i64) -> W {
+        self.mem.get(addr)
+    }
+
+    pub fn read_slice(&self, addr: i64, len: i64) -> Vec<W> {
+        (addr..addr + len).map(|a| self.read(a)).collect()
+    }
+
+    pub fn write(&mut self, addr: i64, val: W) {
+        self.mem.set(addr, val);
+    }
+
+    pub fn write_slice(&mut self, addr: i64, vals: &[W]) {
+        for (i, val) in vals.iter().enumerate() {
+            self.write(addr + i as i64, val.clone());
+        }
+    }
+}
+
+pub type Handler<W = i64> = Arc<dyn Fn(&mut Frame<W>) -> Result<W, String> + Send + Sync>;
+
+#[derive(Clone)]
+pub struct Syscalls<W: Word = i64> {
+    handlers: FxHashMap<i64, Handler<W>>,
+}
+
+impl<W: Word> Default for Syscalls<W> {
+    fn default() -> Syscalls<W> {
+        Syscalls { handlers: FxHashMap::default() }
+    }
+}
+
+impl<W: Word> Syscalls<W> {
+    pub fn register<F>(&mut self, service: i64, handler: F)
+    where F: Fn(&mut Frame<W>) -> Result<W, String> + Send + Sync + 'static {
+        self.handlers.insert(service, Arc::new(handler));
+    }
+
+    pub fn get(&self, service: i64) -> Option<Handler<W>> {
+        self.handlers.get(&service).cloned()
+    }
+}
+
+impl<W: Word> Debug for Syscalls<W> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut services: Vec<&i64> = self.handlers.keys().collect();
+        services.sort();
+        f.debug_struct("Syscalls").field("services", &services).finish()
+    }
+}
+
+// runs the service the frame at `rb` asks for and stores its result in [rb+0]
+pub(crate) fn dispatch<W: Word>(syscalls: &Syscalls<W>, mem: &mut Memory<W>, pc: i64, rb: i64) -> Result<(), Fault> {
+    let mut frame = Frame::new(mem, rb).ok_or(Fault::Address { pc })?;
+    let service = frame.service();
+    let handler = syscalls.get(service).ok_or(Fault::UnknownService { pc, service })?;
+    let ret = handler(&mut frame).map_err(|msg| Fault::Service { pc, service, msg })?;
+    frame.write(rb, ret);
+    Ok(())
+}
@@ -0,0 +1,80 @@
+use std::fmt::{Debug, Display};
+use std::hash::Hash;
+use std::str::FromStr;
+use crate::{Arith, Overflow};
+use crate::bigint::BigInt;
+
+// The type a machine stores in each memory cell and passes through its input/output queues.
+// Addresses, the program counter and the relative base stay i64 no matter the word type;
+// a word used as one of those has to fit or the machine faults.
+pub trait Word: Clone + Debug + Display + FromStr + Eq + Ord + Hash + Default + From<i64> + Send + Sync + 'static {
+    fn to_i64(&self) -> Option<i64>;
+
+    fn to_i128(&self) -> Option<i128>;
+
+    // `None` means the result overflowed under `Overflow::Checked`
+    fn arith(op: Arith, overflow: Overflow, a: &Self, b: &Self) -> Option<Self>;
+
+    fn is_zero(&self) -> bool {
+        *self == Self::default()
+    }
+
+    fn bool(val: bool) -> Self {
+        Self::from(val as i64)
+    }
+
+    // bytes the word owns on the heap, on top of its inline size
+    fn heap_bytes(&self) -> usize {
+        0
+    }
+}
+
+macro_rules! primitive_word {
+    ($t:ty) => {
+        impl Word for $t {
+            fn to_i64(&self) -> Option<i64> {
+                std::convert::TryFrom::try_from(*self).ok()
+            }
+
+            fn to_i128(&self) -> Option<i128> {
+                Some(*self as i128)
+            }
+
+            fn arith(op: Arith, overflow: Overflow, a: &$t, b: &$t) -> Option<$t> {
+                match (overflow, op) {
+                    (Overflow::Wrapping, Arith::Add) => Some(a.wrapping_add(*b)),
+                    (Overflow::Wrapping, Arith::Mul) => Some(a.wrapping_mul(*b)),
+                    (Overflow::Checked, Arith::Add) => a.checked_add(*b),
+                    (Overflow::Checked, Arith::Mul) => a.checked_mul(*b),
+                    (Overflow::Saturating, Arith::Add) => Some(a.saturating_add(*b)),
+                    (Overflow::Saturating, Arith::Mul) => Some(a.saturating_mul(*b)),
+                }
+            }
+        }
+    };
+}
+
+primitive_word!(i64);
+primitive_word!(i128);
+
+// big integers can't overflow, so the policy doesn't matter
+impl Word for BigInt {
+    fn to_i64(&self) -> Option<i64> {
+        self.to_i64()
+    }
+
+    fn to_i128(&self) -> Option<i128> {
+        self.to_i128()
+    }
+
+    fn arith(op: Arith, _overflow: Overflow, a: &BigInt, b: &BigInt) -> Option<BigInt> {
+        match op {
+            Arith::Add => Some(a + b),
+            Arith::Mul => Some(a * b),
+        }
+    }
+
+    fn heap_bytes(&self) -> usize {
+        self.heap_bytes()
+    }
+}
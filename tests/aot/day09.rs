@@ -0,0 +1,789 @@
+// Generated by intcode_rs::aot from a 973-cell image. Don't edit by hand.
+pub struct Day9 {
+    rt: ::intcode_rs::aot::Runtime,
+}
+
+#[allow(dead_code)]
+impl Day9 {
+    const IMAGE: [i64; 973] = [
+        1102, 34463338, 34463338, 63, 1007, 63, 34463338, 63, 1005, 63, 53, 1101, 0, 3, 1000, 109,
+        988, 209, 12, 9, 1000, 209, 6, 209, 3, 203, 0, 1008, 1000, 1, 63, 1005,
+        63, 65, 1008, 1000, 2, 63, 1005, 63, 904, 1008, 1000, 0, 63, 1005, 63, 58,
+        4, 25, 104, 0, 99, 4, 0, 104, 0, 99, 4, 17, 104, 0, 99, 0,
+        0, 1101, 0, 36, 1015, 1102, 1, 387, 1028, 1101, 24, 0, 1016, 1101, 0, 23,
+        1008, 1102, 1, 35, 1012, 1102, 1, 554, 1023, 1101, 29, 0, 1003, 1101, 27, 0,
+        1011, 1101, 25, 0, 1000, 1101, 0, 38, 1018, 1102, 20, 1, 1019, 1102, 28, 1,
+        1005, 1102, 1, 619, 1026, 1102, 1, 22, 1004, 1101, 0, 0, 1020, 1101, 0, 31,
+        1009, 1102, 1, 783, 1024, 1102, 1, 33, 1001, 1102, 616, 1, 1027, 1102, 1, 21,
+        1006, 1101, 32, 0, 1013, 1102, 39, 1, 1014, 1102, 1, 378, 1029, 1101, 774, 0,
+        1025, 1102, 1, 1, 1021, 1102, 30, 1, 1007, 1102, 37, 1, 1002, 1102, 1, 26,
+        1017, 1101, 0, 557, 1022, 1102, 1, 34, 1010, 109, 13, 2101, 0, -5, 63, 1008,
+        63, 23, 63, 1005, 63, 203, 4, 187, 1105, 1, 207, 1001, 64, 1, 64, 1002,
+        64, 2, 64, 109, -14, 2107, 28, 4, 63, 1005, 63, 225, 4, 213, 1106, 0,
+        229, 1001, 64, 1, 64, 1002, 64, 2, 64, 109, 10, 1207, -3, 20, 63, 1005,
+        63, 245, 1106, 0, 251, 4, 235, 1001, 64, 1, 64, 1002, 64, 2, 64, 109,
+        8, 1205, 3, 263, 1105, 1, 269, 4, 257, 1001, 64, 1, 64, 1002, 64, 2,
+        64, 109, -9, 1207, -7, 34, 63, 1005, 63, 287, 4, 275, 1105, 1, 291, 1001,
+        64, 1, 64, 1002, 64, 2, 64, 109, -4, 2102, 1, -3, 63, 1008, 63, 32,
+        63, 1005, 63, 311, 1105, 1, 317, 4, 297, 1001, 64, 1, 64, 1002, 64, 2,
+        64, 109, 21, 21101, 40, 0, -6, 1008, 1019, 43, 63, 1005, 63, 337, 1106, 0,
+        343, 4, 323, 1001, 64, 1, 64, 1002, 64, 2, 64, 109, -26, 1202, 7, 1,
+        63, 1008, 63, 21, 63, 1005, 63, 365, 4, 349, 1106, 0, 369, 1001, 64, 1,
+        64, 1002, 64, 2, 64, 109, 26, 2106, 0, 3, 4, 375, 1001, 64, 1, 64,
+        1105, 1, 387, 1002, 64, 2, 64, 109, -9, 21108, 41, 40, 3, 1005, 1019, 407,
+        1001, 64, 1, 64, 1106, 0, 409, 4, 393, 1002, 64, 2, 64, 109, 13, 1205,
+        -8, 423, 4, 415, 1106, 0, 427, 1001, 64, 1, 64, 1002, 64, 2, 64, 109,
+        -19, 21107, 42, 41, 5, 1005, 1015, 447, 1001, 64, 1, 64, 1106, 0, 449, 4,
+        433, 1002, 64, 2, 64, 109, -3, 2102, 1, -5, 63, 1008, 63, 37, 63, 1005,
+        63, 471, 4, 455, 1105, 1, 475, 1001, 64, 1, 64, 1002, 64, 2, 64, 109,
+        -2, 1201, 0, 0, 63, 1008, 63, 28, 63, 1005, 63, 497, 4, 481, 1105, 1,
+        501, 1001, 64, 1, 64, 1002, 64, 2, 64, 109, 8, 2107, 29, -8, 63, 1005,
+        63, 521, 1001, 64, 1, 64, 1106, 0, 523, 4, 507, 1002, 64, 2, 64, 109,
+        -3, 1208, -3, 30, 63, 1005, 63, 541, 4, 529, 1106, 0, 545, 1001, 64, 1,
+        64, 1002, 64, 2, 64, 109, 4, 2105, 1, 9, 1105, 1, 563, 4, 551, 1001,
+        64, 1, 64, 1002, 64, 2, 64, 109, 9, 1206, -3, 581, 4, 569, 1001, 64,
+        1, 64, 1106, 0, 581, 1002, 64, 2, 64, 109, -8, 1201, -9, 0, 63, 1008,
+        63, 23, 63, 1005, 63, 605, 1001, 64, 1, 64, 1106, 0, 607, 4, 587, 1002,
+        64, 2, 64, 109, 21, 2106, 0, -9, 1106, 0, 625, 4, 613, 1001, 64, 1,
+        64, 1002, 64, 2, 64, 109, -35, 2108, 31, 8, 63, 1005, 63, 647, 4, 631,
+        1001, 64, 1, 64, 1105, 1, 647, 1002, 64, 2, 64, 109, 2, 1202, 0, 1,
+        63, 1008, 63, 30, 63, 1005, 63, 667, 1105, 1, 673, 4, 653, 1001, 64, 1,
+        64, 1002, 64, 2, 64, 109, 17, 21108, 43, 43, -4, 1005, 1016, 691, 4, 679,
+        1106, 0, 695, 1001, 64, 1, 64, 1002, 64, 2, 64, 109, -14, 1208, -1, 30,
+        63, 1005, 63, 711, 1106, 0, 717, 4, 701, 1001, 64, 1, 64, 1002, 64, 2,
+        64, 109, 6, 21101, 44, 0, -1, 1008, 1011, 44, 63, 1005, 63, 739, 4, 723,
+        1105, 1, 743, 1001, 64, 1, 64, 1002, 64, 2, 64, 109, -15, 2108, 30, 8,
+        63, 1005, 63, 759, 1106, 0, 765, 4, 749, 1001, 64, 1, 64, 1002, 64, 2,
+        64, 109, 27, 2105, 1, 0, 4, 771, 1001, 64, 1, 64, 1105, 1, 783, 1002,
+        64, 2, 64, 109, -9, 1206, 6, 795, 1105, 1, 801, 4, 789, 1001, 64, 1,
+        64, 1002, 64, 2, 64, 109, 4, 21102, 45, 1, -7, 1008, 1012, 45, 63, 1005,
+        63, 823, 4, 807, 1105, 1, 827, 1001, 64, 1, 64, 1002, 64, 2, 64, 109,
+        -14, 21102, 46, 1, 5, 1008, 1010, 43, 63, 1005, 63, 851, 1001, 64, 1, 64,
+        1105, 1, 853, 4, 833, 1002, 64, 2, 64, 109, -1, 2101, 0, 1, 63, 1008,
+        63, 25, 63, 1005, 63, 873, 1105, 1, 879, 4, 859, 1001, 64, 1, 64, 1002,
+        64, 2, 64, 109, 9, 21107, 47, 48, -3, 1005, 1010, 897, 4, 885, 1105, 1,
+        901, 1001, 64, 1, 64, 4, 64, 99, 21101, 0, 27, 1, 21101, 915, 0, 0,
+        1106, 0, 922, 21201, 1, 57526, 1, 204, 1, 99, 109, 3, 1207, -2, 3, 63,
+        1005, 63, 964, 21201, -2, -1, 1, 21101, 942, 0, 0, 1106, 0, 922, 21201, 1,
+        0, -1, 21201, -2, -3, 1, 21101, 957, 0, 0, 1106, 0, 922, 22201, 1, -1,
+        -2, 1105, 1, 968, 21202, -2, 1, -2, 109, -3, 2106, 0, 0,
+    ];
+    const CODE: [(i64, i64); 3] = [
+        (0, 63), (65, 378), (904, 973),
+    ];
+
+    pub fn new(inputs: Vec<i64>) -> Day9 {
+        let rt = ::intcode_rs::aot::Runtime::new(Day9::IMAGE.to_vec(), inputs, &Day9::CODE, Day9::native);
+        Day9 { rt }
+    }
+
+    pub fn runtime(&self) -> &::intcode_rs::aot::Runtime {
+        &self.rt
+    }
+
+    pub fn runtime_mut(&mut self) -> &mut ::intcode_rs::aot::Runtime {
+        &mut self.rt
+    }
+
+    fn native(rt: &mut ::intcode_rs::aot::Runtime) -> Result<::intcode_rs::State, ::intcode_rs::Fault> {
+        use ::intcode_rs::State;
+        loop {
+            match rt.pc() {
+                0 => {
+                    // 0: mul 34463338, 34463338, [63]
+                    rt.put(63, 1187721666102244);
+                    rt.retire();
+                    // 4: lt [63], 34463338, [63]
+                    rt.put(63, i64::from(rt.get(63) < 34463338));
+                    rt.retire();
+                    rt.goto(8);
+                },
+                8 => {
+                    // 8: jt [63], 53
+                    rt.retire();
+                    if rt.get(63) != 0 {
+                        rt.goto(53);
+                    } else {
+                        rt.goto(11);
+                    }
+                },
+                11 => {
+                    // 11: add 0, 3, [1000]
+                    rt.put(1000, 3);
+                    rt.retire();
+                    // 15: arb 988
+                    rt.shift(15, 988)?;
+                    rt.retire();
+                    // 17: arb [rb+12]
+                    rt.shift(17, rt.get(rt.rel(17, 12)?))?;
+                    rt.retire();
+                    // 19: arb [1000]
+                    rt.shift(19, rt.get(1000))?;
+                    rt.retire();
+                    rt.goto(21);
+                },
+                21 => {
+                    // 21: arb [rb+6]
+                    rt.shift(21, rt.get(rt.rel(21, 6)?))?;
+                    rt.retire();
+                    rt.goto(23);
+                },
+                23 => {
+                    // 23: arb [rb+3]
+                    rt.shift(23, rt.get(rt.rel(23, 3)?))?;
+                    rt.retire();
+                    rt.goto(25);
+                },
+                25 => {
+                    // 25: in [rb+0]
+                    match rt.input() {
+                        Some(val) => rt.put(rt.rel(25, 0)?, val),
+                        None => {
+                            rt.goto(25);
+                            return Ok(State::Waiting);
+                        },
+                    }
+                    rt.retire();
+                    if rt.stale() {
+                        rt.goto(27);
+                        return Ok(State::Running);
+                    }
+                    rt.goto(27);
+                },
+                27 => {
+                    // 27: eq [1000], 1, [63]
+                    rt.put(63, i64::from(rt.get(1000) == 1));
+                    rt.retire();
+                    rt.goto(31);
+                },
+                31 => {
+                    // 31: jt [63], 65
+                    rt.retire();
+                    if rt.get(63) != 0 {
+                        rt.goto(65);
+                    } else {
+                        rt.goto(34);
+                    }
+                },
+                34 => {
+                    // 34: eq [1000], 2, [63]
+                    rt.put(63, i64::from(rt.get(1000) == 2));
+                    rt.retire();
+                    rt.goto(38);
+                },
+                38 => {
+                    // 38: jt [63], 904
+                    rt.retire();
+                    if rt.get(63) != 0 {
+                        rt.goto(904);
+                    } else {
+                        rt.goto(41);
+                    }
+                },
+                41 => {
+                    // 41: eq [1000], 0, [63]
+                    rt.put(63, i64::from(rt.get(1000) == 0));
+                    rt.retire();
+                    // 45: jt [63], 58
+                    rt.retire();
+                    if rt.get(63) != 0 {
+                        rt.goto(58);
+                    } else {
+                        rt.goto(48);
+                    }
+                },
+                48 => {
+                    // 48: out [25]
+                    rt.output(rt.get(25));
+                    rt.retire();
+                    // 50: out 0
+                    rt.output(0);
+                    rt.retire();
+                    // 52: hlt
+                    rt.goto(52);
+                    return Ok(State::Halted);
+                },
+                53 => {
+                    // 53: out [0]
+                    rt.output(rt.get(0));
+                    rt.retire();
+                    // 55: out 0
+                    rt.output(0);
+                    rt.retire();
+                    // 57: hlt
+                    rt.goto(57);
+                    return Ok(State::Halted);
+                },
+                58 => {
+                    // 58: out [17]
+                    rt.output(rt.get(17));
+                    rt.retire();
+                    // 60: out 0
+                    rt.output(0);
+                    rt.retire();
+                    // 62: hlt
+                    rt.goto(62);
+                    return Ok(State::Halted);
+                },
+                65 => {
+                    // 65: add 0, 36, [1015]
+                    rt.put(1015, 36);
+                    rt.retire();
+                    // 69: mul 1, 387, [1028]
+                    rt.put(1028, 387);
+                    rt.retire();
+                    // 73: add 24, 0, [1016]
+                    rt.put(1016, 24);
+                    rt.retire();
+                    // 77: add 0, 23, [1008]
+                    rt.put(1008, 23);
+                    rt.retire();
+                    // 81: mul 1, 35, [1012]
+                    rt.put(1012, 35);
+                    rt.retire();
+                    // 85: mul 1, 554, [1023]
+                    rt.put(1023, 554);
+                    rt.retire();
+                    // 89: add 29, 0, [1003]
+                    rt.put(1003, 29);
+                    rt.retire();
+                    // 93: add 27, 0, [1011]
+                    rt.put(1011, 27);
+                    rt.retire();
+                    // 97: add 25, 0, [1000]
+                    rt.put(1000, 25);
+                    rt.retire();
+                    // 101: add 0, 38, [1018]
+                    rt.put(1018, 38);
+                    rt.retire();
+                    // 105: mul 20, 1, [1019]
+                    rt.put(1019, 20);
+                    rt.retire();
+                    // 109: mul 28, 1, [1005]
+                    rt.put(1005, 28);
+                    rt.retire();
+                    // 113: mul 1, 619, [1026]
+                    rt.put(1026, 619);
+                    rt.retire();
+                    // 117: mul 1, 22, [1004]
+                    rt.put(1004, 22);
+                    rt.retire();
+                    // 121: add 0, 0, [1020]
+                    rt.put(1020, 0);
+                    rt.retire();
+                    // 125: add 0, 31, [1009]
+                    rt.put(1009, 31);
+                    rt.retire();
+                    // 129: mul 1, 783, [1024]
+                    rt.put(1024, 783);
+                    rt.retire();
+                    // 133: mul 1, 33, [1001]
+                    rt.put(1001, 33);
+                    rt.retire();
+                    // 137: mul 616, 1, [1027]
+                    rt.put(1027, 616);
+                    rt.retire();
+                    // 141: mul 1, 21, [1006]
+                    rt.put(1006, 21);
+                    rt.retire();
+                    // 145: add 32, 0, [1013]
+                    rt.put(1013, 32);
+                    rt.retire();
+                    // 149: mul 39, 1, [1014]
+                    rt.put(1014, 39);
+                    rt.retire();
+                    // 153: mul 1, 378, [1029]
+                    rt.put(1029, 378);
+                    rt.retire();
+                    // 157: add 774, 0, [1025]
+                    rt.put(1025, 774);
+                    rt.retire();
+                    // 161: mul 1, 1, [1021]
+                    rt.put(1021, 1);
+                    rt.retire();
+                    // 165: mul 30, 1, [1007]
+                    rt.put(1007, 30);
+                    rt.retire();
+                    // 169: mul 37, 1, [1002]
+                    rt.put(1002, 37);
+                    rt.retire();
+                    // 173: mul 1, 26, [1017]
+                    rt.put(1017, 26);
+                    rt.retire();
+                    // 177: add 0, 557, [1022]
+                    rt.put(1022, 557);
+                    rt.retire();
+                    // 181: mul 1, 34, [1010]
+                    rt.put(1010, 34);
+                    rt.retire();
+                    // 185: arb 13
+                    rt.shift(185, 13)?;
+                    rt.retire();
+                    // 187: add 0, [rb-5], [63]
+                    rt.put(63, i64::wrapping_add(0, rt.get(rt.rel(187, -5)?)));
+                    rt.retire();
+                    // 191: eq [63], 23, [63]
+                    rt.put(63, i64::from(rt.get(63) == 23));
+                    rt.retire();
+                    // 195: jt [63], 203
+                    rt.retire();
+                    if rt.get(63) != 0 {
+                        rt.goto(203);
+                    } else {
+                        rt.goto(198);
+                    }
+                },
+                198 => {
+                    // 198: out [187]
+                    rt.output(rt.get(187));
+                    rt.retire();
+                    // 200: jt 1, 207
+                    rt.retire();
+                    rt.goto(207);
+                },
+                203 => {
+                    // 203: add [64], 1, [64]
+                    rt.put(64, i64::wrapping_add(rt.get(64), 1));
+                    rt.retire();
+                    rt.goto(207);
+                },
+                207 => {
+                    // 207: mul [64], 2, [64]
+                    rt.put(64, i64::wrapping_mul(rt.get(64), 2));
+                    rt.retire();
+                    // 211: arb -14
+                    rt.shift(211, -14)?;
+                    rt.retire();
+                    // 213: lt 28, [rb+4], [63]
+                    rt.put(63, i64::from(28 < rt.get(rt.rel(213, 4)?)));
+                    rt.retire();
+                    // 217: jt [63], 225
+                    rt.retire();
+                    if rt.get(63) != 0 {
+                        rt.goto(225);
+                    } else {
+                        rt.goto(220);
+                    }
+                },
+                220 => {
+                    // 220: out [213]
+                    rt.output(rt.get(213));
+                    rt.retire();
+                    // 222: jf 0, 229
+                    rt.retire();
+                    rt.goto(229);
+                },
+                225 => {
+                    // 225: add [64], 1, [64]
+                    rt.put(64, i64::wrapping_add(rt.get(64), 1));
+                    rt.retire();
+                    rt.goto(229);
+                },
+                229 => {
+                    // 229: mul [64], 2, [64]
+                    rt.put(64, i64::wrapping_mul(rt.get(64), 2));
+                    rt.retire();
+                    // 233: arb 10
+                    rt.shift(233, 10)?;
+                    rt.retire();
+                    // 235: lt [rb-3], 20, [63]
+                    rt.put(63, i64::from(rt.get(rt.rel(235, -3)?) < 20));
+                    rt.retire();
+                    // 239: jt [63], 245
+                    rt.retire();
+                    if rt.get(63) != 0 {
+                        rt.goto(245);
+                    } else {
+                        rt.goto(242);
+                    }
+                },
+                242 => {
+                    // 242: jf 0, 251
+                    rt.retire();
+                    rt.goto(251);
+                },
+                245 => {
+                    // 245: out [235]
+                    rt.output(rt.get(235));
+                    rt.retire();
+                    // 247: add [64], 1, [64]
+                    rt.put(64, i64::wrapping_add(rt.get(64), 1));
+                    rt.retire();
+                    rt.goto(251);
+                },
+                251 => {
+                    // 251: mul [64], 2, [64]
+                    rt.put(64, i64::wrapping_mul(rt.get(64), 2));
+                    rt.retire();
+                    // 255: arb 8
+                    rt.shift(255, 8)?;
+                    rt.retire();
+                    // 257: jt [rb+3], 263
+                    rt.retire();
+                    if rt.get(rt.rel(257, 3)?) != 0 {
+                        rt.goto(263);
+                    } else {
+                        rt.goto(260);
+                    }
+                },
+                260 => {
+                    // 260: jt 1, 269
+                    rt.retire();
+                    rt.goto(269);
+                },
+                263 => {
+                    // 263: out [257]
+                    rt.output(rt.get(257));
+                    rt.retire();
+                    // 265: add [64], 1, [64]
+                    rt.put(64, i64::wrapping_add(rt.get(64), 1));
+                    rt.retire();
+                    rt.goto(269);
+                },
+                269 => {
+                    // 269: mul [64], 2, [64]
+                    rt.put(64, i64::wrapping_mul(rt.get(64), 2));
+                    rt.retire();
+                    // 273: arb -9
+                    rt.shift(273, -9)?;
+                    rt.retire();
+                    // 275: lt [rb-7], 34, [63]
+                    rt.put(63, i64::from(rt.get(rt.rel(275, -7)?) < 34));
+                    rt.retire();
+                    // 279: jt [63], 287
+                    rt.retire();
+                    if rt.get(63) != 0 {
+                        rt.goto(287);
+                    } else {
+                        rt.goto(282);
+                    }
+                },
+                282 => {
+                    // 282: out [275]
+                    rt.output(rt.get(275));
+                    rt.retire();
+                    // 284: jt 1, 291
+                    rt.retire();
+                    rt.goto(291);
+                },
+                287 => {
+                    // 287: add [64], 1, [64]
+                    rt.put(64, i64::wrapping_add(rt.get(64), 1));
+                    rt.retire();
+                    rt.goto(291);
+                },
+                291 => {
+                    // 291: mul [64], 2, [64]
+                    rt.put(64, i64::wrapping_mul(rt.get(64), 2));
+                    rt.retire();
+                    // 295: arb -4
+                    rt.shift(295, -4)?;
+                    rt.retire();
+                    // 297: mul 1, [rb-3], [63]
+                    rt.put(63, i64::wrapping_mul(1, rt.get(rt.rel(297, -3)?)));
+                    rt.retire();
+                    // 301: eq [63], 32, [63]
+                    rt.put(63, i64::from(rt.get(63) == 32));
+                    rt.retire();
+                    // 305: jt [63], 311
+                    rt.retire();
+                    if rt.get(63) != 0 {
+                        rt.goto(311);
+                    } else {
+                        rt.goto(308);
+                    }
+                },
+                308 => {
+                    // 308: jt 1, 317
+                    rt.retire();
+                    rt.goto(317);
+                },
+                311 => {
+                    // 311: out [297]
+                    rt.output(rt.get(297));
+                    rt.retire();
+                    // 313: add [64], 1, [64]
+                    rt.put(64, i64::wrapping_add(rt.get(64), 1));
+                    rt.retire();
+                    rt.goto(317);
+                },
+                317 => {
+                    // 317: mul [64], 2, [64]
+                    rt.put(64, i64::wrapping_mul(rt.get(64), 2));
+                    rt.retire();
+                    // 321: arb 21
+                    rt.shift(321, 21)?;
+                    rt.retire();
+                    // 323: add 40, 0, [rb-6]
+                    rt.put(rt.rel(323, -6)?, 40);
+                    rt.retire();
+                    if rt.stale() {
+                        rt.goto(327);
+                        return Ok(State::Running);
+                    }
+                    // 327: eq [1019], 43, [63]
+                    rt.put(63, i64::from(rt.get(1019) == 43));
+                    rt.retire();
+                    // 331: jt [63], 337
+                    rt.retire();
+                    if rt.get(63) != 0 {
+                        rt.goto(337);
+                    } else {
+                        rt.goto(334);
+                    }
+                },
+                334 => {
+                    // 334: jf 0, 343
+                    rt.retire();
+                    rt.goto(343);
+                },
+                337 => {
+                    // 337: out [323]
+                    rt.output(rt.get(323));
+                    rt.retire();
+                    // 339: add [64], 1, [64]
+                    rt.put(64, i64::wrapping_add(rt.get(64), 1));
+                    rt.retire();
+                    rt.goto(343);
+                },
+                343 => {
+                    // 343: mul [64], 2, [64]
+                    rt.put(64, i64::wrapping_mul(rt.get(64), 2));
+                    rt.retire();
+                    // 347: arb -26
+                    rt.shift(347, -26)?;
+                    rt.retire();
+                    // 349: mul [rb+7], 1, [63]
+                    rt.put(63, i64::wrapping_mul(rt.get(rt.rel(349, 7)?), 1));
+                    rt.retire();
+                    // 353: eq [63], 21, [63]
+                    rt.put(63, i64::from(rt.get(63) == 21));
+                    rt.retire();
+                    // 357: jt [63], 365
+                    rt.retire();
+                    if rt.get(63) != 0 {
+                        rt.goto(365);
+                    } else {
+                        rt.goto(360);
+                    }
+                },
+                360 => {
+                    // 360: out [349]
+                    rt.output(rt.get(349));
+                    rt.retire();
+                    // 362: jf 0, 369
+                    rt.retire();
+                    rt.goto(369);
+                },
+                365 => {
+                    // 365: add [64], 1, [64]
+                    rt.put(64, i64::wrapping_add(rt.get(64), 1));
+                    rt.retire();
+                    rt.goto(369);
+                },
+                369 => {
+                    // 369: mul [64], 2, [64]
+                    rt.put(64, i64::wrapping_mul(rt.get(64), 2));
+                    rt.retire();
+                    // 373: arb 26
+                    rt.shift(373, 26)?;
+                    rt.retire();
+                    // 375: jf 0, [rb+3]
+                    rt.retire();
+                    rt.goto(rt.get(rt.rel(375, 3)?));
+                },
+                904 => {
+                    // 904: add 0, 27, [rb+1]
+                    rt.put(rt.rel(904, 1)?, 27);
+                    rt.retire();
+                    if rt.stale() {
+                        rt.goto(908);
+                        return Ok(State::Running);
+                    }
+                    // 908: add 915, 0, [rb+0]
+                    rt.put(rt.rel(908, 0)?, 915);
+                    rt.retire();
+                    if rt.stale() {
+                        rt.goto(912);
+                        return Ok(State::Running);
+                    }
+                    // 912: jf 0, 922
+                    rt.retire();
+                    rt.goto(922);
+                },
+                915 => {
+                    // 915: add [rb+1], 57526, [rb+1]
+                    rt.put(rt.rel(915, 1)?, i64::wrapping_add(rt.get(rt.rel(915, 1)?), 57526));
+                    rt.retire();
+                    if rt.stale() {
+                        rt.goto(919);
+                        return Ok(State::Running);
+                    }
+                    // 919: out [rb+1]
+                    rt.output(rt.get(rt.rel(919, 1)?));
+                    rt.retire();
+                    // 921: hlt
+                    rt.goto(921);
+                    return Ok(State::Halted);
+                },
+                922 => {
+                    // 922: arb 3
+                    rt.shift(922, 3)?;
+                    rt.retire();
+                    // 924: lt [rb-2], 3, [63]
+                    rt.put(63, i64::from(rt.get(rt.rel(924, -2)?) < 3));
+                    rt.retire();
+                    // 928: jt [63], 964
+                    rt.retire();
+                    if rt.get(63) != 0 {
+                        rt.goto(964);
+                    } else {
+                        rt.goto(931);
+                    }
+                },
+                931 => {
+                    // 931: add [rb-2], -1, [rb+1]
+                    rt.put(rt.rel(931, 1)?, i64::wrapping_add(rt.get(rt.rel(931, -2)?), -1));
+                    rt.retire();
+                    if rt.stale() {
+                        rt.goto(935);
+                        return Ok(State::Running);
+                    }
+                    // 935: add 942, 0, [rb+0]
+                    rt.put(rt.rel(935, 0)?, 942);
+                    rt.retire();
+                    if rt.stale() {
+                        rt.goto(939);
+                        return Ok(State::Running);
+                    }
+                    // 939: jf 0, 922
+                    rt.retire();
+                    rt.goto(922);
+                },
+                942 => {
+                    // 942: add [rb+1], 0, [rb-1]
+                    rt.put(rt.rel(942, -1)?, i64::wrapping_add(rt.get(rt.rel(942, 1)?), 0));
+                    rt.retire();
+                    if rt.stale() {
+                        rt.goto(946);
+                        return Ok(State::Running);
+                    }
+                    // 946: add [rb-2], -3, [rb+1]
+                    rt.put(rt.rel(946, 1)?, i64::wrapping_add(rt.get(rt.rel(946, -2)?), -3));
+                    rt.retire();
+                    if rt.stale() {
+                        rt.goto(950);
+                        return Ok(State::Running);
+                    }
+                    // 950: add 957, 0, [rb+0]
+                    rt.put(rt.rel(950, 0)?, 957);
+                    rt.retire();
+                    if rt.stale() {
+                        rt.goto(954);
+                        return Ok(State::Running);
+                    }
+                    // 954: jf 0, 922
+                    rt.retire();
+                    rt.goto(922);
+                },
+                957 => {
+                    // 957: add [rb+1], [rb-1], [rb-2]
+                    rt.put(rt.rel(957, -2)?, i64::wrapping_add(rt.get(rt.rel(957, 1)?), rt.get(rt.rel(957, -1)?)));
+                    rt.retire();
+                    if rt.stale() {
+                        rt.goto(961);
+                        return Ok(State::Running);
+                    }
+                    // 961: jt 1, 968
+                    rt.retire();
+                    rt.goto(968);
+                },
+                964 => {
+                    // 964: mul [rb-2], 1, [rb-2]
+                    rt.put(rt.rel(964, -2)?, i64::wrapping_mul(rt.get(rt.rel(964, -2)?), 1));
+                    rt.retire();
+                    if rt.stale() {
+                        rt.goto(968);
+                        return Ok(State::Running);
+                    }
+                    rt.goto(968);
+                },
+                968 => {
+                    // 968: arb -3
+                    rt.shift(968, -3)?;
+                    rt.retire();
+                    // 970: jf 0, [rb+0]
+                    rt.retire();
+                    rt.goto(rt.get(rt.rel(970, 0)?));
+                },
+                _ => return Ok(State::Running),
+            }
+        }
+    }
+}
+
+impl ::intcode_rs::IntCodeComputer for Day9 {
+    fn run(&mut self) -> ::intcode_rs::State {
+        ::intcode_rs::IntCodeComputer::run(&mut self.rt)
+    }
+
+    fn out(&self) -> &Vec<i64> {
+        ::intcode_rs::IntCodeComputer::out(&self.rt)
+    }
+
+    fn push(&mut self, val: i64) {
+        ::intcode_rs::IntCodeComputer::push(&mut self.rt, val)
+    }
+
+    fn mem(&self, at: i64) -> i64 {
+        ::intcode_rs::IntCodeComputer::mem(&self.rt, at)
+    }
+
+    fn state(&self) -> ::intcode_rs::State {
+        ::intcode_rs::IntCodeComputer::state(&self.rt)
+    }
+
+    fn step(&mut self) -> ::intcode_rs::State {
+        ::intcode_rs::IntCodeComputer::step(&mut self.rt)
+    }
+
+    fn cycles(&self) -> u64 {
+        ::intcode_rs::IntCodeComputer::cycles(&self.rt)
+    }
+
+    fn queued(&self) -> usize {
+        ::intcode_rs::IntCodeComputer::queued(&self.rt)
+    }
+
+    fn pc(&self) -> i64 {
+        ::intcode_rs::IntCodeComputer::pc(&self.rt)
+    }
+
+    fn set_pc(&mut self, pc: i64) {
+        ::intcode_rs::IntCodeComputer::set_pc(&mut self.rt, pc)
+    }
+
+    fn rb(&self) -> i64 {
+        ::intcode_rs::IntCodeComputer::rb(&self.rt)
+    }
+
+    fn set_rb(&mut self, rb: i64) {
+        ::intcode_rs::IntCodeComputer::set_rb(&mut self.rt, rb)
+    }
+
+    fn poke(&mut self, at: i64, val: i64) {
+        ::intcode_rs::IntCodeComputer::poke(&mut self.rt, at, val)
+    }
+
+    fn inputs(&self) -> &[i64] {
+        ::intcode_rs::IntCodeComputer::inputs(&self.rt)
+    }
+
+    fn image_size(&self) -> i64 {
+        ::intcode_rs::IntCodeComputer::image_size(&self.rt)
+    }
+
+    fn touched(&self) -> Vec<i64> {
+        ::intcode_rs::IntCodeComputer::touched(&self.rt)
+    }
+}
@@ -0,0 +1,1285 @@
+// Generated by intcode_rs::aot from a 2236-cell image. Don't edit by hand.
+pub struct Day13 {
+    rt: ::intcode_rs::aot::Runtime,
+}
+
+#[allow(dead_code)]
+impl Day13 {
+    const IMAGE: [i64; 2236] = [
+        1, 380, 379, 385, 1008, 2235, 768501, 381, 1005, 381, 12, 99, 109, 2236, 1102, 0,
+        1, 383, 1102, 1, 0, 382, 21002, 382, 1, 1, 20102, 1, 383, 2, 21102, 1,
+        37, 0, 1106, 0, 578, 4, 382, 4, 383, 204, 1, 1001, 382, 1, 382, 1007,
+        382, 38, 381, 1005, 381, 22, 1001, 383, 1, 383, 1007, 383, 21, 381, 1005, 381,
+        18, 1006, 385, 69, 99, 104, -1, 104, 0, 4, 386, 3, 384, 1007, 384, 0,
+        381, 1005, 381, 94, 107, 0, 384, 381, 1005, 381, 108, 1106, 0, 161, 107, 1,
+        392, 381, 1006, 381, 161, 1101, 0, -1, 384, 1106, 0, 119, 1007, 392, 36, 381,
+        1006, 381, 161, 1101, 0, 1, 384, 20101, 0, 392, 1, 21102, 1, 19, 2, 21101,
+        0, 0, 3, 21102, 138, 1, 0, 1106, 0, 549, 1, 392, 384, 392, 21002, 392,
+        1, 1, 21101, 0, 19, 2, 21101, 3, 0, 3, 21102, 161, 1, 0, 1106, 0,
+        549, 1102, 1, 0, 384, 20001, 388, 390, 1, 21001, 389, 0, 2, 21101, 180, 0,
+        0, 1106, 0, 578, 1206, 1, 213, 1208, 1, 2, 381, 1006, 381, 205, 20001, 388,
+        390, 1, 21001, 389, 0, 2, 21101, 0, 205, 0, 1106, 0, 393, 1002, 390, -1,
+        390, 1102, 1, 1, 384, 21002, 388, 1, 1, 20001, 389, 391, 2, 21101, 0, 228,
+        0, 1106, 0, 578, 1206, 1, 261, 1208, 1, 2, 381, 1006, 381, 253, 21002, 388,
+        1, 1, 20001, 389, 391, 2, 21102, 253, 1, 0, 1106, 0, 393, 1002, 391, -1,
+        391, 1102, 1, 1, 384, 1005, 384, 161, 20001, 388, 390, 1, 20001, 389, 391, 2,
+        21101, 279, 0, 0, 1105, 1, 578, 1206, 1, 316, 1208, 1, 2, 381, 1006, 381,
+        304, 20001, 388, 390, 1, 20001, 389, 391, 2, 21101, 0, 304, 0, 1105, 1, 393,
+        1002, 390, -1, 390, 1002, 391, -1, 391, 1102, 1, 1, 384, 1005, 384, 161, 20102,
+        1, 388, 1, 20102, 1, 389, 2, 21101, 0, 0, 3, 21102, 338, 1, 0, 1105,
+        1, 549, 1, 388, 390, 388, 1, 389, 391, 389, 21002, 388, 1, 1, 21002, 389,
+        1, 2, 21101, 0, 4, 3, 21101, 0, 365, 0, 1106, 0, 549, 1007, 389, 20,
+        381, 1005, 381, 75, 104, -1, 104, 0, 104, 0, 99, 0, 1, 0, 0, 0,
+        0, 0, 0, 320, 17, 16, 1, 1, 19, 109, 3, 21201, -2, 0, 1, 22101,
+        0, -1, 2, 21101, 0, 0, 3, 21102, 414, 1, 0, 1106, 0, 549, 21202, -2,
+        1, 1, 21201, -1, 0, 2, 21102, 1, 429, 0, 1105, 1, 601, 1201, 1, 0,
+        435, 1, 386, 0, 386, 104, -1, 104, 0, 4, 386, 1001, 387, -1, 387, 1005,
+        387, 451, 99, 109, -3, 2106, 0, 0, 109, 8, 22202, -7, -6, -3, 22201, -3,
+        -5, -3, 21202, -4, 64, -2, 2207, -3, -2, 381, 1005, 381, 492, 21202, -2, -1,
+        -1, 22201, -3, -1, -3, 2207, -3, -2, 381, 1006, 381, 481, 21202, -4, 8, -2,
+        2207, -3, -2, 381, 1005, 381, 518, 21202, -2, -1, -1, 22201, -3, -1, -3, 2207,
+        -3, -2, 381, 1006, 381, 507, 2207, -3, -4, 381, 1005, 381, 540, 21202, -4, -1,
+        -1, 22201, -3, -1, -3, 2207, -3, -4, 381, 1006, 381, 529, 22101, 0, -3, -7,
+        109, -8, 2105, 1, 0, 109, 4, 1202, -2, 38, 566, 201, -3, 566, 566, 101,
+        639, 566, 566, 1201, -1, 0, 0, 204, -3, 204, -2, 204, -1, 109, -4, 2106,
+        0, 0, 109, 3, 1202, -1, 38, 594, 201, -2, 594, 594, 101, 639, 594, 594,
+        20102, 1, 0, -2, 109, -3, 2106, 0, 0, 109, 3, 22102, 21, -2, 1, 22201,
+        1, -1, 1, 21101, 0, 401, 2, 21102, 1, 733, 3, 21102, 798, 1, 4, 21102,
+        1, 630, 0, 1106, 0, 456, 21201, 1, 1437, -2, 109, -3, 2105, 1, 0, 1,
+        1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1,
+        1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1,
+        1, 1, 1, 1, 1, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 0, 2, 2, 2,
+        2, 2, 2, 2, 2, 2, 2, 2, 0, 2, 0, 2, 2, 2, 0, 2,
+        2, 2, 2, 2, 2, 0, 2, 2, 0, 2, 2, 2, 2, 2, 2, 0,
+        1, 1, 0, 2, 2, 0, 2, 0, 2, 2, 2, 2, 0, 2, 2, 2,
+        2, 2, 0, 2, 0, 2, 2, 0, 2, 2, 2, 2, 0, 0, 2, 2,
+        2, 0, 2, 0, 2, 0, 1, 1, 0, 0, 2, 2, 2, 0, 0, 2,
+        0, 2, 2, 0, 2, 2, 2, 2, 0, 0, 2, 0, 2, 2, 2, 2,
+        2, 2, 2, 0, 2, 0, 2, 2, 2, 0, 2, 0, 1, 1, 0, 2,
+        2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 0, 2, 2, 2, 0, 0,
+        2, 0, 2, 0, 2, 2, 0, 2, 2, 0, 2, 2, 2, 2, 2, 0,
+        2, 0, 1, 1, 0, 2, 0, 2, 2, 0, 2, 2, 2, 2, 2, 2,
+        2, 2, 2, 2, 0, 0, 0, 2, 2, 2, 0, 2, 0, 2, 2, 0,
+        0, 2, 2, 0, 2, 2, 2, 0, 1, 1, 0, 2, 2, 2, 2, 0,
+        2, 0, 0, 2, 2, 2, 0, 2, 2, 2, 0, 2, 0, 2, 0, 0,
+        2, 2, 2, 0, 2, 2, 2, 0, 0, 2, 0, 2, 2, 0, 1, 1,
+        0, 0, 2, 2, 0, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2,
+        2, 2, 2, 0, 2, 2, 2, 2, 0, 2, 0, 2, 2, 2, 0, 2,
+        2, 2, 0, 0, 1, 1, 0, 2, 2, 0, 2, 0, 0, 2, 2, 2,
+        0, 0, 0, 2, 0, 2, 2, 2, 0, 2, 2, 0, 2, 2, 2, 2,
+        2, 2, 2, 0, 0, 0, 2, 0, 0, 0, 1, 1, 0, 0, 2, 2,
+        0, 0, 2, 2, 2, 2, 0, 2, 2, 2, 0, 0, 2, 0, 2, 2,
+        0, 2, 2, 2, 2, 2, 2, 2, 2, 2, 0, 0, 0, 2, 2, 0,
+        1, 1, 0, 2, 0, 0, 2, 2, 2, 2, 2, 2, 2, 0, 2, 0,
+        2, 0, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2,
+        0, 2, 0, 0, 2, 0, 1, 1, 0, 2, 2, 0, 2, 2, 2, 0,
+        2, 2, 0, 2, 0, 2, 0, 0, 0, 0, 2, 2, 2, 2, 2, 2,
+        2, 2, 2, 2, 0, 0, 2, 2, 0, 2, 2, 0, 1, 1, 0, 2,
+        2, 0, 2, 2, 2, 2, 2, 0, 2, 0, 2, 2, 2, 2, 2, 0,
+        0, 2, 2, 2, 2, 0, 2, 2, 0, 2, 2, 2, 2, 0, 2, 0,
+        2, 0, 1, 1, 0, 2, 2, 0, 2, 0, 2, 2, 2, 2, 2, 2,
+        0, 2, 2, 2, 2, 2, 2, 0, 2, 2, 0, 2, 2, 2, 0, 2,
+        2, 2, 2, 2, 2, 2, 2, 0, 1, 1, 0, 0, 0, 0, 0, 0,
+        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 1,
+        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+        4, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+        0, 0, 0, 0, 1, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 0, 0, 0, 0,
+        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+        1, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+        0, 0, 0, 0, 3, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+        0, 0, 0, 0, 0, 0, 1, 1, 0, 0, 0, 0, 0, 0, 0, 0,
+        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 3, 25, 47,
+        80, 47, 44, 8, 60, 70, 40, 60, 9, 9, 88, 82, 66, 91, 81, 11,
+        3, 95, 72, 53, 39, 7, 33, 48, 69, 45, 31, 75, 6, 61, 77, 72,
+        25, 57, 70, 2, 92, 78, 46, 43, 88, 74, 45, 27, 94, 73, 90, 43,
+        68, 90, 22, 64, 2, 5, 3, 40, 98, 62, 25, 95, 74, 1, 35, 2,
+        1, 54, 76, 68, 88, 75, 30, 77, 35, 40, 43, 49, 85, 55, 53, 12,
+        77, 94, 89, 9, 55, 8, 50, 82, 7, 89, 21, 85, 37, 48, 4, 33,
+        1, 28, 97, 62, 95, 41, 14, 22, 52, 24, 72, 2, 25, 51, 32, 55,
+        36, 73, 84, 22, 66, 69, 36, 1, 57, 97, 50, 21, 98, 41, 36, 59,
+        56, 6, 80, 46, 2, 86, 14, 67, 77, 59, 77, 5, 13, 97, 98, 83,
+        83, 42, 10, 62, 64, 86, 97, 17, 90, 37, 27, 54, 40, 39, 61, 38,
+        11, 67, 40, 65, 13, 6, 85, 71, 9, 93, 69, 9, 28, 48, 7, 93,
+        67, 95, 90, 15, 29, 90, 88, 8, 75, 64, 36, 42, 29, 92, 24, 28,
+        19, 4, 19, 60, 16, 1, 97, 43, 50, 13, 10, 82, 30, 19, 86, 32,
+        93, 46, 32, 66, 94, 91, 44, 39, 57, 51, 48, 41, 92, 17, 97, 16,
+        92, 41, 92, 58, 31, 94, 82, 68, 25, 10, 32, 98, 24, 77, 17, 5,
+        82, 30, 16, 40, 82, 67, 14, 16, 33, 3, 96, 72, 90, 83, 97, 4,
+        55, 69, 8, 30, 29, 2, 47, 8, 47, 52, 41, 2, 14, 67, 7, 57,
+        14, 4, 94, 44, 47, 9, 81, 54, 91, 50, 85, 41, 84, 45, 65, 33,
+        66, 28, 35, 98, 89, 92, 57, 81, 49, 89, 89, 39, 11, 8, 97, 77,
+        12, 30, 33, 77, 12, 46, 64, 37, 2, 84, 34, 11, 54, 23, 33, 57,
+        40, 27, 95, 47, 3, 17, 8, 43, 15, 13, 13, 41, 80, 62, 93, 68,
+        45, 82, 86, 17, 44, 49, 51, 29, 9, 92, 67, 14, 81, 16, 97, 5,
+        65, 6, 85, 46, 35, 19, 50, 88, 51, 23, 90, 35, 44, 74, 33, 36,
+        13, 20, 44, 42, 71, 51, 32, 60, 22, 29, 13, 26, 1, 64, 26, 75,
+        86, 43, 78, 4, 43, 41, 52, 67, 16, 20, 63, 37, 60, 2, 1, 53,
+        37, 75, 55, 3, 40, 66, 36, 1, 69, 18, 55, 33, 81, 38, 1, 81,
+        24, 80, 31, 25, 79, 30, 84, 83, 71, 72, 11, 94, 62, 6, 35, 15,
+        9, 63, 29, 27, 76, 33, 62, 77, 47, 12, 61, 84, 13, 38, 73, 11,
+        32, 49, 87, 6, 25, 57, 87, 4, 35, 91, 67, 19, 30, 72, 59, 79,
+        46, 64, 66, 14, 21, 15, 85, 25, 22, 45, 87, 96, 90, 28, 83, 72,
+        29, 71, 58, 14, 50, 71, 48, 19, 50, 78, 63, 65, 3, 41, 64, 82,
+        50, 64, 74, 77, 93, 21, 52, 55, 24, 34, 19, 61, 19, 13, 44, 80,
+        38, 53, 36, 41, 96, 17, 77, 9, 84, 87, 79, 51, 77, 35, 5, 55,
+        82, 23, 63, 20, 66, 68, 23, 92, 81, 10, 2, 57, 97, 4, 24, 44,
+        28, 25, 56, 4, 22, 61, 11, 35, 60, 75, 63, 96, 60, 94, 3, 65,
+        93, 63, 28, 54, 21, 10, 20, 12, 46, 15, 84, 93, 43, 83, 71, 90,
+        52, 48, 33, 47, 32, 75, 22, 22, 38, 8, 62, 42, 30, 95, 66, 15,
+        75, 14, 73, 17, 10, 94, 64, 70, 29, 51, 70, 14, 68, 56, 60, 57,
+        9, 84, 16, 77, 37, 17, 44, 37, 22, 88, 60, 85, 59, 61, 52, 3,
+        21, 15, 19, 23, 90, 33, 47, 36, 48, 44, 30, 33, 16, 22, 37, 93,
+        78, 16, 43, 18, 65, 18, 61, 67, 71, 51, 13, 33, 7, 48, 40, 70,
+        9, 66, 12, 59, 49, 67, 34, 23, 51, 75, 48, 23, 30, 47, 23, 81,
+        21, 42, 6, 66, 34, 4, 67, 45, 21, 90, 34, 48, 47, 43, 11, 3,
+        54, 9, 10, 35, 60, 78, 19, 17, 68, 1, 9, 26, 92, 7, 86, 66,
+        13, 12, 31, 12, 71, 55, 43, 78, 39, 54, 70, 62, 38, 93, 25, 89,
+        83, 37, 37, 2, 60, 87, 84, 48, 98, 43, 1, 78, 86, 97, 67, 41,
+        85, 66, 17, 23, 32, 9, 35, 91, 4, 18, 89, 71, 4, 88, 66, 50,
+        32, 92, 9, 44, 10, 23, 31, 17, 53, 36, 46, 94, 33, 93, 68, 44,
+        85, 73, 72, 14, 34, 69, 66, 77, 43, 93, 23, 24, 14, 17, 79, 27,
+        63, 7, 44, 95, 66, 55, 83, 8, 90, 58, 43, 768501,
+    ];
+    const CODE: [(i64, i64); 5] = [
+        (0, 379), (393, 435), (436, 566), (567, 594), (595, 639),
+    ];
+
+    pub fn new(inputs: Vec<i64>) -> Day13 {
+        let rt = ::intcode_rs::aot::Runtime::new(Day13::IMAGE.to_vec(), inputs, &Day13::CODE, Day13::native);
+        Day13 { rt }
+    }
+
+    pub fn runtime(&self) -> &::intcode_rs::aot::Runtime {
+        &self.rt
+    }
+
+    pub fn runtime_mut(&mut self) -> &mut ::intcode_rs::aot::Runtime {
+        &mut self.rt
+    }
+
+    fn native(rt: &mut ::intcode_rs::aot::Runtime) -> Result<::intcode_rs::State, ::intcode_rs::Fault> {
+        use ::intcode_rs::State;
+        loop {
+            match rt.pc() {
+                0 => {
+                    // 0: add [380], [379], [385]
+                    rt.put(385, i64::wrapping_add(rt.get(380), rt.get(379)));
+                    rt.retire();
+                    rt.goto(4);
+                },
+                4 => {
+                    // 4: eq [2235], 768501, [381]
+                    rt.put(381, i64::from(rt.get(2235) == 768501));
+                    rt.retire();
+                    rt.goto(8);
+                },
+                8 => {
+                    // 8: jt [381], 12
+                    rt.retire();
+                    if rt.get(381) != 0 {
+                        rt.goto(12);
+                    } else {
+                        rt.goto(11);
+                    }
+                },
+                11 => {
+                    // 11: hlt
+                    rt.goto(11);
+                    return Ok(State::Halted);
+                },
+                12 => {
+                    // 12: arb 2236
+                    rt.shift(12, 2236)?;
+                    rt.retire();
+                    // 14: mul 0, 1, [383]
+                    rt.put(383, 0);
+                    rt.retire();
+                    rt.goto(18);
+                },
+                18 => {
+                    // 18: mul 1, 0, [382]
+                    rt.put(382, 0);
+                    rt.retire();
+                    rt.goto(22);
+                },
+                22 => {
+                    // 22: mul [382], 1, [rb+1]
+                    rt.put(rt.rel(22, 1)?, i64::wrapping_mul(rt.get(382), 1));
+                    rt.retire();
+                    if rt.stale() {
+                        rt.goto(26);
+                        return Ok(State::Running);
+                    }
+                    // 26: mul 1, [383], [rb+2]
+                    rt.put(rt.rel(26, 2)?, i64::wrapping_mul(1, rt.get(383)));
+                    rt.retire();
+                    if rt.stale() {
+                        rt.goto(30);
+                        return Ok(State::Running);
+                    }
+                    // 30: mul 1, 37, [rb+0]
+                    rt.put(rt.rel(30, 0)?, 37);
+                    rt.retire();
+                    if rt.stale() {
+                        rt.goto(34);
+                        return Ok(State::Running);
+                    }
+                    // 34: jf 0, 578
+                    rt.retire();
+                    rt.goto(578);
+                },
+                37 => {
+                    // 37: out [382]
+                    rt.output(rt.get(382));
+                    rt.retire();
+                    // 39: out [383]
+                    rt.output(rt.get(383));
+                    rt.retire();
+                    // 41: out [rb+1]
+                    rt.output(rt.get(rt.rel(41, 1)?));
+                    rt.retire();
+                    // 43: add [382], 1, [382]
+                    rt.put(382, i64::wrapping_add(rt.get(382), 1));
+                    rt.retire();
+                    // 47: lt [382], 38, [381]
+                    rt.put(381, i64::from(rt.get(382) < 38));
+                    rt.retire();
+                    // 51: jt [381], 22
+                    rt.retire();
+                    if rt.get(381) != 0 {
+                        rt.goto(22);
+                    } else {
+                        rt.goto(54);
+                    }
+                },
+                54 => {
+                    // 54: add [383], 1, [383]
+                    rt.put(383, i64::wrapping_add(rt.get(383), 1));
+                    rt.retire();
+                    // 58: lt [383], 21, [381]
+                    rt.put(381, i64::from(rt.get(383) < 21));
+                    rt.retire();
+                    // 62: jt [381], 18
+                    rt.retire();
+                    if rt.get(381) != 0 {
+                        rt.goto(18);
+                    } else {
+                        rt.goto(65);
+                    }
+                },
+                65 => {
+                    // 65: jf [385], 69
+                    rt.retire();
+                    if rt.get(385) == 0 {
+                        rt.goto(69);
+                    } else {
+                        rt.goto(68);
+                    }
+                },
+                68 => {
+                    // 68: hlt
+                    rt.goto(68);
+                    return Ok(State::Halted);
+                },
+                69 => {
+                    // 69: out -1
+                    rt.output(-1);
+                    rt.retire();
+                    // 71: out 0
+                    rt.output(0);
+                    rt.retire();
+                    // 73: out [386]
+                    rt.output(rt.get(386));
+                    rt.retire();
+                    rt.goto(75);
+                },
+                75 => {
+                    // 75: in [384]
+                    match rt.input() {
+                        Some(val) => rt.put(384, val),
+                        None => {
+                            rt.goto(75);
+                            return Ok(State::Waiting);
+                        },
+                    }
+                    rt.retire();
+                    // 77: lt [384], 0, [381]
+                    rt.put(381, i64::from(rt.get(384) < 0));
+                    rt.retire();
+                    // 81: jt [381], 94
+                    rt.retire();
+                    if rt.get(381) != 0 {
+                        rt.goto(94);
+                    } else {
+                        rt.goto(84);
+                    }
+                },
+                84 => {
+                    // 84: lt 0, [384], [381]
+                    rt.put(381, i64::from(0 < rt.get(384)));
+                    rt.retire();
+                    // 88: jt [381], 108
+                    rt.retire();
+                    if rt.get(381) != 0 {
+                        rt.goto(108);
+                    } else {
+                        rt.goto(91);
+                    }
+                },
+                91 => {
+                    // 91: jf 0, 161
+                    rt.retire();
+                    rt.goto(161);
+                },
+                94 => {
+                    // 94: lt 1, [392], [381]
+                    rt.put(381, i64::from(1 < rt.get(392)));
+                    rt.retire();
+                    // 98: jf [381], 161
+                    rt.retire();
+                    if rt.get(381) == 0 {
+                        rt.goto(161);
+                    } else {
+                        rt.goto(101);
+                    }
+                },
+                101 => {
+                    // 101: add 0, -1, [384]
+                    rt.put(384, -1);
+                    rt.retire();
+                    // 105: jf 0, 119
+                    rt.retire();
+                    rt.goto(119);
+                },
+                108 => {
+                    // 108: lt [392], 36, [381]
+                    rt.put(381, i64::from(rt.get(392) < 36));
+                    rt.retire();
+                    // 112: jf [381], 161
+                    rt.retire();
+                    if rt.get(381) == 0 {
+                        rt.goto(161);
+                    } else {
+                        rt.goto(115);
+                    }
+                },
+                115 => {
+                    // 115: add 0, 1, [384]
+                    rt.put(384, 1);
+                    rt.retire();
+                    rt.goto(119);
+                },
+                119 => {
+                    // 119: add 0, [392], [rb+1]
+                    rt.put(rt.rel(119, 1)?, i64::wrapping_add(0, rt.get(392)));
+                    rt.retire();
+                    if rt.stale() {
+                        rt.goto(123);
+                        return Ok(State::Running);
+                    }
+                    // 123: mul 1, 19, [rb+2]
+                    rt.put(rt.rel(123, 2)?, 19);
+                    rt.retire();
+                    if rt.stale() {
+                        rt.goto(127);
+                        return Ok(State::Running);
+                    }
+                    // 127: add 0, 0, [rb+3]
+                    rt.put(rt.rel(127, 3)?, 0);
+                    rt.retire();
+                    if rt.stale() {
+                        rt.goto(131);
+                        return Ok(State::Running);
+                    }
+                    // 131: mul 138, 1, [rb+0]
+                    rt.put(rt.rel(131, 0)?, 138);
+                    rt.retire();
+                    if rt.stale() {
+                        rt.goto(135);
+                        return Ok(State::Running);
+                    }
+                    // 135: jf 0, 549
+                    rt.retire();
+                    rt.goto(549);
+                },
+                138 => {
+                    // 138: add [392], [384], [392]
+                    rt.put(392, i64::wrapping_add(rt.get(392), rt.get(384)));
+                    rt.retire();
+                    // 142: mul [392], 1, [rb+1]
+                    rt.put(rt.rel(142, 1)?, i64::wrapping_mul(rt.get(392), 1));
+                    rt.retire();
+                    if rt.stale() {
+                        rt.goto(146);
+                        return Ok(State::Running);
+                    }
+                    // 146: add 0, 19, [rb+2]
+                    rt.put(rt.rel(146, 2)?, 19);
+                    rt.retire();
+                    if rt.stale() {
+                        rt.goto(150);
+                        return Ok(State::Running);
+                    }
+                    // 150: add 3, 0, [rb+3]
+                    rt.put(rt.rel(150, 3)?, 3);
+                    rt.retire();
+                    if rt.stale() {
+                        rt.goto(154);
+                        return Ok(State::Running);
+                    }
+                    // 154: mul 161, 1, [rb+0]
+                    rt.put(rt.rel(154, 0)?, 161);
+                    rt.retire();
+                    if rt.stale() {
+                        rt.goto(158);
+                        return Ok(State::Running);
+                    }
+                    // 158: jf 0, 549
+                    rt.retire();
+                    rt.goto(549);
+                },
+                161 => {
+                    // 161: mul 1, 0, [384]
+                    rt.put(384, 0);
+                    rt.retire();
+                    // 165: add [388], [390], [rb+1]
+                    rt.put(rt.rel(165, 1)?, i64::wrapping_add(rt.get(388), rt.get(390)));
+                    rt.retire();
+                    if rt.stale() {
+                        rt.goto(169);
+                        return Ok(State::Running);
+                    }
+                    // 169: add [389], 0, [rb+2]
+                    rt.put(rt.rel(169, 2)?, i64::wrapping_add(rt.get(389), 0));
+                    rt.retire();
+                    if rt.stale() {
+                        rt.goto(173);
+                        return Ok(State::Running);
+                    }
+                    // 173: add 180, 0, [rb+0]
+                    rt.put(rt.rel(173, 0)?, 180);
+                    rt.retire();
+                    if rt.stale() {
+                        rt.goto(177);
+                        return Ok(State::Running);
+                    }
+                    // 177: jf 0, 578
+                    rt.retire();
+                    rt.goto(578);
+                },
+                180 => {
+                    // 180: jf [rb+1], 213
+                    rt.retire();
+                    if rt.get(rt.rel(180, 1)?) == 0 {
+                        rt.goto(213);
+                    } else {
+                        rt.goto(183);
+                    }
+                },
+                183 => {
+                    // 183: eq [rb+1], 2, [381]
+                    rt.put(381, i64::from(rt.get(rt.rel(183, 1)?) == 2));
+                    rt.retire();
+                    // 187: jf [381], 205
+                    rt.retire();
+                    if rt.get(381) == 0 {
+                        rt.goto(205);
+                    } else {
+                        rt.goto(190);
+                    }
+                },
+                190 => {
+                    // 190: add [388], [390], [rb+1]
+                    rt.put(rt.rel(190, 1)?, i64::wrapping_add(rt.get(388), rt.get(390)));
+                    rt.retire();
+                    if rt.stale() {
+                        rt.goto(194);
+                        return Ok(State::Running);
+                    }
+                    // 194: add [389], 0, [rb+2]
+                    rt.put(rt.rel(194, 2)?, i64::wrapping_add(rt.get(389), 0));
+                    rt.retire();
+                    if rt.stale() {
+                        rt.goto(198);
+                        return Ok(State::Running);
+                    }
+                    // 198: add 0, 205, [rb+0]
+                    rt.put(rt.rel(198, 0)?, 205);
+                    rt.retire();
+                    if rt.stale() {
+                        rt.goto(202);
+                        return Ok(State::Running);
+                    }
+                    // 202: jf 0, 393
+                    rt.retire();
+                    rt.goto(393);
+                },
+                205 => {
+                    // 205: mul [390], -1, [390]
+                    rt.put(390, i64::wrapping_mul(rt.get(390), -1));
+                    rt.retire();
+                    // 209: mul 1, 1, [384]
+                    rt.put(384, 1);
+                    rt.retire();
+                    rt.goto(213);
+                },
+                213 => {
+                    // 213: mul [388], 1, [rb+1]
+                    rt.put(rt.rel(213, 1)?, i64::wrapping_mul(rt.get(388), 1));
+                    rt.retire();
+                    if rt.stale() {
+                        rt.goto(217);
+                        return Ok(State::Running);
+                    }
+                    // 217: add [389], [391], [rb+2]
+                    rt.put(rt.rel(217, 2)?, i64::wrapping_add(rt.get(389), rt.get(391)));
+                    rt.retire();
+                    if rt.stale() {
+                        rt.goto(221);
+                        return Ok(State::Running);
+                    }
+                    // 221: add 0, 228, [rb+0]
+                    rt.put(rt.rel(221, 0)?, 228);
+                    rt.retire();
+                    if rt.stale() {
+                        rt.goto(225);
+                        return Ok(State::Running);
+                    }
+                    // 225: jf 0, 578
+                    rt.retire();
+                    rt.goto(578);
+                },
+                228 => {
+                    // 228: jf [rb+1], 261
+                    rt.retire();
+                    if rt.get(rt.rel(228, 1)?) == 0 {
+                        rt.goto(261);
+                    } else {
+                        rt.goto(231);
+                    }
+                },
+                231 => {
+                    // 231: eq [rb+1], 2, [381]
+                    rt.put(381, i64::from(rt.get(rt.rel(231, 1)?) == 2));
+                    rt.retire();
+                    // 235: jf [381], 253
+                    rt.retire();
+                    if rt.get(381) == 0 {
+                        rt.goto(253);
+                    } else {
+                        rt.goto(238);
+                    }
+                },
+                238 => {
+                    // 238: mul [388], 1, [rb+1]
+                    rt.put(rt.rel(238, 1)?, i64::wrapping_mul(rt.get(388), 1));
+                    rt.retire();
+                    if rt.stale() {
+                        rt.goto(242);
+                        return Ok(State::Running);
+                    }
+                    // 242: add [389], [391], [rb+2]
+                    rt.put(rt.rel(242, 2)?, i64::wrapping_add(rt.get(389), rt.get(391)));
+                    rt.retire();
+                    if rt.stale() {
+                        rt.goto(246);
+                        return Ok(State::Running);
+                    }
+                    // 246: mul 253, 1, [rb+0]
+                    rt.put(rt.rel(246, 0)?, 253);
+                    rt.retire();
+                    if rt.stale() {
+                        rt.goto(250);
+                        return Ok(State::Running);
+                    }
+                    // 250: jf 0, 393
+                    rt.retire();
+                    rt.goto(393);
+                },
+                253 => {
+                    // 253: mul [391], -1, [391]
+                    rt.put(391, i64::wrapping_mul(rt.get(391), -1));
+                    rt.retire();
+                    // 257: mul 1, 1, [384]
+                    rt.put(384, 1);
+                    rt.retire();
+                    rt.goto(261);
+                },
+                261 => {
+                    // 261: jt [384], 161
+                    rt.retire();
+                    if rt.get(384) != 0 {
+                        rt.goto(161);
+                    } else {
+                        rt.goto(264);
+                    }
+                },
+                264 => {
+                    // 264: add [388], [390], [rb+1]
+                    rt.put(rt.rel(264, 1)?, i64::wrapping_add(rt.get(388), rt.get(390)));
+                    rt.retire();
+                    if rt.stale() {
+                        rt.goto(268);
+                        return Ok(State::Running);
+                    }
+                    // 268: add [389], [391], [rb+2]
+                    rt.put(rt.rel(268, 2)?, i64::wrapping_add(rt.get(389), rt.get(391)));
+                    rt.retire();
+                    if rt.stale() {
+                        rt.goto(272);
+                        return Ok(State::Running);
+                    }
+                    // 272: add 279, 0, [rb+0]
+                    rt.put(rt.rel(272, 0)?, 279);
+                    rt.retire();
+                    if rt.stale() {
+                        rt.goto(276);
+                        return Ok(State::Running);
+                    }
+                    // 276: jt 1, 578
+                    rt.retire();
+                    rt.goto(578);
+                },
+                279 => {
+                    // 279: jf [rb+1], 316
+                    rt.retire();
+                    if rt.get(rt.rel(279, 1)?) == 0 {
+                        rt.goto(316);
+                    } else {
+                        rt.goto(282);
+                    }
+                },
+                282 => {
+                    // 282: eq [rb+1], 2, [381]
+                    rt.put(381, i64::from(rt.get(rt.rel(282, 1)?) == 2));
+                    rt.retire();
+                    // 286: jf [381], 304
+                    rt.retire();
+                    if rt.get(381) == 0 {
+                        rt.goto(304);
+                    } else {
+                        rt.goto(289);
+                    }
+                },
+                289 => {
+                    // 289: add [388], [390], [rb+1]
+                    rt.put(rt.rel(289, 1)?, i64::wrapping_add(rt.get(388), rt.get(390)));
+                    rt.retire();
+                    if rt.stale() {
+                        rt.goto(293);
+                        return Ok(State::Running);
+                    }
+                    // 293: add [389], [391], [rb+2]
+                    rt.put(rt.rel(293, 2)?, i64::wrapping_add(rt.get(389), rt.get(391)));
+                    rt.retire();
+                    if rt.stale() {
+                        rt.goto(297);
+                        return Ok(State::Running);
+                    }
+                    // 297: add 0, 304, [rb+0]
+                    rt.put(rt.rel(297, 0)?, 304);
+                    rt.retire();
+                    if rt.stale() {
+                        rt.goto(301);
+                        return Ok(State::Running);
+                    }
+                    // 301: jt 1, 393
+                    rt.retire();
+                    rt.goto(393);
+                },
+                304 => {
+                    // 304: mul [390], -1, [390]
+                    rt.put(390, i64::wrapping_mul(rt.get(390), -1));
+                    rt.retire();
+                    // 308: mul [391], -1, [391]
+                    rt.put(391, i64::wrapping_mul(rt.get(391), -1));
+                    rt.retire();
+                    // 312: mul 1, 1, [384]
+                    rt.put(384, 1);
+                    rt.retire();
+                    rt.goto(316);
+                },
+                316 => {
+                    // 316: jt [384], 161
+                    rt.retire();
+                    if rt.get(384) != 0 {
+                        rt.goto(161);
+                    } else {
+                        rt.goto(319);
+                    }
+                },
+                319 => {
+                    // 319: mul 1, [388], [rb+1]
+                    rt.put(rt.rel(319, 1)?, i64::wrapping_mul(1, rt.get(388)));
+                    rt.retire();
+                    if rt.stale() {
+                        rt.goto(323);
+                        return Ok(State::Running);
+                    }
+                    // 323: mul 1, [389], [rb+2]
+                    rt.put(rt.rel(323, 2)?, i64::wrapping_mul(1, rt.get(389)));
+                    rt.retire();
+                    if rt.stale() {
+                        rt.goto(327);
+                        return Ok(State::Running);
+                    }
+                    // 327: add 0, 0, [rb+3]
+                    rt.put(rt.rel(327, 3)?, 0);
+                    rt.retire();
+                    if rt.stale() {
+                        rt.goto(331);
+                        return Ok(State::Running);
+                    }
+                    // 331: mul 338, 1, [rb+0]
+                    rt.put(rt.rel(331, 0)?, 338);
+                    rt.retire();
+                    if rt.stale() {
+                        rt.goto(335);
+                        return Ok(State::Running);
+                    }
+                    // 335: jt 1, 549
+                    rt.retire();
+                    rt.goto(549);
+                },
+                338 => {
+                    // 338: add [388], [390], [388]
+                    rt.put(388, i64::wrapping_add(rt.get(388), rt.get(390)));
+                    rt.retire();
+                    // 342: add [389], [391], [389]
+                    rt.put(389, i64::wrapping_add(rt.get(389), rt.get(391)));
+                    rt.retire();
+                    // 346: mul [388], 1, [rb+1]
+                    rt.put(rt.rel(346, 1)?, i64::wrapping_mul(rt.get(388), 1));
+                    rt.retire();
+                    if rt.stale() {
+                        rt.goto(350);
+                        return Ok(State::Running);
+                    }
+                    // 350: mul [389], 1, [rb+2]
+                    rt.put(rt.rel(350, 2)?, i64::wrapping_mul(rt.get(389), 1));
+                    rt.retire();
+                    if rt.stale() {
+                        rt.goto(354);
+                        return Ok(State::Running);
+                    }
+                    // 354: add 0, 4, [rb+3]
+                    rt.put(rt.rel(354, 3)?, 4);
+                    rt.retire();
+                    if rt.stale() {
+                        rt.goto(358);
+                        return Ok(State::Running);
+                    }
+                    // 358: add 0, 365, [rb+0]
+                    rt.put(rt.rel(358, 0)?, 365);
+                    rt.retire();
+                    if rt.stale() {
+                        rt.goto(362);
+                        return Ok(State::Running);
+                    }
+                    // 362: jf 0, 549
+                    rt.retire();
+                    rt.goto(549);
+                },
+                365 => {
+                    // 365: lt [389], 20, [381]
+                    rt.put(381, i64::from(rt.get(389) < 20));
+                    rt.retire();
+                    // 369: jt [381], 75
+                    rt.retire();
+                    if rt.get(381) != 0 {
+                        rt.goto(75);
+                    } else {
+                        rt.goto(372);
+                    }
+                },
+                372 => {
+                    // 372: out -1
+                    rt.output(-1);
+                    rt.retire();
+                    // 374: out 0
+                    rt.output(0);
+                    rt.retire();
+                    // 376: out 0
+                    rt.output(0);
+                    rt.retire();
+                    // 378: hlt
+                    rt.goto(378);
+                    return Ok(State::Halted);
+                },
+                393 => {
+                    // 393: arb 3
+                    rt.shift(393, 3)?;
+                    rt.retire();
+                    // 395: add [rb-2], 0, [rb+1]
+                    rt.put(rt.rel(395, 1)?, i64::wrapping_add(rt.get(rt.rel(395, -2)?), 0));
+                    rt.retire();
+                    if rt.stale() {
+                        rt.goto(399);
+                        return Ok(State::Running);
+                    }
+                    // 399: add 0, [rb-1], [rb+2]
+                    rt.put(rt.rel(399, 2)?, i64::wrapping_add(0, rt.get(rt.rel(399, -1)?)));
+                    rt.retire();
+                    if rt.stale() {
+                        rt.goto(403);
+                        return Ok(State::Running);
+                    }
+                    // 403: add 0, 0, [rb+3]
+                    rt.put(rt.rel(403, 3)?, 0);
+                    rt.retire();
+                    if rt.stale() {
+                        rt.goto(407);
+                        return Ok(State::Running);
+                    }
+                    // 407: mul 414, 1, [rb+0]
+                    rt.put(rt.rel(407, 0)?, 414);
+                    rt.retire();
+                    if rt.stale() {
+                        rt.goto(411);
+                        return Ok(State::Running);
+                    }
+                    // 411: jf 0, 549
+                    rt.retire();
+                    rt.goto(549);
+                },
+                414 => {
+                    // 414: mul [rb-2], 1, [rb+1]
+                    rt.put(rt.rel(414, 1)?, i64::wrapping_mul(rt.get(rt.rel(414, -2)?), 1));
+                    rt.retire();
+                    if rt.stale() {
+                        rt.goto(418);
+                        return Ok(State::Running);
+                    }
+                    // 418: add [rb-1], 0, [rb+2]
+                    rt.put(rt.rel(418, 2)?, i64::wrapping_add(rt.get(rt.rel(418, -1)?), 0));
+                    rt.retire();
+                    if rt.stale() {
+                        rt.goto(422);
+                        return Ok(State::Running);
+                    }
+                    // 422: mul 1, 429, [rb+0]
+                    rt.put(rt.rel(422, 0)?, 429);
+                    rt.retire();
+                    if rt.stale() {
+                        rt.goto(426);
+                        return Ok(State::Running);
+                    }
+                    // 426: jt 1, 601
+                    rt.retire();
+                    rt.goto(601);
+                },
+                429 => {
+                    // 429: add [rb+1], 0, [435]
+                    rt.put(435, i64::wrapping_add(rt.get(rt.rel(429, 1)?), 0));
+                    rt.retire();
+                    // 433: add [386], [0], [386]
+                    rt.put(386, i64::wrapping_add(rt.get(386), rt.get(rt.get(435))));
+                    rt.retire();
+                    // 437: out -1
+                    rt.output(-1);
+                    rt.retire();
+                    // 439: out 0
+                    rt.output(0);
+                    rt.retire();
+                    // 441: out [386]
+                    rt.output(rt.get(386));
+                    rt.retire();
+                    // 443: add [387], -1, [387]
+                    rt.put(387, i64::wrapping_add(rt.get(387), -1));
+                    rt.retire();
+                    // 447: jt [387], 451
+                    rt.retire();
+                    if rt.get(387) != 0 {
+                        rt.goto(451);
+                    } else {
+                        rt.goto(450);
+                    }
+                },
+                450 => {
+                    // 450: hlt
+                    rt.goto(450);
+                    return Ok(State::Halted);
+                },
+                451 => {
+                    // 451: arb -3
+                    rt.shift(451, -3)?;
+                    rt.retire();
+                    // 453: jf 0, [rb+0]
+                    rt.retire();
+                    rt.goto(rt.get(rt.rel(453, 0)?));
+                },
+                456 => {
+                    // 456: arb 8
+                    rt.shift(456, 8)?;
+                    rt.retire();
+                    // 458: mul [rb-7], [rb-6], [rb-3]
+                    rt.put(rt.rel(458, -3)?, i64::wrapping_mul(rt.get(rt.rel(458, -7)?), rt.get(rt.rel(458, -6)?)));
+                    rt.retire();
+                    if rt.stale() {
+                        rt.goto(462);
+                        return Ok(State::Running);
+                    }
+                    // 462: add [rb-3], [rb-5], [rb-3]
+                    rt.put(rt.rel(462, -3)?, i64::wrapping_add(rt.get(rt.rel(462, -3)?), rt.get(rt.rel(462, -5)?)));
+                    rt.retire();
+                    if rt.stale() {
+                        rt.goto(466);
+                        return Ok(State::Running);
+                    }
+                    // 466: mul [rb-4], 64, [rb-2]
+                    rt.put(rt.rel(466, -2)?, i64::wrapping_mul(rt.get(rt.rel(466, -4)?), 64));
+                    rt.retire();
+                    if rt.stale() {
+                        rt.goto(470);
+                        return Ok(State::Running);
+                    }
+                    // 470: lt [rb-3], [rb-2], [381]
+                    rt.put(381, i64::from(rt.get(rt.rel(470, -3)?) < rt.get(rt.rel(470, -2)?)));
+                    rt.retire();
+                    // 474: jt [381], 492
+                    rt.retire();
+                    if rt.get(381) != 0 {
+                        rt.goto(492);
+                    } else {
+                        rt.goto(477);
+                    }
+                },
+                477 => {
+                    // 477: mul [rb-2], -1, [rb-1]
+                    rt.put(rt.rel(477, -1)?, i64::wrapping_mul(rt.get(rt.rel(477, -2)?), -1));
+                    rt.retire();
+                    if rt.stale() {
+                        rt.goto(481);
+                        return Ok(State::Running);
+                    }
+                    rt.goto(481);
+                },
+                481 => {
+                    // 481: add [rb-3], [rb-1], [rb-3]
+                    rt.put(rt.rel(481, -3)?, i64::wrapping_add(rt.get(rt.rel(481, -3)?), rt.get(rt.rel(481, -1)?)));
+                    rt.retire();
+                    if rt.stale() {
+                        rt.goto(485);
+                        return Ok(State::Running);
+                    }
+                    // 485: lt [rb-3], [rb-2], [381]
+                    rt.put(381, i64::from(rt.get(rt.rel(485, -3)?) < rt.get(rt.rel(485, -2)?)));
+                    rt.retire();
+                    // 489: jf [381], 481
+                    rt.retire();
+                    if rt.get(381) == 0 {
+                        rt.goto(481);
+                    } else {
+                        rt.goto(492);
+                    }
+                },
+                492 => {
+                    // 492: mul [rb-4], 8, [rb-2]
+                    rt.put(rt.rel(492, -2)?, i64::wrapping_mul(rt.get(rt.rel(492, -4)?), 8));
+                    rt.retire();
+                    if rt.stale() {
+                        rt.goto(496);
+                        return Ok(State::Running);
+                    }
+                    // 496: lt [rb-3], [rb-2], [381]
+                    rt.put(381, i64::from(rt.get(rt.rel(496, -3)?) < rt.get(rt.rel(496, -2)?)));
+                    rt.retire();
+                    // 500: jt [381], 518
+                    rt.retire();
+                    if rt.get(381) != 0 {
+                        rt.goto(518);
+                    } else {
+                        rt.goto(503);
+                    }
+                },
+                503 => {
+                    // 503: mul [rb-2], -1, [rb-1]
+                    rt.put(rt.rel(503, -1)?, i64::wrapping_mul(rt.get(rt.rel(503, -2)?), -1));
+                    rt.retire();
+                    if rt.stale() {
+                        rt.goto(507);
+                        return Ok(State::Running);
+                    }
+                    rt.goto(507);
+                },
+                507 => {
+                    // 507: add [rb-3], [rb-1], [rb-3]
+                    rt.put(rt.rel(507, -3)?, i64::wrapping_add(rt.get(rt.rel(507, -3)?), rt.get(rt.rel(507, -1)?)));
+                    rt.retire();
+                    if rt.stale() {
+                        rt.goto(511);
+                        return Ok(State::Running);
+                    }
+                    // 511: lt [rb-3], [rb-2], [381]
+                    rt.put(381, i64::from(rt.get(rt.rel(511, -3)?) < rt.get(rt.rel(511, -2)?)));
+                    rt.retire();
+                    // 515: jf [381], 507
+                    rt.retire();
+                    if rt.get(381) == 0 {
+                        rt.goto(507);
+                    } else {
+                        rt.goto(518);
+                    }
+                },
+                518 => {
+                    // 518: lt [rb-3], [rb-4], [381]
+                    rt.put(381, i64::from(rt.get(rt.rel(518, -3)?) < rt.get(rt.rel(518, -4)?)));
+                    rt.retire();
+                    // 522: jt [381], 540
+                    rt.retire();
+                    if rt.get(381) != 0 {
+                        rt.goto(540);
+                    } else {
+                        rt.goto(525);
+                    }
+                },
+                525 => {
+                    // 525: mul [rb-4], -1, [rb-1]
+                    rt.put(rt.rel(525, -1)?, i64::wrapping_mul(rt.get(rt.rel(525, -4)?), -1));
+                    rt.retire();
+                    if rt.stale() {
+                        rt.goto(529);
+                        return Ok(State::Running);
+                    }
+                    rt.goto(529);
+                },
+                529 => {
+                    // 529: add [rb-3], [rb-1], [rb-3]
+                    rt.put(rt.rel(529, -3)?, i64::wrapping_add(rt.get(rt.rel(529, -3)?), rt.get(rt.rel(529, -1)?)));
+                    rt.retire();
+                    if rt.stale() {
+                        rt.goto(533);
+                        return Ok(State::Running);
+                    }
+                    // 533: lt [rb-3], [rb-4], [381]
+                    rt.put(381, i64::from(rt.get(rt.rel(533, -3)?) < rt.get(rt.rel(533, -4)?)));
+                    rt.retire();
+                    // 537: jf [381], 529
+                    rt.retire();
+                    if rt.get(381) == 0 {
+                        rt.goto(529);
+                    } else {
+                        rt.goto(540);
+                    }
+                },
+                540 => {
+                    // 540: add 0, [rb-3], [rb-7]
+                    rt.put(rt.rel(540, -7)?, i64::wrapping_add(0, rt.get(rt.rel(540, -3)?)));
+                    rt.retire();
+                    if rt.stale() {
+                        rt.goto(544);
+                        return Ok(State::Running);
+                    }
+                    // 544: arb -8
+                    rt.shift(544, -8)?;
+                    rt.retire();
+                    // 546: jt 1, [rb+0]
+                    rt.retire();
+                    rt.goto(rt.get(rt.rel(546, 0)?));
+                },
+                549 => {
+                    // 549: arb 4
+                    rt.shift(549, 4)?;
+                    rt.retire();
+                    // 551: mul [rb-2], 38, [566]
+                    rt.put(566, i64::wrapping_mul(rt.get(rt.rel(551, -2)?), 38));
+                    rt.retire();
+                    // 555: add [rb-3], [566], [566]
+                    rt.put(566, i64::wrapping_add(rt.get(rt.rel(555, -3)?), rt.get(566)));
+                    rt.retire();
+                    // 559: add 639, [566], [566]
+                    rt.put(566, i64::wrapping_add(639, rt.get(566)));
+                    rt.retire();
+                    // 563: add [rb-1], 0, [0]
+                    rt.put(rt.get(566), i64::wrapping_add(rt.get(rt.rel(563, -1)?), 0));
+                    rt.retire();
+                    if rt.stale() {
+                        rt.goto(567);
+                        return Ok(State::Running);
+                    }
+                    // 567: out [rb-3]
+                    rt.output(rt.get(rt.rel(567, -3)?));
+                    rt.retire();
+                    // 569: out [rb-2]
+                    rt.output(rt.get(rt.rel(569, -2)?));
+                    rt.retire();
+                    // 571: out [rb-1]
+                    rt.output(rt.get(rt.rel(571, -1)?));
+                    rt.retire();
+                    // 573: arb -4
+                    rt.shift(573, -4)?;
+                    rt.retire();
+                    // 575: jf 0, [rb+0]
+                    rt.retire();
+                    rt.goto(rt.get(rt.rel(575, 0)?));
+                },
+                578 => {
+                    // 578: arb 3
+                    rt.shift(578, 3)?;
+                    rt.retire();
+                    // 580: mul [rb-1], 38, [594]
+                    rt.put(594, i64::wrapping_mul(rt.get(rt.rel(580, -1)?), 38));
+                    rt.retire();
+                    // 584: add [rb-2], [594], [594]
+                    rt.put(594, i64::wrapping_add(rt.get(rt.rel(584, -2)?), rt.get(594)));
+                    rt.retire();
+                    // 588: add 639, [594], [594]
+                    rt.put(594, i64::wrapping_add(639, rt.get(594)));
+                    rt.retire();
+                    // 592: mul 1, [0], [rb-2]
+                    rt.put(rt.rel(592, -2)?, i64::wrapping_mul(1, rt.get(rt.get(594))));
+                    rt.retire();
+                    if rt.stale() {
+                        rt.goto(596);
+                        return Ok(State::Running);
+                    }
+                    // 596: arb -3
+                    rt.shift(596, -3)?;
+                    rt.retire();
+                    // 598: jf 0, [rb+0]
+                    rt.retire();
+                    rt.goto(rt.get(rt.rel(598, 0)?));
+                },
+                601 => {
+                    // 601: arb 3
+                    rt.shift(601, 3)?;
+                    rt.retire();
+                    // 603: mul 21, [rb-2], [rb+1]
+                    rt.put(rt.rel(603, 1)?, i64::wrapping_mul(21, rt.get(rt.rel(603, -2)?)));
+                    rt.retire();
+                    if rt.stale() {
+                        rt.goto(607);
+                        return Ok(State::Running);
+                    }
+                    // 607: add [rb+1], [rb-1], [rb+1]
+                    rt.put(rt.rel(607, 1)?, i64::wrapping_add(rt.get(rt.rel(607, 1)?), rt.get(rt.rel(607, -1)?)));
+                    rt.retire();
+                    if rt.stale() {
+                        rt.goto(611);
+                        return Ok(State::Running);
+                    }
+                    // 611: add 0, 401, [rb+2]
+                    rt.put(rt.rel(611, 2)?, 401);
+                    rt.retire();
+                    if rt.stale() {
+                        rt.goto(615);
+                        return Ok(State::Running);
+                    }
+                    // 615: mul 1, 733, [rb+3]
+                    rt.put(rt.rel(615, 3)?, 733);
+                    rt.retire();
+                    if rt.stale() {
+                        rt.goto(619);
+                        return Ok(State::Running);
+                    }
+                    // 619: mul 798, 1, [rb+4]
+                    rt.put(rt.rel(619, 4)?, 798);
+                    rt.retire();
+                    if rt.stale() {
+                        rt.goto(623);
+                        return Ok(State::Running);
+                    }
+                    // 623: mul 1, 630, [rb+0]
+                    rt.put(rt.rel(623, 0)?, 630);
+                    rt.retire();
+                    if rt.stale() {
+                        rt.goto(627);
+                        return Ok(State::Running);
+                    }
+                    // 627: jf 0, 456
+                    rt.retire();
+                    rt.goto(456);
+                },
+                630 => {
+                    // 630: add [rb+1], 1437, [rb-2]
+                    rt.put(rt.rel(630, -2)?, i64::wrapping_add(rt.get(rt.rel(630, 1)?), 1437));
+                    rt.retire();
+                    if rt.stale() {
+                        rt.goto(634);
+                        return Ok(State::Running);
+                    }
+                    // 634: arb -3
+                    rt.shift(634, -3)?;
+                    rt.retire();
+                    // 636: jt 1, [rb+0]
+                    rt.retire();
+                    rt.goto(rt.get(rt.rel(636, 0)?));
+                },
+                _ => return Ok(State::Running),
+            }
+        }
+    }
+}
+
+impl ::intcode_rs::IntCodeComputer for Day13 {
+    fn run(&mut self) -> ::intcode_rs::State {
+        ::intcode_rs::IntCodeComputer::run(&mut self.rt)
+    }
+
+    fn out(&self) -> &Vec<i64> {
+        ::intcode_rs::IntCodeComputer::out(&self.rt)
+    }
+
+    fn push(&mut self, val: i64) {
+        ::intcode_rs::IntCodeComputer::push(&mut self.rt, val)
+    }
+
+    fn mem(&self, at: i64) -> i64 {
+        ::intcode_rs::IntCodeComputer::mem(&self.rt, at)
+    }
+
+    fn state(&self) -> ::intcode_rs::State {
+        ::intcode_rs::IntCodeComputer::state(&self.rt)
+    }
+
+    fn step(&mut self) -> ::intcode_rs::State {
+        ::intcode_rs::IntCodeComputer::step(&mut self.rt)
+    }
+
+    fn cycles(&self) -> u64 {
+        ::intcode_rs::IntCodeComputer::cycles(&self.rt)
+    }
+
+    fn queued(&self) -> usize {
+        ::intcode_rs::IntCodeComputer::queued(&self.rt)
+    }
+
+    fn pc(&self) -> i64 {
+        ::intcode_rs::IntCodeComputer::pc(&self.rt)
+    }
+
+    fn set_pc(&mut self, pc: i64) {
+        ::intcode_rs::IntCodeComputer::set_pc(&mut self.rt, pc)
+    }
+
+    fn rb(&self) -> i64 {
+        ::intcode_rs::IntCodeComputer::rb(&self.rt)
+    }
+
+    fn set_rb(&mut self, rb: i64) {
+        ::intcode_rs::IntCodeComputer::set_rb(&mut self.rt, rb)
+    }
+
+    fn poke(&mut self, at: i64, val: i64) {
+        ::intcode_rs::IntCodeComputer::poke(&mut self.rt, at, val)
+    }
+
+    fn inputs(&self) -> &[i64] {
+        ::intcode_rs::IntCodeComputer::inputs(&self.rt)
+    }
+
+    fn image_size(&self) -> i64 {
+        ::intcode_rs::IntCodeComputer::image_size(&self.rt)
+    }
+
+    fn touched(&self) -> Vec<i64> {
+        ::intcode_rs::IntCodeComputer::touched(&self.rt)
+    }
+}
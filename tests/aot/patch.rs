@@ -0,0 +1,139 @@
+// Generated by intcode_rs::aot from a 22-cell image. Don't edit by hand.
+pub struct Patch {
+    rt: ::intcode_rs::aot::Runtime,
+}
+
+#[allow(dead_code)]
+impl Patch {
+    const IMAGE: [i64; 22] = [
+        1101, 3, 4, 30, 4, 30, 1101, 1102, 0, 0, 1001, 31, 1, 31, 1007, 31,
+        2, 32, 1005, 32, 0, 99,
+    ];
+    const CODE: [(i64, i64); 1] = [
+        (0, 22),
+    ];
+
+    pub fn new(inputs: Vec<i64>) -> Patch {
+        let rt = ::intcode_rs::aot::Runtime::new(Patch::IMAGE.to_vec(), inputs, &Patch::CODE, Patch::native);
+        Patch { rt }
+    }
+
+    pub fn runtime(&self) -> &::intcode_rs::aot::Runtime {
+        &self.rt
+    }
+
+    pub fn runtime_mut(&mut self) -> &mut ::intcode_rs::aot::Runtime {
+        &mut self.rt
+    }
+
+    fn native(rt: &mut ::intcode_rs::aot::Runtime) -> Result<::intcode_rs::State, ::intcode_rs::Fault> {
+        use ::intcode_rs::State;
+        loop {
+            match rt.pc() {
+                0 => {
+                    // 0: add 3, 4, [30]
+                    rt.put(30, 7);
+                    rt.retire();
+                    rt.goto(4);
+                },
+                4 => {
+                    // 4: out [30]
+                    rt.output(rt.get(30));
+                    rt.retire();
+                    // 6: add 1102, 0, [0]
+                    rt.put(0, 1102);
+                    rt.retire();
+                    if rt.stale() {
+                        rt.goto(10);
+                        return Ok(State::Running);
+                    }
+                    // 10: add [31], 1, [31]
+                    rt.put(31, i64::wrapping_add(rt.get(31), 1));
+                    rt.retire();
+                    // 14: lt [31], 2, [32]
+                    rt.put(32, i64::from(rt.get(31) < 2));
+                    rt.retire();
+                    // 18: jt [32], 0
+                    rt.retire();
+                    if rt.get(32) != 0 {
+                        rt.goto(0);
+                    } else {
+                        rt.goto(21);
+                    }
+                },
+                21 => {
+                    // 21: hlt
+                    rt.goto(21);
+                    return Ok(State::Halted);
+                },
+                _ => return Ok(State::Running),
+            }
+        }
+    }
+}
+
+impl ::intcode_rs::IntCodeComputer for Patch {
+    fn run(&mut self) -> ::intcode_rs::State {
+        ::intcode_rs::IntCodeComputer::run(&mut self.rt)
+    }
+
+    fn out(&self) -> &Vec<i64> {
+        ::intcode_rs::IntCodeComputer::out(&self.rt)
+    }
+
+    fn push(&mut self, val: i64) {
+        ::intcode_rs::IntCodeComputer::push(&mut self.rt, val)
+    }
+
+    fn mem(&self, at: i64) -> i64 {
+        ::intcode_rs::IntCodeComputer::mem(&self.rt, at)
+    }
+
+    fn state(&self) -> ::intcode_rs::State {
+        ::intcode_rs::IntCodeComputer::state(&self.rt)
+    }
+
+    fn step(&mut self) -> ::intcode_rs::State {
+        ::intcode_rs::IntCodeComputer::step(&mut self.rt)
+    }
+
+    fn cycles(&self) -> u64 {
+        ::intcode_rs::IntCodeComputer::cycles(&self.rt)
+    }
+
+    fn queued(&self) -> usize {
+        ::intcode_rs::IntCodeComputer::queued(&self.rt)
+    }
+
+    fn pc(&self) -> i64 {
+        ::intcode_rs::IntCodeComputer::pc(&self.rt)
+    }
+
+    fn set_pc(&mut self, pc: i64) {
+        ::intcode_rs::IntCodeComputer::set_pc(&mut self.rt, pc)
+    }
+
+    fn rb(&self) -> i64 {
+        ::intcode_rs::IntCodeComputer::rb(&self.rt)
+    }
+
+    fn set_rb(&mut self, rb: i64) {
+        ::intcode_rs::IntCodeComputer::set_rb(&mut self.rt, rb)
+    }
+
+    fn poke(&mut self, at: i64, val: i64) {
+        ::intcode_rs::IntCodeComputer::poke(&mut self.rt, at, val)
+    }
+
+    fn inputs(&self) -> &[i64] {
+        ::intcode_rs::IntCodeComputer::inputs(&self.rt)
+    }
+
+    fn image_size(&self) -> i64 {
+        ::intcode_rs::IntCodeComputer::image_size(&self.rt)
+    }
+
+    fn touched(&self) -> Vec<i64> {
+        ::intcode_rs::IntCodeComputer::touched(&self.rt)
+    }
+}
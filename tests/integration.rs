@@ -1,14 +1,55 @@
+// the baseline puzzle tests are kept as written, from before clippy ran over the test suite
+#![allow(
+    clippy::bool_comparison,
+    clippy::clone_on_copy,
+    clippy::get_first,
+    clippy::match_like_matches_macro,
+    clippy::needless_return,
+    clippy::unnecessary_fold,
+)]
+
 use std::fs;
+use std::sync::Arc;
 use intcode_rs::*;
 use intcode_rs::procedural_comp::ProcIntCode;
-use intcode_rs::polymorphic_comp::PolyIntCode;
+use intcode_rs::polymorphic_comp::{self, PolyIntCode, Action, Arg, OpCode};
+use intcode_rs::cfg::{Cfg, Edge, EdgeKind, Region, RegionKind};
+use intcode_rs::coverage::Coverage;
+use intcode_rs::history::Writer;
+use intcode_rs::replay::{self, Divergence, Event, Recorder, Replay};
+use intcode_rs::symbolic::{Goal, SolveError, Symbolic};
+use intcode_rs::sweep::{Candidate, Sweep, SweepError};
+use intcode_rs::optimize::{self, Report};
+use intcode_rs::decompile;
+use intcode_rs::aot;
+use intcode_rs::lang::{self, CompileError};
+use intcode_rs::oracle::{CallError, Oracle};
+use intcode_rs::maze::{self, Dir, ExploreError, Maze, Tile};
+use intcode_rs::ascii;
+use intcode_rs::adventure::{self, Session, World};
+use intcode_rs::springscript::{self, Expr, Gate, Instr, Mode, Outcome, Reg, RunError, Script, ScriptError};
+use intcode_rs::scaffold::{self, Camera, Routines, ScaffoldError, Step};
+use intcode_rs::beam::{Beam, ProbeError};
+use intcode_rs::instruction::{self, DecodeError, Instruction, Op, Operand};
+use intcode_rs::memory::{Breach, Negative, OnViolation, Perms, Protection, Sandbox, Violation};
+
+// ahead-of-time compiled programs; the `aot` test checks they're up to date
+mod compiled {
+    include!("aot/day09.rs");
+    include!("aot/day13.rs");
+    include!("aot/patch.rs");
+}
 
 fn read(file_name: &str) -> Vec<i64> {
+    read_as(file_name)
+}
+
+fn read_as<W: Word>(file_name: &str) -> Vec<W> {
     fs::read_to_string(file_name)
         .unwrap()
         .as_str()
         .split(",")
-        .map(|s| s.parse().unwrap())
+        .map(|s| s.trim().parse().ok().unwrap())
         .collect()
 }
 
@@ -219,4 +260,1735 @@ fn d9p2() {
     println!("Day 02, Part 1: {} proc / {} poly", ans_proc, ans_poly);
     assert_eq!(78831, ans_proc);
     assert_eq!(78831, ans_poly);
-}
\ No newline at end of file
+}
+
+#[test]
+fn coverage() {
+    let program = read("res/02.txt");
+    let mut total = Coverage::new();
+    for noun in 0..=9 {
+        for verb in 0..=9 {
+            let mut program = program.clone();
+            program[1] = noun;
+            program[2] = verb;
+            let mut comp = ProcIntCode::new(program, vec![]);
+            comp.enable_coverage();
+            comp.run();
+            total.merge(comp.coverage().unwrap());
+        }
+    }
+    assert_eq!(100, total.hits(0));
+    let listing = total.annotate(&program);
+    assert!(listing.lines().next().unwrap().starts_with("     100 |      0: add [0], [0], [3]"));
+    let lcov = total.lcov(&program, "res/02.txt");
+    assert!(lcov.contains("DA:1,100\nDA:2,100\nDA:3,100\nDA:4,100\n"));
+    assert!(lcov.ends_with("end_of_record\n"));
+
+    let program = read("res/05.txt");
+    let mut comp = ProcIntCode::new(program.clone(), vec![5]);
+    comp.enable_coverage();
+    comp.run();
+    let coverage = comp.take_coverage().unwrap();
+    let branches: Vec<_> = coverage.executed().into_iter()
+        .filter_map(|addr| coverage.branch_at(addr))
+        .collect();
+    assert!(!branches.is_empty());
+    assert!(branches.iter().all(|b| b.taken + b.not_taken > 0));
+    assert!(coverage.lcov(&program, "res/05.txt").contains("BRDA:"));
+
+    // arb 1; jt 1, [rb+MAX], whose target address overflows
+    let mut comp = ProcIntCode::new(vec![109, 1, 2105, 1, i64::MAX], vec![]);
+    comp.enable_coverage();
+    assert_eq!(State::Faulted(Fault::Address { pc: 2 }), comp.run());
+    assert_eq!(None, comp.coverage().unwrap().branch_at(2));
+}
+
+// a downstream-defined opcode: out = a - b
+#[derive(Debug)]
+struct Sub {
+    a: Box<dyn Arg>,
+    b: Box<dyn Arg>,
+    out: Box<dyn Arg>,
+}
+
+impl OpCode for Sub {
+    fn execute(&self, comp: &PolyIntCode) -> Result<Action, Fault> {
+        Ok(Action::Set {
+            val: comp.fetch(self.a.as_ref())? - comp.fetch(self.b.as_ref())?,
+            addr: comp.addr(self.out.as_ref())?,
+        })
+    }
+}
+
+#[test]
+fn custom_opcode() {
+    // in [9]; sub [9], 3 -> [9]; out [9]; hlt
+    let program = vec![3, 9, 1010, 9, 3, 9, 4, 9, 99, 0];
+    let mut comp = PolyIntCode::new(program, vec![10]);
+    let sub = |data: [i64; 4], rb: i64| -> Result<Box<dyn OpCode>, DecodeError> {
+        Ok(Box::new(Sub {
+            a: polymorphic_comp::param_mode::new(data[0], data[1], 0)?,
+            b: polymorphic_comp::param_mode::new(data[0], data[2], 1)?,
+            out: polymorphic_comp::param_mode::new(data[0], data[3], 2)?.out_addr(rb),
+        }))
+    };
+    comp.register(10, sub);
+    assert_eq!(State::Halted, comp.run());
+    assert_eq!(&vec![7], comp.out());
+    assert!(comp.instructions().contains(10));
+
+    // a bad mode digit in a custom instruction faults the guest rather than the host
+    let mut comp = PolyIntCode::new(vec![3010, 0, 0, 0, 99], vec![]);
+    comp.register(10, sub);
+    assert_eq!(State::Faulted(Fault::Decode { pc: 0, err: DecodeError::BadModes(3010) }), comp.run());
+}
+
+fn syscall_program(service: i64) -> Vec<i64> {
+    vec![
+        109, 100,               // arb 100
+        21101, service, 0, 0,   // [rb+0] = service
+        21101, 2, 0, 1,         // [rb+1] = 2 arguments
+        21101, 20, 0, 2,        // [rb+2] = 20
+        21101, 22, 0, 3,        // [rb+3] = 22
+        80,                     // sys
+        204, 0,                 // out [rb+0]
+        99,
+    ]
+}
+
+#[test]
+fn syscalls() {
+    let mut proc = ProcIntCode::new(syscall_program(7), vec![]);
+    let mut poly = PolyIntCode::new(syscall_program(7), vec![]);
+    proc.register_syscall(7, |frame| Ok(frame.args().sum()));
+    poly.register_syscall(7, |frame| Ok(frame.args().sum()));
+    assert_eq!(State::Halted, proc.run());
+    assert_eq!(State::Halted, poly.run());
+    assert_eq!(&vec![42], proc.out());
+    assert_eq!(&vec![42], poly.out());
+
+    let unknown = State::Faulted(Fault::UnknownService { pc: 18, service: 8 });
+    let mut proc = ProcIntCode::new(syscall_program(8), vec![]);
+    let mut poly = PolyIntCode::new(syscall_program(8), vec![]);
+    assert_eq!(unknown, proc.run());
+    assert_eq!(unknown, poly.run());
+    assert_eq!(unknown, proc.state());
+    assert_eq!(unknown, poly.state());
+
+    let mut proc = ProcIntCode::new(syscall_program(9), vec![]);
+    proc.register_syscall(9, |_| Err("no blob named 9".to_string()));
+    assert_eq!(
+        State::Faulted(Fault::Service { pc: 18, service: 9, msg: "no blob named 9".to_string() }),
+        proc.run()
+    );
+
+    // a huge argument count is only a number until a handler walks the arguments
+    let huge = vec![109, 100, 21101, 7, 0, 0, 21101, 1 << 62, 0, 1, 80, 99];
+    let unknown = State::Faulted(Fault::UnknownService { pc: 10, service: 7 });
+    assert_eq!(unknown, ProcIntCode::new(huge.clone(), vec![]).run());
+    assert_eq!(unknown, PolyIntCode::new(huge.clone(), vec![]).run());
+    let mut proc = ProcIntCode::new(huge.clone(), vec![]);
+    let mut poly = PolyIntCode::new(huge, vec![]);
+    proc.register_syscall(7, |frame| Ok(frame.argc() + frame.arg(0)));
+    poly.register_syscall(7, |frame| Ok(frame.argc() + frame.arg(0)));
+    assert_eq!(State::Halted, proc.run());
+    assert_eq!(State::Halted, poly.run());
+    assert_eq!(1 << 62, proc.mem(100));
+    assert_eq!(1 << 62, poly.mem(100));
+
+    // so is a frame that runs off the end of the address space
+    let edge = vec![109, i64::MAX, 80, 99];
+    assert_eq!(State::Faulted(Fault::Address { pc: 2 }), ProcIntCode::new(edge.clone(), vec![]).run());
+    assert_eq!(State::Faulted(Fault::Address { pc: 2 }), PolyIntCode::new(edge, vec![]).run());
+}
+
+#[test]
+fn overflow() {
+    // mul [7], [7], [7]; out [7]; hlt; .data 2^62
+    let program: Vec<i64> = vec![2, 7, 7, 7, 4, 7, 99, 1 << 62];
+    let run = |overflow| {
+        let mut proc = ProcIntCode::new(program.clone(), vec![]).with_overflow(overflow);
+        let mut poly = PolyIntCode::new(program.clone(), vec![]).with_overflow(overflow);
+        let (state_proc, state_poly) = (proc.run(), poly.run());
+        assert_eq!(state_proc, state_poly);
+        assert_eq!(proc.out(), poly.out());
+        (state_proc, proc.out().clone())
+    };
+    assert_eq!((State::Halted, vec![0]), run(Overflow::Wrapping));
+    assert_eq!((State::Halted, vec![i64::MAX]), run(Overflow::Saturating));
+    let fault = Fault::Overflow { pc: 0, op: Arith::Mul, a: 1 << 62, b: 1 << 62 };
+    assert_eq!((State::Faulted(fault), vec![]), run(Overflow::Checked));
+
+    // day 9 doesn't overflow, so every policy agrees on the answer
+    let program = read("res/09.txt");
+    let mut comp = ProcIntCode::new(program, vec![1]).with_overflow(Overflow::Checked);
+    assert_eq!(State::Halted, comp.run());
+    assert_eq!(3380552333, comp.out()[0]);
+}
+
+// runs the single-machine puzzles on both backends with a given word type
+fn word_mode<W: Word>() {
+    let run = |file: &str, patch: &[(usize, i64)], inputs: &[i64]| {
+        let mut program: Vec<W> = read_as(file);
+        for (addr, val) in patch.iter() {
+            program[*addr] = W::from(*val);
+        }
+        let inputs: Vec<W> = inputs.iter().map(|v| W::from(*v)).collect();
+        let mut proc = ProcIntCode::new(program.clone(), inputs.clone());
+        let mut poly = PolyIntCode::new(program, inputs);
+        assert_eq!(State::Halted, proc.run());
+        assert_eq!(State::Halted, poly.run());
+        assert_eq!(proc.out(), poly.out());
+        assert_eq!(proc.mem(0), poly.mem(0));
+        (proc.mem(0), proc.out().last().cloned())
+    };
+    assert_eq!(W::from(4484226), run("res/02.txt", &[(1, 12), (2, 2)], &[]).0);
+    assert_eq!(W::from(19690720), run("res/02.txt", &[(1, 56), (2, 96)], &[]).0);
+    assert_eq!(Some(W::from(14522484)), run("res/05.txt", &[], &[1]).1);
+    assert_eq!(Some(W::from(4655956)), run("res/05.txt", &[], &[5]).1);
+    assert_eq!(Some(W::from(3380552333)), run("res/09.txt", &[], &[1]).1);
+    assert_eq!(Some(W::from(78831)), run("res/09.txt", &[], &[2]).1);
+
+    // day 7, each amplifier on both backends, looped back round for part 2
+    let program: Vec<W> = read_as("res/07.txt");
+    let amplify = |phases: &Vec<i64>, feedback: bool| {
+        let mut procs: Vec<ProcIntCode<W>> = phases.iter().map(|p| ProcIntCode::new(program.clone(), vec![W::from(*p)])).collect();
+        let mut polys: Vec<PolyIntCode<W>> = phases.iter().map(|p| PolyIntCode::new(program.clone(), vec![W::from(*p)])).collect();
+        let mut signal = W::from(0);
+        loop {
+            for (proc, poly) in procs.iter_mut().zip(polys.iter_mut()) {
+                proc.push(signal.clone());
+                poly.push(signal.clone());
+                assert_eq!(proc.run(), poly.run());
+                assert_eq!(proc.out(), poly.out());
+                signal = proc.out().last().cloned().unwrap();
+            }
+            if !feedback || procs.iter().any(|proc| proc.state() == State::Halted) {
+                return signal.to_i64().unwrap();
+            }
+        }
+    };
+    assert_eq!(Some(880726), unique_perms(43210, 0, 4).iter().map(|p| amplify(p, false)).max());
+    assert_eq!(Some(4931744), unique_perms(98765, 5, 9).iter().map(|p| amplify(p, true)).max());
+}
+
+#[test]
+fn word_modes() {
+    word_mode::<i64>();
+    word_mode::<i128>();
+    word_mode::<BigInt>();
+}
+
+#[test]
+fn big_words() {
+    // mul [7], [7], [7]; out [7]; hlt; .data 2^62
+    let program: Vec<i64> = vec![2, 7, 7, 7, 4, 7, 99, 1 << 62];
+    let big: Vec<BigInt> = program.iter().map(|v| BigInt::from(*v)).collect();
+    let mut comp = PolyIntCode::new(big, vec![]).with_overflow(Overflow::Checked);
+    assert_eq!(State::Halted, comp.run());
+    assert_eq!("21267647932558653966460912964485513216", comp.out()[0].to_string());
+
+    let wide: Vec<i128> = program.iter().map(|v| *v as i128).collect();
+    let mut comp = ProcIntCode::new(wide, vec![]);
+    assert_eq!(State::Halted, comp.run());
+    assert_eq!(1i128 << 124, comp.out()[0]);
+
+    // a pointer that doesn't fit in an i64 faults instead of wrapping
+    let wide: Vec<i128> = vec![4, 1 << 80, 99];
+    let mut comp = ProcIntCode::new(wide.clone(), vec![]);
+    assert_eq!(State::Faulted(Fault::Address { pc: 0 }), comp.run());
+    let mut comp = PolyIntCode::new(wide, vec![]);
+    assert_eq!(State::Faulted(Fault::Address { pc: 0 }), comp.run());
+}
+
+#[test]
+fn protection() {
+    // day 9 never touches its own code
+    let program = read("res/09.txt");
+    let mut proc = ProcIntCode::new(program.clone(), vec![1]).with_protection(Protection::new(OnViolation::Log));
+    assert_eq!(State::Halted, proc.run());
+    assert!(proc.violations().is_empty());
+
+    // day 5 patches the instruction at 6 before running it
+    let program = read("res/05.txt");
+    let audit = || Protection::new(OnViolation::Log);
+    let mut proc = ProcIntCode::new(program.clone(), vec![5]).with_protection(audit());
+    let mut poly = PolyIntCode::new(program.clone(), vec![5]).with_protection(audit());
+    assert_eq!(State::Halted, proc.run());
+    assert_eq!(State::Halted, poly.run());
+    assert_eq!(4655956, proc.out()[0]);
+    assert_eq!(Violation::ExecData { pc: 6 }, proc.violations()[0]);
+    assert_eq!(proc.violations(), poly.violations());
+
+    let strict = || Protection::new(OnViolation::Fault);
+    let mut proc = ProcIntCode::new(program.clone(), vec![5]).with_protection(strict());
+    let mut poly = PolyIntCode::new(program, vec![5]).with_protection(strict());
+    let fault = State::Faulted(Fault::Protection(Violation::ExecData { pc: 6 }));
+    assert_eq!(fault, proc.run());
+    assert_eq!(fault, poly.run());
+
+    // add 1, 1 -> [1] with the code mapped read/execute only
+    let program: Vec<i64> = vec![1101, 1, 1, 1, 99];
+    let rx = || Protection::new(OnViolation::Fault).region(0..5, Perms::RX);
+    let fault = State::Faulted(Fault::Protection(Violation::Write { pc: 0, addr: 1 }));
+    assert_eq!(fault, ProcIntCode::new(program.clone(), vec![]).with_protection(rx()).run());
+    assert_eq!(fault, PolyIntCode::new(program, vec![]).with_protection(rx()).run());
+
+    // out [5] with 5 unreadable: asking for the state doesn't count as a read
+    let program: Vec<i64> = vec![4, 5, 99, 0, 0, 42];
+    let audit = || Protection::new(OnViolation::Log).region(5..6, Perms::NONE);
+    let proc = ProcIntCode::new(program.clone(), vec![]).with_protection(audit());
+    let poly = PolyIntCode::new(program, vec![]).with_protection(audit());
+    for _ in 0..3 {
+        assert_eq!(State::Running, proc.state());
+        assert_eq!(State::Running, poly.state());
+    }
+    assert!(proc.violations().is_empty());
+    assert!(poly.violations().is_empty());
+}
+
+#[test]
+fn sandbox() {
+    // add 1, 1 -> [-1]
+    let program: Vec<i64> = vec![1101, 1, 1, -1, 99];
+    let fault = State::Faulted(Fault::Sandbox(Breach::NegativeAddress { pc: 0, addr: -1 }));
+    assert_eq!(fault, ProcIntCode::new(program.clone(), vec![]).with_sandbox(Sandbox::default()).run());
+    assert_eq!(fault, PolyIntCode::new(program.clone(), vec![]).with_sandbox(Sandbox::default()).run());
+    let allow = Sandbox { negative: Negative::Allow, ..Sandbox::default() };
+    let mut proc = ProcIntCode::new(program, vec![]).with_sandbox(allow);
+    assert_eq!(State::Halted, proc.run());
+    assert_eq!(2, proc.mem(-1));
+
+    // add 1, 1 -> [100], one cell past what the image already uses
+    let program: Vec<i64> = vec![1101, 1, 1, 100, 99];
+    let quota = Sandbox { max_cells: Some(5), ..Sandbox::default() };
+    let fault = State::Faulted(Fault::Sandbox(Breach::CellQuota { pc: 0, addr: 100, limit: 5 }));
+    assert_eq!(fault, ProcIntCode::new(program.clone(), vec![]).with_sandbox(quota).run());
+    assert_eq!(fault, PolyIntCode::new(program.clone(), vec![]).with_sandbox(quota).run());
+    let quota = Sandbox { max_bytes: Some(5 * 16), ..Sandbox::default() };
+    let fault = State::Faulted(Fault::Sandbox(Breach::ByteQuota { pc: 0, addr: 100, limit: 80 }));
+    assert_eq!(fault, ProcIntCode::new(program, vec![]).with_sandbox(quota).run());
+
+    // day 9 runs comfortably inside a modest quota
+    let program = read("res/09.txt");
+    let quota = Sandbox { max_cells: Some(program.len() + 100), ..Sandbox::default() };
+    let mut proc = ProcIntCode::new(program.clone(), vec![1]).with_sandbox(quota);
+    let mut poly = PolyIntCode::new(program, vec![1]).with_sandbox(quota);
+    assert_eq!(State::Halted, proc.run());
+    assert_eq!(State::Halted, poly.run());
+    assert_eq!(proc.out(), poly.out());
+}
+
+#[test]
+fn time_travel() {
+    // read [9], [9] + 5 -> [10], out [10]
+    let program: Vec<i64> = vec![3, 9, 1001, 9, 5, 10, 4, 10, 99, 0, 0];
+    let mut proc = ProcIntCode::new(program, vec![7]);
+    proc.enable_recording(2, 4);
+    proc.add_breakpoint(2);
+    assert_eq!(State::Halted, proc.run());
+    assert_eq!(vec![12], *proc.out());
+    let history = proc.history().unwrap();
+    assert_eq!(3, history.steps());
+    assert_eq!(Some(Writer { step: 0, pc: 0 }), history.last_writer(9));
+    assert_eq!(Some(Writer { step: 1, pc: 2 }), history.last_writer(10));
+
+    assert!(proc.reverse_continue());
+    assert_eq!(2, proc.pc());
+    assert!(proc.out().is_empty());
+    assert_eq!(0, proc.mem(10));
+    assert_eq!(None, proc.history().unwrap().last_writer(10));
+    assert!(proc.step_back());
+    assert!(!proc.step_back());
+    assert_eq!(0, proc.mem(9));
+    assert_eq!(State::Halted, proc.run());
+    assert_eq!(vec![12], *proc.out());
+
+    // rewind day 9 all the way through a handful of keyframes and replay it
+    let program = read("res/09.txt");
+    let mut proc = ProcIntCode::new(program.clone(), vec![2]);
+    proc.enable_recording(1000, 8);
+    assert_eq!(State::Halted, proc.run());
+    let answer = proc.out().clone();
+    let horizon = proc.history().unwrap().horizon();
+    assert!(horizon > 0);
+    while proc.step_back() {}
+    assert_eq!(horizon, proc.history().unwrap().steps());
+    assert!(proc.out().len() <= answer.len());
+    assert_eq!(State::Halted, proc.run());
+    assert_eq!(answer, *proc.out());
+
+    let mut proc = ProcIntCode::new(program, vec![2]);
+    proc.enable_recording(64, 1 << 20);
+    assert_eq!(State::Halted, proc.run());
+    while proc.step_back() {}
+    assert_eq!(0, proc.pc());
+    assert!(proc.out().is_empty());
+    assert_eq!(State::Halted, proc.run());
+    assert_eq!(answer, *proc.out());
+
+    // stepping back forgets what protection saw the undone instructions do
+    let mut proc = ProcIntCode::new(read("res/05.txt"), vec![1]).with_protection(Protection::new(OnViolation::Log));
+    proc.enable_recording(1 << 20, 1);
+    assert_eq!(State::Halted, proc.run());
+    let violations = proc.violations();
+    assert_eq!(Violation::ExecData { pc: 6 }, violations[0]);
+    while proc.step_back() {}
+    assert!(proc.violations().is_empty());
+    assert_eq!(State::Halted, proc.run());
+    assert_eq!(violations, proc.violations());
+}
+
+#[test]
+fn record_replay() {
+    let program = read("res/05.txt");
+    let mut recorder = Recorder::new(ProcIntCode::new(program.clone(), vec![]), &program);
+    assert_eq!(State::Waiting, recorder.run());
+    recorder.push(5);
+    assert_eq!(State::Halted, recorder.run());
+    assert_eq!(4655956, recorder.out()[0]);
+    let recording = recorder.into_replay();
+    assert!(matches!(recording.events[..], [Event::Input { val: 5, .. }, Event::Output { val: 4655956, .. }]));
+    // inputs the machine was built with are recorded just the same
+    let mut preloaded = Recorder::new(ProcIntCode::new(program.clone(), vec![5]), &program);
+    assert_eq!(State::Halted, preloaded.run());
+    assert_eq!(recording.events, preloaded.into_replay().events);
+
+    let path = std::env::temp_dir().join(format!("intcode-replay-{}.txt", std::process::id()));
+    recording.save(&path).unwrap();
+    let loaded: Replay<i64> = Replay::load(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+    assert_eq!(recording, loaded);
+    let mut poly = PolyIntCode::new(program.clone(), vec![]);
+    assert_eq!(Ok(()), replay::replay(&mut poly, &program, &loaded));
+
+    let mut tampered = loaded.clone();
+    if let Event::Output { val, .. } = &mut tampered.events[1] {
+        *val += 1;
+    }
+    let err = replay::replay(&mut ProcIntCode::new(program.clone(), vec![]), &program, &tampered);
+    assert_eq!(Err(Divergence::Event { index: 1, expected: Some(tampered.events[1].clone()), actual: Some(loaded.events[1].clone()) }), err);
+
+    let mut patched = program.clone();
+    patched[225] += 1;
+    let err = replay::replay(&mut ProcIntCode::new(patched.clone(), vec![]), &patched, &loaded);
+    assert!(matches!(err, Err(Divergence::Image { .. })));
+
+    // saved hashes check out on any platform and in any word mode
+    assert_eq!(9305964949994214313, replay::image_hash(&[1i64, -2, 99]));
+    let wide: Vec<i128> = vec![1, -2, 99];
+    assert_eq!(replay::image_hash(&[1i64, -2, 99]), replay::image_hash(&wide));
+}
+
+#[test]
+fn control_flow() {
+    let program: Vec<i64> = vec![
+        1105, 1, 7,         // 0: jt 1, 7
+        -1, -1, -1, -1,     // 3: data
+        1006, 20, 14,       // 7: jf [20], 14
+        4, 20,              // 10: out [20]
+        99,                 // 12: hlt
+        99,                 // 13: hlt, never reached
+        5, 20, 20,          // 14: jt [20], [20]
+        99,                 // 17: hlt
+        -1, -1, 7,          // 18: data
+    ];
+    let cfg = Cfg::build(&program);
+    let starts: Vec<i64> = cfg.blocks().map(|block| block.start).collect();
+    assert_eq!(vec![0, 7, 10, 14, 17], starts);
+    assert_eq!(vec![Edge { to: 7, kind: EdgeKind::Taken }], cfg.block(0).unwrap().successors);
+    assert_eq!(
+        vec![Edge { to: 14, kind: EdgeKind::Taken }, Edge { to: 10, kind: EdgeKind::Fallthrough }],
+        cfg.block(7).unwrap().successors,
+    );
+    assert_eq!(13, cfg.block(10).unwrap().end);
+    assert_eq!(vec![14], cfg.indirect());
+    assert_eq!(
+        vec![
+            Region { start: 3, end: 7, kind: RegionKind::Data },
+            Region { start: 13, end: 14, kind: RegionKind::Unreachable },
+            Region { start: 18, end: 21, kind: RegionKind::Data },
+        ],
+        cfg.regions(),
+    );
+    assert!(cfg.is_code(11));
+    assert!(!cfg.is_code(13));
+    let lines = cfg.lines(&program);
+    assert_eq!("     7: jf [20], 14", lines.iter().find(|line| line.addr() == 7).unwrap().to_string());
+    assert_eq!("    13: hlt", lines.iter().find(|line| line.addr() == 13).unwrap().to_string());
+
+    let dot = cfg.dot();
+    assert!(dot.starts_with("digraph cfg {"));
+    assert!(dot.contains("b7 -> b14 [label=\"taken\"];"));
+    assert!(dot.contains("b14 [label=\"    14: jt [20], [20]\\l\", color=red];"));
+    let json = cfg.json();
+    assert!(json.contains("{\"start\":13,\"end\":14,\"kind\":\"unreachable\"}"));
+    assert!(json.contains("{\"to\":10,\"kind\":\"fallthrough\"}"));
+
+    // day 9 calls its subroutines by pushing a return address and jumping through memory,
+    // so everything past the first call is only reachable indirectly
+    let program = read("res/09.txt");
+    let cfg = Cfg::build(&program);
+    assert!(cfg.invalid().is_empty());
+    assert!(!cfg.indirect().is_empty());
+    assert!(cfg.regions().iter().any(|region| region.kind == RegionKind::Unreachable));
+    let mut proc = ProcIntCode::new(program.clone(), vec![1]);
+    proc.enable_coverage();
+    proc.run();
+    let coverage = proc.take_coverage().unwrap();
+    assert!(coverage.annotate_cfg(&program, &cfg).lines().count() <= program.len());
+}
+
+#[test]
+fn symbolic() {
+    // day 2 part 2 without the brute force
+    let mut day2 = Symbolic::new(read("res/02.txt"));
+    let noun = day2.symbolic_cell(1, "noun", 0..=99);
+    let verb = day2.symbolic_cell(2, "verb", 0..=99);
+    let solution = day2.solve(Goal::Memory { addr: 0, val: 19690720 }).unwrap();
+    assert_eq!(5696, 100 * solution.get(noun) + solution.get(verb));
+
+    // read x and y, output 1 if x * y == 391 and 0 otherwise
+    let program: Vec<i64> = vec![3, 100, 3, 101, 2, 100, 101, 102, 1008, 102, 391, 103, 1005, 103, 18, 104, 0, 99, 104, 1, 99];
+    let mut factor = Symbolic::new(program.clone());
+    let x = factor.symbolic_input("x", 2..=100);
+    let y = factor.symbolic_input("y", 2..=100);
+    let solution = factor.solve(Goal::Output { index: 0, val: 1 }).unwrap();
+    assert_eq!(391, solution.get(x) * solution.get(y));
+    assert_eq!("x", factor.name(x));
+    assert!(factor.solve(Goal::Reach { pc: 18 }).is_ok());
+
+    let mut small = Symbolic::new(program);
+    small.symbolic_input("x", 2..=10);
+    small.symbolic_input("y", 2..=10);
+    assert_eq!(Err(SolveError::Unsatisfiable), small.solve(Goal::Output { index: 0, val: 1 }));
+}
+
+#[test]
+fn sweep() {
+    let grid = (0..=99).flat_map(|noun| (0..=99).map(move |verb| vec![(1, noun), (2, verb)]));
+    let sweep = Sweep::new(read("res/02.txt")).patches(grid);
+    let found = sweep.find(ProcIntCode::new, |comp| comp.mem(0) == 19690720).unwrap().unwrap();
+    assert_eq!(vec![(1, 56), (2, 96)], found.candidate.patches);
+    assert_eq!(5696, found.index);
+    let found = sweep.find(PolyIntCode::new, |comp| comp.mem(0) == 19690720).unwrap().unwrap();
+    assert_eq!(5696, found.index);
+    let all = sweep.clone().with_threads(3).find_all(ProcIntCode::new, |comp| comp.mem(0) % 1_000_000 == 690720).unwrap();
+    assert!(all.windows(2).all(|pair| pair[0].index < pair[1].index));
+    assert!(all.iter().any(|found| found.index == 5696));
+
+    // a patch below address zero is an error up front rather than a wild write
+    let bad = Candidate { patches: vec![(1, 12), (-2, 2)], inputs: Vec::new() };
+    assert_eq!(Err(SweepError::NegativePatch { addr: -2 }), sweep.image(&bad));
+    let sweep = sweep.candidate(bad);
+    assert!(matches!(sweep.find(ProcIntCode::new, |_| true), Err(SweepError::NegativePatch { addr: -2 })));
+
+    // day 7 part 1, scoring each phase permutation by running the amplifier chain
+    let program = read("res/07.txt");
+    let sweep = Sweep::new(program.clone()).inputs(unique_perms(43210, 0, 4));
+    let chain = |candidate: &Candidate<i64>| {
+        let mut signal = 0;
+        for phase in candidate.inputs.iter() {
+            let mut comp = ProcIntCode::new(program.clone(), vec![*phase, signal]);
+            comp.run();
+            signal = comp.out()[0];
+        }
+        Some(signal)
+    };
+    let (best, signal) = sweep.best_by(chain).unwrap();
+    assert_eq!(880726, signal);
+    assert_eq!(Some(880726), chain(&best));
+
+    // the strongest single amplifier stage for a zero input signal
+    let stages = Sweep::new(program).inputs((0..5).map(|phase| vec![phase, 0]));
+    let (best, out) = stages.best(PolyIntCode::new, |comp| comp.out().first().copied()).unwrap().unwrap();
+    let mut comp = ProcIntCode::new(read("res/07.txt"), best.inputs);
+    comp.run();
+    assert_eq!(out, comp.out()[0]);
+}
+
+#[test]
+fn peephole() {
+    let program: Vec<i64> = vec![
+        1102, 6, 7, 30,     // 0: mul 6, 7, [30]
+        1006, 30, 11,       // 4: jf [30], 11
+        1106, 0, 14,        // 7: jf 0, 14
+        99,                 // 10: hlt
+        1105, 1, 7,         // 11: jt 1, 7
+        1105, 1, 17,        // 14: jt 1, 17
+        4, 30,              // 17: out [30]
+        1106, 1, 25,        // 19: jf 1, 25
+        1007, 30, 50, 31,   // 22: lt [30], 50, [31]
+        99,                 // 26: hlt
+    ];
+    let (optimized, report) = optimize::optimize_checked(&program, &[vec![]]).unwrap();
+    assert_eq!(Report { folded: 1, jumps: 2, threaded: 3 }, report);
+    assert_eq!(vec![1101, 42, 0, 30], optimized[0..4]);
+    // jf [30], 11 -> jt 1, 7 -> jf 0, 14 -> jt 1, 17
+    assert_eq!(17, optimized[6]);
+    assert_eq!(vec![1105, 1, 17], optimized[7..10]);
+    assert_eq!(vec![1105, 1, 22], optimized[19..22]);
+    let mut before = ProcIntCode::new(program.clone(), vec![]);
+    let mut after = ProcIntCode::new(optimized.clone(), vec![]);
+    before.run();
+    after.run();
+    assert_eq!(before.out(), after.out());
+    assert!(after.cycles() < before.cycles());
+    let mut broken = optimized;
+    broken[18] = 31;
+    assert!(optimize::check(&program, &broken, &[vec![]]).is_err());
+
+    // code a program reads or writes as data is left alone: day 5 rewrites its own
+    // instruction at 6 before running it
+    let day5 = read("res/05.txt");
+    let (optimized, _) = optimize::optimize_checked(&day5, &[vec![1], vec![5], vec![8]]).unwrap();
+    assert_eq!(day5[6..10], optimized[6..10]);
+    let day9 = read("res/09.txt");
+    assert!(optimize::optimize_checked(&day9, &[vec![1]]).is_ok());
+}
+
+#[test]
+fn decompile() {
+    let program: Vec<i64> = vec![
+        3, 100,                 // 0: in [100]
+        109, 200,               // 2: arb 200
+        21001, 100, 0, 1,       // 4: add [100], 0, [rb+1]
+        21101, 15, 0, 0,        // 8: add 15, 0, [rb+0]
+        1105, 1, 34,            // 12: jt 1, 34
+        204, 1,                 // 15: out [rb+1]
+        1007, 102, 3, 103,      // 17: lt [102], 3, [103]
+        1006, 103, 33,          // 21: jf [103], 33
+        4, 102,                 // 24: out [102]
+        1001, 102, 1, 102,      // 26: add [102], 1, [102]
+        1105, 1, 17,            // 30: jt 1, 17
+        99,                     // 33: hlt
+        109, 2,                 // 34: arb 2
+        1207, -1, 10, 101,      // 36: lt [rb-1], 10, [101]
+        1006, 101, 50,          // 40: jf [101], 50
+        21201, -1, 1, -1,       // 43: add [rb-1], 1, [rb-1]
+        1105, 1, 54,            // 47: jt 1, 54
+        21202, -1, 2, -1,       // 50: mul [rb-1], 2, [rb-1]
+        109, -2,                // 54: arb -2
+        2106, 0, 0,             // 56: jf 0, [rb+0]
+    ];
+    let mut comp = ProcIntCode::new(program.clone(), vec![20]);
+    comp.run();
+    assert_eq!(&vec![40, 0, 1, 2], comp.out());
+    let expected = "\
+void main() {
+    in100 = input();
+    rb += 200;
+    f34(in100);
+    output(r1);
+    while (g102 < 3) {
+        output(g102);
+        g102 = g102 + 1;
+    }
+    halt();
+}
+
+void f34(a1) {
+    if (a1 < 10) {
+        a1 = a1 + 1;
+    } else {
+        a1 = a1 * 2;
+    }
+    return;
+}
+";
+    assert_eq!(expected, decompile::decompile(&program));
+
+    // day 13's screen-drawing loop, with the tile lookup as a call
+    let day13 = decompile::decompile(&read("res/13.txt"));
+    assert!(day13.contains("\
+    do {
+        g382 = 0;
+        do {
+            f578(g382, g383);
+            output(g382);
+            output(g383);
+            output(r1);
+            g382 = g382 + 1;
+        } while (g382 < 38);
+        g383 = g383 + 1;
+    } while (g383 < 21);
+"));
+    assert!(day13.contains("void f456(a1, a2, a3, a4) {"));
+}
+
+#[test]
+fn aot() {
+    let patch = vec![
+        1101, 3, 4, 30,         // 0: add 3, 4, [30]
+        4, 30,                  // 4: out [30]
+        1101, 1102, 0, 0,       // 6: add 1102, 0, [0], turning the first add into a mul
+        1001, 31, 1, 31,        // 10: add [31], 1, [31]
+        1007, 31, 2, 32,        // 14: lt [31], 2, [32]
+        1005, 32, 0,            // 18: jt [32], 0
+        99,                     // 21: hlt
+    ];
+    assert_eq!(include_str!("aot/day09.rs"), aot::compile(&read("res/09.txt"), "Day9"));
+    assert_eq!(include_str!("aot/day13.rs"), aot::compile(&read("res/13.txt"), "Day13"));
+    assert_eq!(include_str!("aot/patch.rs"), aot::compile(&patch, "Patch"));
+
+    for input in [1, 2] {
+        let mut native = compiled::Day9::new(vec![input]);
+        let mut interpreted = ProcIntCode::new(read("res/09.txt"), vec![input]);
+        assert_eq!(State::Halted, native.run());
+        interpreted.run();
+        assert_eq!(interpreted.out(), native.out());
+        assert_eq!(interpreted.cycles(), native.cycles());
+        assert!(!native.runtime().stale());
+    }
+
+    // day 13 draws its screen by rewriting instruction operands, which stays native
+    let mut native = compiled::Day13::new(vec![]);
+    let mut interpreted = ProcIntCode::new(read("res/13.txt"), vec![]);
+    assert_eq!(State::Halted, native.run());
+    interpreted.run();
+    assert_eq!(interpreted.out(), native.out());
+    assert_eq!(interpreted.cycles(), native.cycles());
+    assert!(!native.runtime().stale());
+
+    // rewriting an opcode that already ran falls back to the interpreter
+    let mut native = compiled::Patch::new(vec![]);
+    assert_eq!(State::Halted, native.run());
+    assert_eq!(&vec![7, 12], native.out());
+    assert!(native.runtime().stale());
+    assert_eq!(1102, native.mem(0));
+}
+
+// compiles `source` and runs it to a halt on both backends, which have to agree
+fn run_lang(source: &str, inputs: Vec<i64>) -> Vec<i64> {
+    let image = lang::compile(source).unwrap_or_else(|err| panic!("{}", err));
+    let mut proc = ProcIntCode::new(image.clone(), inputs.clone());
+    let mut poly = PolyIntCode::new(image, inputs);
+    assert_eq!(State::Halted, proc.run());
+    assert_eq!(State::Halted, poly.run());
+    assert_eq!(proc.out(), poly.out());
+    proc.out().clone()
+}
+
+#[test]
+fn high_level_language() {
+    let fib = "
+        var primes[5] = { 2, 3, 5, 7, 11 };
+        var calls = 0;
+
+        fn fib(n) {
+            calls = calls + 1;
+            if (n < 2) { return n; }
+            return fib(n - 1) + fib(n - 2);
+        }
+
+        fn main() {
+            var i = input();
+            while (i < 5) {
+                output(fib(primes[i]));
+                i = i + 1;
+            }
+            output(calls);
+        }
+    ";
+    assert_eq!(vec![1, 2, 5, 13, 89, 351], run_lang(fib, vec![0]));
+    assert_eq!(vec![89, 287], run_lang(fib, vec![4]));
+
+    let sort = "
+        var a[8] = { 5, -3, 9, 0, 12, 7, -8, 1 };
+
+        fn swap(i, j) {
+            var t = a[i];
+            a[i] = a[j];
+            a[j] = t;
+        }
+
+        fn main() {
+            var i = 0;
+            while (i < 8) {
+                var j = i + 1;
+                while (j < 8) {
+                    if (a[j] < a[i]) { swap(i, j); }
+                    j = j + 1;
+                }
+                i = i + 1;
+            }
+            // everything but the zero
+            i = 0;
+            while (1) {
+                if (i >= 8) { break; }
+                if (a[i] == 0) { i = i + 1; continue; }
+                output(a[i]);
+                i = i + 1;
+            }
+        }
+    ";
+    assert_eq!(vec![-8, -3, 1, 5, 7, 9, 12], run_lang(sort, vec![]));
+
+    let operators = "
+        var g = 1;
+        fn bump() { g = g * 10; return g; }
+        fn add3(a, b, c) { return a + b + c; }
+        fn ack(m, n) {
+            if (m == 0) { return n + 1; }
+            else if (n == 0) { return ack(m - 1, 1); }
+            return ack(m - 1, ack(m, n - 1));
+        }
+        fn main() {
+            output(g + bump());
+            output(add3(g, bump(), g));
+            output(add3(1, add3(2, 3, 4), add3(5, 6, 7)));
+            output(ack(2, 3));
+            output(0 && bump());
+            output(1 || bump());
+            output(g);
+            output(3 > 2 && 2 >= 2 && 1 <= 0 == 0 && 4 != 5);
+            output(-input() - -2 * 3);
+            output(!0 + !7);
+        }
+    ";
+    // operands are read left to right, so a call only affects what comes after it
+    assert_eq!(vec![11, 210, 28, 9, 0, 1, 100, 1, 2, 1], run_lang(operators, vec![4]));
+
+    let err = |source: &str| lang::compile(source).unwrap_err();
+    assert_eq!(CompileError { line: 1, msg: "`x` isn't declared".to_string() }, err("fn main() { x = 1; }"));
+    assert_eq!(
+        CompileError { line: 2, msg: "`f` takes 1 argument, not 2".to_string() },
+        err("fn f(a) { return a; }\nfn main() { f(1, 2); }"),
+    );
+    assert_eq!(CompileError { line: 1, msg: "not inside a loop".to_string() }, err("fn main() { break; }"));
+    assert_eq!(CompileError { line: 1, msg: "there's no `main` function".to_string() }, err("var x;"));
+    assert_eq!("line 3: expected `;`, found `}`", err("fn main() {\n  output(1)\n}").to_string());
+}
+
+#[test]
+fn instruction_decoding() {
+    let image: Vec<i64> = vec![21101, 3, -4, 7, 204, -1, 80, 99];
+    let add = instruction::decode(&image[..], 0).unwrap();
+    assert_eq!(Op::Add, add.op);
+    let modes: Vec<ParamMode> = add.operands().iter().map(|operand| operand.mode).collect();
+    assert_eq!(vec![ParamMode::Immediate, ParamMode::Immediate, ParamMode::Relative], modes);
+    assert_eq!(4, add.size());
+    assert_eq!(image[..4].to_vec(), add.encode());
+    let out = Instruction::new(Op::Write, &[Operand { mode: ParamMode::Relative, val: -1 }]);
+    assert_eq!(Ok(out), instruction::decode(&image[..], 4));
+    assert_eq!(Op::Syscall, instruction::decode(&image[..], 6).unwrap().op);
+    assert_eq!(1, instruction::decode(&image[..], 7).unwrap().size());
+
+    let err = |image: &[i64]| instruction::decode(image, 0).unwrap_err();
+    assert_eq!(DecodeError::UnknownOpcode(42), err(&[42]));
+    assert_eq!(DecodeError::BadModes(304), err(&[304, 0]));
+    // digits past the last parameter's mode don't matter
+    let out = Instruction::new(Op::Write, &[Operand { mode: ParamMode::Immediate, val: 0 }]);
+    assert_eq!(Ok(out), instruction::decode(&[10104i64, 0][..], 0));
+    assert_eq!(Ok(Instruction::new(Op::Halt, &[])), instruction::decode(&[90099i64][..], 0));
+    assert_eq!(DecodeError::BadWord, err(&[-1]));
+    assert_eq!(DecodeError::Truncated, err(&[1101, 1, 2]));
+    assert_eq!(DecodeError::Truncated, instruction::decode(&image[..], 8).unwrap_err());
+    assert!(instruction::decode(&[i128::MAX, 0, 0, 0][..], 0) == Err(DecodeError::BadWord));
+
+    // both machines fault on whatever the decoder rejects, and so does the disassembler
+    for (image, err) in [
+        (vec![104i64, 1, 42], DecodeError::UnknownOpcode(42)),
+        (vec![104, 1, 304, 0], DecodeError::BadModes(304)),
+    ] {
+        let pc = 2;
+        let fault = State::Faulted(Fault::Decode { pc, err });
+        let mut proc = ProcIntCode::new(image.clone(), vec![]);
+        let mut poly = PolyIntCode::new(image.clone(), vec![]);
+        assert_eq!(State::Running, proc.step());
+        assert_eq!(State::Running, poly.step());
+        assert_eq!(fault, proc.state());
+        assert_eq!(fault, poly.state());
+        assert_eq!(fault, proc.run());
+        assert_eq!(fault, poly.run());
+        assert_eq!(&vec![1], proc.out());
+        assert_eq!(&vec![1], poly.out());
+        assert_eq!(pc, proc.pc());
+        assert_eq!(pc, poly.pc());
+        assert_eq!(None, disasm::decode(&image, pc));
+    }
+
+    // and run the stray digits the way the baseline machines did
+    let image: Vec<i64> = vec![10104, 7, 99];
+    let mut proc = ProcIntCode::new(image.clone(), vec![]);
+    let mut poly = PolyIntCode::new(image, vec![]);
+    assert_eq!(State::Halted, proc.run());
+    assert_eq!(State::Halted, poly.run());
+    assert_eq!(&vec![7], proc.out());
+    assert_eq!(&vec![7], poly.out());
+
+    // everything the disassembler accepts re-encodes to the same words
+    let program = read("res/09.txt");
+    for line in disasm::disassemble(&program) {
+        if let disasm::Line::Instr { addr, words, .. } = line {
+            assert_eq!(words, instruction::decode(&program[..], addr).unwrap().encode());
+        }
+    }
+}
+
+fn introspect(comp: &mut impl IntCodeComputer) {
+    // add 2, 3, [12]; out [12]; hlt
+    assert_eq!(7, comp.image_size());
+    assert_eq!((0..7).collect::<Vec<i64>>(), comp.touched());
+    assert_eq!(vec![1101, 2, 3], comp.peek(0..3));
+    comp.push(5);
+    comp.push(6);
+    assert_eq!(&[5, 6], comp.inputs());
+
+    assert_eq!(State::Running, comp.step());
+    assert_eq!(4, comp.pc());
+    assert_eq!(vec![0, 1, 2, 3, 4, 5, 6, 12], comp.touched());
+    assert_eq!(vec![5, 0], comp.peek(12..14));
+
+    // point the output at the opcode word and run the add again from the top
+    comp.poke(5, 0);
+    comp.set_pc(0);
+    comp.set_rb(-10);
+    assert_eq!(-10, comp.rb());
+    assert_eq!(State::Running, comp.step());
+    assert_eq!(State::Running, comp.step());
+    assert_eq!(&vec![1101], comp.out());
+    assert_eq!(State::Halted, comp.run());
+    assert_eq!(&[5, 6], comp.inputs());
+    assert_eq!(7, comp.image_size());
+}
+
+#[test]
+fn introspection() {
+    let program = vec![1101, 2, 3, 12, 4, 12, 99];
+    introspect(&mut ProcIntCode::new(program.clone(), vec![]));
+    introspect(&mut PolyIntCode::new(program.clone(), vec![]));
+    introspect(&mut Recorder::new(ProcIntCode::new(program.clone(), vec![]), &program));
+    introspect(&mut aot::Runtime::new(program.clone(), vec![], &[], |_| Ok(State::Running)));
+
+    let mut comp: ProcIntCode = ProcIntCode::new(vec![3, 0, 99], vec![7, 8]);
+    comp.step();
+    assert_eq!(&[8], comp.inputs());
+    assert_eq!(comp.queued(), comp.inputs().len());
+}
+
+fn day2_with_reset<C: IntCodeComputer>(comp: &mut C, reset: fn(&mut C)) -> i64 {
+    for noun in 0..=99 {
+        for verb in 0..=99 {
+            reset(comp);
+            comp.poke(1, noun);
+            comp.poke(2, verb);
+            comp.run();
+            if comp.mem(0) == 19690720 {
+                return 100 * noun + verb;
+            }
+        }
+    }
+    panic!("no noun and verb give 19690720");
+}
+
+#[test]
+fn reset() {
+    let mut proc = ProcIntCode::new(read("res/02.txt"), vec![]);
+    let mut poly = PolyIntCode::shared(proc.image(), vec![]);
+    assert!(Arc::ptr_eq(&proc.image(), &poly.image()));
+    assert_eq!(5696, day2_with_reset(&mut proc, ProcIntCode::reset));
+    assert_eq!(5696, day2_with_reset(&mut poly, PolyIntCode::reset));
+    // writes stay with the machine that made them
+    assert_eq!(56, proc.mem(1));
+    assert_eq!(56, poly.mem(1));
+    proc.reset();
+    assert_eq!(*proc.image(), proc.peek(0..proc.image_size()));
+    assert_eq!(96, poly.mem(2));
+
+    // machines started on one image each see only their own pokes
+    let image = proc.image();
+    let mut first = ProcIntCode::shared(image.clone(), vec![]);
+    let second = PolyIntCode::shared(image.clone(), vec![]);
+    first.poke(1, 12);
+    first.poke(2, 2);
+    assert_eq!(4484226, day2_part1(&mut first));
+    assert_eq!(image[..3].to_vec(), second.peek(0..3));
+    assert_eq!(image[0], proc.mem(0));
+
+    // add 2, 3, [20]; out [20]; then a bad opcode
+    let program: Vec<i64> = vec![1101, 2, 3, 20, 4, 20, 42];
+    let fault = State::Faulted(Fault::Decode { pc: 6, err: DecodeError::UnknownOpcode(42) });
+    let sandbox = Sandbox { max_cells: Some(8), ..Sandbox::default() };
+    let mut proc = ProcIntCode::new(program.clone(), vec![3]).with_sandbox(sandbox);
+    proc.enable_recording(2, 2);
+    for _ in 0..2 {
+        assert_eq!(fault, proc.run());
+        assert_eq!(&vec![5], proc.out());
+        assert_eq!(vec![0, 1, 2, 3, 4, 5, 6, 20], proc.touched());
+        assert_eq!(2, proc.history().unwrap().steps());
+        proc.reset();
+        assert_eq!(State::Running, proc.state());
+        assert_eq!((0, 0, 0), (proc.pc(), proc.rb(), proc.cycles()));
+        assert!(proc.out().is_empty() && proc.inputs().is_empty());
+        assert_eq!((0..7).collect::<Vec<i64>>(), proc.touched());
+        assert_eq!(0, proc.mem(20));
+        assert_eq!(0, proc.history().unwrap().steps());
+    }
+
+    // add 1, 1100, [0] rewrites code that already ran
+    let mut poly: PolyIntCode = PolyIntCode::new(vec![1101, 1, 1100, 0, 99], vec![])
+        .with_protection(Protection::new(OnViolation::Log));
+    for _ in 0..2 {
+        assert_eq!(State::Halted, poly.run());
+        assert_eq!(vec![Violation::SelfModify { pc: 0, addr: 0 }], poly.violations());
+        poly.reset();
+        assert!(poly.violations().is_empty());
+    }
+}
+
+#[test]
+fn oracle() {
+    // day 7 part 1 with each amplifier as a function call
+    let amp = Oracle::new(read("res/07.txt")).with_cache();
+    let chain = |phases: &Vec<i64>| phases.iter().fold(0, |signal, phase| amp.call(&[*phase, signal]).unwrap()[0]);
+    let perms = unique_perms(43210, 0, 4);
+    let best = std::thread::scope(|scope| {
+        let workers: Vec<_> = perms.chunks(20)
+            .map(|chunk| scope.spawn(move || chunk.iter().map(chain).max().unwrap()))
+            .collect();
+        workers.into_iter().map(|worker| worker.join().unwrap()).max().unwrap()
+    });
+    assert_eq!(880726, best);
+    // the first amplifier always sees a zero signal, so permutations share its answers
+    assert!(amp.cached() < 5 * perms.len());
+    assert!(amp.pooled() >= 1);
+    // asking everything again is answered from the cache
+    let cached = amp.cached();
+    assert_eq!(best, perms.iter().map(chain).max().unwrap());
+    assert_eq!(cached, amp.cached());
+
+    // in [9]; out [9]; in [9]; out [9]; hlt
+    let echo = Oracle::new(vec![3i64, 9, 4, 9, 3, 9, 4, 9, 99, 0]).into_fn();
+    assert_eq!(Ok(vec![1, 2]), echo(&[1, 2]));
+    assert_eq!(Ok(vec![1, 2]), echo(&[1, 2, 3]));
+    assert_eq!(Err(CallError::Starved { outputs: vec![1] }), echo(&[1]));
+
+    let fault = Fault::Decode { pc: 0, err: DecodeError::UnknownOpcode(42) };
+    assert_eq!(Err(CallError::Faulted(fault)), Oracle::new(vec![42i64]).call(&[]));
+
+    // jt 1, 0 spins forever; out 7; hlt takes exactly one cycle
+    let spin = Oracle::new(vec![1105i64, 1, 0]).with_max_cycles(1000);
+    assert_eq!(Err(CallError::CycleLimit), spin.call(&[]));
+    assert_eq!(Ok(vec![7]), Oracle::new(vec![104i64, 7, 99]).with_max_cycles(1).call(&[]));
+}
+
+// a repair droid in the 9x7 maze that `Maze::render` should reproduce below
+const DROID: &str = "
+    // 1 is a wall, 2 the target
+    var grid[63] = {
+        1, 1, 1, 1, 1, 1, 1, 1, 1,
+            1, 0, 0, 0, 1, 0, 0, 0, 1,
+            1, 0, 1, 0, 1, 0, 1, 0, 1,
+            1, 0, 1, 0, 0, 0, 1, 2, 1,
+            1, 0, 1, 1, 1, 1, 1, 0, 1,
+            1, 0, 0, 0, 0, 0, 0, 0, 1,
+            1, 1, 1, 1, 1, 1, 1, 1, 1
+    };
+    var x = 1;
+    var y = 5;
+
+    fn main() {
+        while (1) {
+            var dir = input();
+            var nx = x;
+            var ny = y;
+            if (dir == 1) { ny = y - 1; }
+            else if (dir == 2) { ny = y + 1; }
+            else if (dir == 3) { nx = x - 1; }
+            else { nx = x + 1; }
+            var tile = grid[ny * 9 + nx];
+            if (tile == 1) {
+                output(0);
+            } else {
+                x = nx;
+                y = ny;
+                if (tile == 2) { output(2); } else { output(1); }
+            }
+        }
+    }
+";
+
+#[test]
+fn maze_exploration() {
+    let droid = lang::compile(DROID).unwrap();
+    let mut proc = ProcIntCode::new(droid.clone(), vec![]);
+    let depth_first = Maze::explore(&mut proc).unwrap();
+    let breadth_first = Maze::explore_forking(&PolyIntCode::new(droid.clone(), vec![])).unwrap();
+    let map = concat!(
+        " ### ### \n",
+        "#...#...#\n",
+        "#.#.#.#.#\n",
+        "#.#...#O#\n",
+        "#.#####.#\n",
+        "#S......#\n",
+        " ####### \n",
+    );
+    for maze in [&depth_first, &breadth_first] {
+        assert_eq!(map, maze.render());
+        assert_eq!(Some((6, -2)), maze.target());
+        assert_eq!(Some(Tile::Wall), maze.tile((-1, 0)));
+        assert_eq!(None, maze.tile((7, 1)));
+        let path = maze.path_to_target().unwrap();
+        assert_eq!(8, path.len());
+        assert_eq!(&[Dir::East; 6], &path[..6]);
+        let fill = maze.distances(maze.target().unwrap());
+        assert_eq!(24, fill.len());
+        assert_eq!(Some(&12), fill.values().max());
+        assert_eq!(Some(&8), fill.get(&maze::START));
+        assert_eq!(Some(vec![]), maze.path((2, -2), (2, -2)));
+        assert_eq!(None, maze.path(maze::START, (7, 1)));
+    }
+
+    // depth first leaves the droid back at the start, so the path leads it to the target
+    let path = depth_first.path_to_target().unwrap();
+    for dir in path.iter() {
+        proc.push(dir.code());
+    }
+    proc.run();
+    assert_eq!(Some(&2), proc.out().last());
+
+    let err = Maze::explore(&mut ProcIntCode::new(vec![3, 0, 99], vec![])).unwrap_err();
+    assert_eq!(ExploreError::Stopped { pos: maze::START, dir: Dir::North, state: State::Halted }, err);
+    // in [20]; out 7; jt 1, 0
+    let err = Maze::explore(&mut ProcIntCode::new(vec![3, 20, 104, 7, 1105, 1, 0], vec![])).unwrap_err();
+    assert_eq!(ExploreError::BadReply { pos: maze::START, dir: Dir::North, outputs: vec![7] }, err);
+}
+
+// Line at a time programs written in Rust rather than Intcode, which harnesses built on
+// `IntCodeComputer` should drive all the same
+trait Talk {
+    fn greet(&mut self) -> Vec<i64>;
+    // what to print in answer to a line and whether that ends the program, or `None` to hang
+    fn answer(&mut self, line: &str) -> Option<(Vec<i64>, bool)>;
+}
+
+fn chars(text: &str) -> Vec<i64> {
+    text.bytes().map(i64::from).collect()
+}
+
+struct Chat<T> {
+    talk: T,
+    inputs: Vec<i64>,
+    outputs: Vec<i64>,
+    state: State,
+    cycles: u64,
+    stuck: bool,
+}
+
+impl<T: Talk> Chat<T> {
+    fn new(talk: T) -> Chat<T> {
+        Chat { talk, inputs: Vec::new(), outputs: Vec::new(), state: State::Running, cycles: 0, stuck: false }
+    }
+}
+
+impl<T: Talk> IntCodeComputer for Chat<T> {
+    fn run(&mut self) -> State {
+        loop {
+            match self.step() {
+                State::Running => continue,
+                state => return state,
+            }
+        }
+    }
+
+    fn out(&self) -> &Vec<i64> {
+        &self.outputs
+    }
+
+    fn push(&mut self, val: i64) {
+        self.inputs.push(val);
+    }
+
+    fn mem(&self, _at: i64) -> i64 {
+        0
+    }
+
+    fn state(&self) -> State {
+        self.state.clone()
+    }
+
+    // the greeting, then one line per step
+    fn step(&mut self) -> State {
+        if self.state == State::Halted {
+            return State::Halted;
+        }
+        let (out, halted) = match self.inputs.iter().position(|c| *c == 10) {
+            _ if self.stuck => (Vec::new(), false),
+            _ if self.cycles == 0 => (self.talk.greet(), false),
+            Some(end) => {
+                let line: Vec<i64> = self.inputs.drain(..=end).collect();
+                match self.talk.answer(&ascii::decode(&line[..end])) {
+                    Some(answer) => answer,
+                    None => {
+                        self.stuck = true;
+                        (Vec::new(), false)
+                    },
+                }
+            },
+            None => {
+                self.state = State::Waiting;
+                return State::Waiting;
+            },
+        };
+        self.cycles += 1;
+        self.outputs.extend(out);
+        self.state = if halted { State::Halted } else { State::Running };
+        self.state.clone()
+    }
+
+    fn cycles(&self) -> u64 {
+        self.cycles
+    }
+
+    fn queued(&self) -> usize {
+        self.inputs.len()
+    }
+
+    fn pc(&self) -> i64 {
+        0
+    }
+
+    fn set_pc(&mut self, _pc: i64) {}
+
+    fn rb(&self) -> i64 {
+        0
+    }
+
+    fn set_rb(&mut self, _rb: i64) {}
+
+    fn poke(&mut self, _at: i64, _val: i64) {}
+
+    fn inputs(&self) -> &[i64] {
+        &self.inputs
+    }
+
+    fn image_size(&self) -> i64 {
+        0
+    }
+
+    fn touched(&self) -> Vec<i64> {
+        Vec::new()
+    }
+}
+
+// (name, doors) for each room of a small ship, and the items lying around in each
+const SHIP: [(&str, &[(&str, usize)]); 6] = [
+    ("Hull Breach", &[("north", 1), ("east", 3)]),
+    ("Kitchen", &[("south", 0), ("east", 2)]),
+    ("Arcade", &[("west", 1), ("south", 3)]),
+    ("Stables", &[("north", 2), ("west", 0), ("east", 4)]),
+    ("Security Checkpoint", &[("west", 3), ("north", 5)]),
+    ("Pressure-Sensitive Floor", &[("south", 4)]),
+];
+const LOOT: [&[&str]; 6] = [&[], &["mug", "ornament"], &["infinite loop"], &["dark matter", "molten lava"], &["hologram"], &[]];
+
+fn weight(item: &str) -> u32 {
+    match item {
+        "mug" => 1,
+        "dark matter" => 2,
+        "hologram" => 4,
+        _ => 8,
+    }
+}
+
+// A text adventure played out in Rust rather than Intcode. Only the combined weight of 11
+// gets past the floor.
+struct Ship {
+    at: usize,
+    loot: Vec<Vec<&'static str>>,
+    carrying: Vec<&'static str>,
+    halted: bool,
+    // took the infinite loop, and won't even answer that
+    stuck: bool,
+}
+
+impl Ship {
+    fn new() -> Ship {
+        Ship {
+            at: 0,
+            loot: LOOT.iter().map(|items| items.to_vec()).collect(),
+            carrying: Vec::new(),
+            halted: false,
+            stuck: false,
+        }
+    }
+
+    fn describe(&self, room: usize) -> String {
+        let (name, doors) = SHIP[room];
+        let mut text = format!("\n\n\n== {} ==\nIt's a room.\n\nDoors here lead:\n", name);
+        for (door, _) in doors.iter() {
+            text += &format!("- {}\n", door);
+        }
+        if !self.loot[room].is_empty() {
+            text += "\nItems here:\n";
+            for item in self.loot[room].iter() {
+                text += &format!("- {}\n", item);
+            }
+        }
+        text
+    }
+
+    fn command(&mut self, command: &str) -> String {
+        let (_, doors) = SHIP[self.at];
+        if let Some((_, to)) = doors.iter().find(|(door, _)| *door == command) {
+            if *to != 5 {
+                self.at = *to;
+                return self.describe(self.at) + "\nCommand?\n";
+            }
+            let total: u32 = self.carrying.iter().map(|item| weight(item)).sum();
+            let floor = self.describe(5).replace("It's a room.", "Analyzing...");
+            if total == 11 {
+                self.halted = true;
+                return floor + "\n\"Oh, hello! You should be able to get in by typing 2424 on the keypad.\"\n";
+            }
+            let than = if total < 11 { "heavier" } else { "lighter" };
+            return format!(
+                "{}\nA loud, robotic voice says \"Alert! Droids on this ship are {} than the detected value!\" \
+                 and you are ejected back to the checkpoint.\n{}\nCommand?\n",
+                floor, than, self.describe(self.at),
+            );
+        }
+        if let Some(item) = command.strip_prefix("take ") {
+            let found = self.loot[self.at].iter().position(|held| *held == item);
+            if let Some(found) = found {
+                let item = self.loot[self.at].remove(found);
+                match item {
+                    "molten lava" => self.halted = true,
+                    "infinite loop" => self.stuck = true,
+                    _ => self.carrying.push(item),
+                }
+                return format!("\nYou take the {}.\n\nCommand?\n", item);
+            }
+        }
+        if let Some(item) = command.strip_prefix("drop ") {
+            if let Some(found) = self.carrying.iter().position(|held| *held == item) {
+                let item = self.carrying.remove(found);
+                self.loot[self.at].push(item);
+                return format!("\nYou drop the {}.\n\nCommand?\n", item);
+            }
+        }
+        if command == "inv" && self.carrying.is_empty() {
+            return "\nYou aren't carrying any items.\n\nCommand?\n".to_string();
+        }
+        if command == "inv" {
+            let items: String = self.carrying.iter().map(|item| format!("- {}\n", item)).collect();
+            return format!("\nItems in your inventory:\n{}\nCommand?\n", items);
+        }
+        "\nYou can't do that.\n\nCommand?\n".to_string()
+    }
+}
+
+impl Talk for Ship {
+    fn greet(&mut self) -> Vec<i64> {
+        chars(&(self.describe(0) + "\nCommand?\n"))
+    }
+
+    fn answer(&mut self, line: &str) -> Option<(Vec<i64>, bool)> {
+        let text = self.command(line);
+        match self.stuck {
+            true => None,
+            false => Some((chars(&text), self.halted)),
+        }
+    }
+}
+
+#[test]
+fn text_adventure() {
+    let text = "\n\n\n== Pressure-Sensitive Floor ==\nAnalyzing...\n\nDoors here lead:\n- south\n\n\
+        A loud voice says you are ejected back to the checkpoint.\n\n\n\n== Security Checkpoint ==\n\
+        In the next room, a pressure-sensitive floor will verify your identity.\nStay clear.\n\n\
+        Doors here lead:\n- north\n- west\n\nItems here:\n- hologram\n\nCommand?\n";
+    let rooms = adventure::parse_rooms(text);
+    assert_eq!(2, rooms.len());
+    assert_eq!("Pressure-Sensitive Floor", rooms[0].name);
+    assert_eq!(vec!["south"], rooms[0].doors);
+    assert_eq!(adventure::Room {
+        name: "Security Checkpoint".to_string(),
+        description: "In the next room, a pressure-sensitive floor will verify your identity.\nStay clear.".to_string(),
+        doors: vec!["north".to_string(), "west".to_string()],
+        items: vec!["hologram".to_string()],
+    }, rooms[1]);
+    let inv = "\nItems in your inventory:\n- mug\n- dark matter\n\nCommand?\n";
+    assert_eq!(Some(vec!["mug".to_string(), "dark matter".to_string()]), adventure::parse_inventory(inv));
+    assert_eq!(None, adventure::parse_inventory(text));
+    assert_eq!(Some("2424".to_string()), adventure::keypad_code("get in by typing 2424 on the keypad"));
+
+    let mut session = Session::new(Chat::new(Ship::new()));
+    let world = World::explore(&mut session, &adventure::TRAPS).unwrap();
+    assert_eq!(6, world.rooms.len());
+    assert_eq!(vec!["infinite loop"], world.rooms["Arcade"].items);
+    assert_eq!(Some(("Security Checkpoint".to_string(), "north".to_string())), world.checkpoint);
+    assert_eq!(Some(&"Stables".to_string()), world.doors.get(&("Arcade".to_string(), "south".to_string())));
+    assert_eq!("Hull Breach", session.room().unwrap().name);
+    assert_eq!(Some(vec!["east".to_string(), "east".to_string()]), world.route("Hull Breach", "Security Checkpoint"));
+    let mut carried = session.inventory().unwrap();
+    carried.sort();
+    assert_eq!(vec!["dark matter", "hologram", "mug", "ornament"], carried);
+
+    let reply = adventure::crack(&mut session, &world).unwrap();
+    assert_eq!(State::Halted, reply.state);
+    assert_eq!(Some("2424".to_string()), adventure::keypad_code(&reply.text));
+    assert_eq!("north", session.transcript().last().unwrap());
+
+    // one combination per bit of a u64, so a droid carrying 64 items gives up up front
+    let mut hoarder = Ship::new();
+    hoarder.carrying = (0..64).map(|i| &*Box::leak(format!("widget {}", i).into_boxed_str())).collect();
+    let mut overloaded = Session::new(Chat::new(hoarder));
+    overloaded.start();
+    let err = adventure::crack(&mut overloaded, &world).unwrap_err();
+    assert_eq!(adventure::AdventureError::TooManyItems { count: 64 }, err);
+
+    // a saved transcript plays back to the same ending
+    let saved = session.save();
+    assert_eq!(session.transcript().len(), saved.lines().count());
+    let mut replayed = Session::new(Chat::new(Ship::new()));
+    assert_eq!(reply, replayed.load(&saved).unwrap());
+
+    // taking a trap that never returns runs out of cycles instead of hanging
+    let mut stuck = Session::new(Chat::new(Ship::new())).with_max_cycles(1000);
+    assert_eq!(State::Waiting, stuck.send("north").state);
+    assert_eq!(State::Waiting, stuck.send("east").state);
+    assert_eq!(State::Running, stuck.send("take infinite loop").state);
+    let mut melted = Session::new(Chat::new(Ship::new()));
+    let err = melted.load("east\ntake molten lava\nwest\n").unwrap_err();
+    assert!(matches!(err, adventure::AdventureError::Stopped { ref command, .. } if command == "take molten lava"));
+
+    let mut played = Vec::new();
+    let mut session = Session::new(Chat::new(Ship::new()));
+    adventure::play(&mut session, &b"n\ninv\n!history\n"[..], &mut played).unwrap();
+    let played = String::from_utf8(played).unwrap();
+    assert!(played.starts_with("\n\n\n== Hull Breach =="));
+    assert!(played.contains("== Kitchen =="));
+    assert!(played.contains("You aren't carrying any items."));
+    assert!(played.ends_with("   1  north\n   2  inv\n"));
+}
+
+// stretches of hull the springdroid has to get across, the last only when running
+const HULLS: [&str; 4] = ["#####.###########", "#####..#.########", "#####...#########", "#####.#.##..#####"];
+
+// Runs whatever springscript it's given over `HULLS`, printing every step of the first fall
+#[derive(Default)]
+struct Springdroid {
+    script: Vec<String>,
+}
+
+impl Springdroid {
+    fn jumps(&self, ground: &[bool]) -> bool {
+        let (mut t, mut j) = (false, false);
+        for line in self.script.iter() {
+            let words: Vec<&str> = line.split(' ').collect();
+            let x = match words[1] {
+                "T" => t,
+                "J" => j,
+                sensor => ground[(sensor.as_bytes()[0] - b'A') as usize],
+            };
+            let y = if words[2] == "T" { &mut t } else { &mut j };
+            *y = match words[0] {
+                "AND" => x && *y,
+                "OR" => x || *y,
+                _ => !x,
+            };
+        }
+        j
+    }
+
+    // the frames up to the fall, or `None` if it made it
+    fn cross(&self, hull: &str) -> Option<String> {
+        let hull = hull.as_bytes();
+        let frame = |x: usize, row: usize| -> String {
+            let mut rows = vec![vec![b'.'; hull.len()]; 3];
+            rows.push(hull.to_vec());
+            rows[row][x] = b'@';
+            rows.iter().map(|row| String::from_utf8(row.clone()).unwrap() + "\n").collect::<String>() + "\n"
+        };
+        let mut frames = frame(0, 2);
+        let mut x = 0;
+        while x + 4 < hull.len() {
+            let ground: Vec<bool> = (1..=9).map(|d| hull.get(x + d) != Some(&b'.')).collect();
+            let path: &[(usize, usize)] = if self.jumps(&ground) { &[(1, 1), (2, 0), (3, 1), (4, 2)] } else { &[(1, 2)] };
+            for (dx, row) in path.iter() {
+                if *row == 2 && hull[x + dx] == b'.' {
+                    return Some(frames + &frame(x + dx, 3));
+                }
+                frames += &frame(x + dx, *row);
+            }
+            x += path.len();
+        }
+        None
+    }
+}
+
+impl Talk for Springdroid {
+    fn greet(&mut self) -> Vec<i64> {
+        chars("Input instructions:\n")
+    }
+
+    fn answer(&mut self, line: &str) -> Option<(Vec<i64>, bool)> {
+        let (moving, hulls, damage) = match line {
+            "WALK" => ("Walking", 3, 19352638),
+            "RUN" => ("Running", 4, 1141251258),
+            _ => {
+                self.script.push(line.to_string());
+                return Some((Vec::new(), false));
+            },
+        };
+        let mut out = chars(&format!("\n{}...\n\n", moving));
+        match HULLS[..hulls].iter().find_map(|hull| self.cross(hull)) {
+            Some(frames) => out.extend(chars(&format!("\nDidn't make it across:\n\n{}", frames))),
+            None => out.push(damage),
+        }
+        Some((out, true))
+    }
+}
+
+fn spring(script: &Script, mode: Mode) -> Result<Outcome, RunError> {
+    springscript::run(&mut Chat::new(Springdroid::default()), script, mode)
+}
+
+#[test]
+fn springdroid() {
+    let script = Script::parse("NOT A J\n\nNOT B T\nOR T J\n").unwrap();
+    assert_eq!(3, script.instrs().len());
+    assert_eq!("NOT A J\nNOT B T\nOR T J\n", script.to_string());
+    assert_eq!(Err(ScriptError::Syntax { line: 1, text: "JUMP A J".to_string() }), Script::parse("JUMP A J"));
+    assert_eq!(Err(ScriptError::BadRegister { line: 1, name: "X".to_string() }), Script::parse("AND X J"));
+    assert_eq!(Err(ScriptError::ReadOnly { line: 3, reg: Reg::Sensor(1) }), Script::parse("NOT A J\n\nOR J A"));
+    let far = Script::parse("NOT E J").unwrap();
+    assert_eq!(Ok(()), far.validate(Mode::Run));
+    assert_eq!(Err(ScriptError::OutOfRange { line: 1, reg: Reg::Sensor(5), mode: Mode::Walk }), far.validate(Mode::Walk));
+    let long = Script::new(vec![Instr { gate: Gate::Not, x: Reg::Sensor(1), y: Reg::J }; 16]);
+    assert_eq!(Err(ScriptError::TooLong { len: 16 }), long.validate(Mode::Walk));
+
+    // compiled scripts jump exactly when their formulas say so, on every reading
+    assert_eq!(Err(ScriptError::BadExpression { at: 8 }), Expr::parse("A & (B |"));
+    let walk = Expr::parse("!(A & B & C) & D").unwrap();
+    let run = Expr::parse("(!A | !B | !C) & D & (E | H)").unwrap();
+    let walk_script = walk.compile().unwrap();
+    let run_script = run.compile().unwrap();
+    assert_eq!(5, walk_script.instrs().len());
+    assert_eq!(8, run_script.instrs().len());
+    // parity of three sensors takes exactly ten instructions, and a limit is inclusive
+    let parity = Expr::parse("(A & !B & !C) | (!A & B & !C) | (!A & !B & C) | (A & B & C)").unwrap();
+    let parity_script = parity.shortest(10).unwrap();
+    assert_eq!(10, parity_script.instrs().len());
+    assert_eq!(None, parity.shortest(9));
+    assert_eq!(parity_script, parity.compile().unwrap());
+    for readings in 0..512 {
+        let ground: [bool; 9] = std::array::from_fn(|i| readings >> i & 1 == 1);
+        assert_eq!(walk.eval(&ground), walk_script.eval(&ground));
+        assert_eq!(run.eval(&ground), run_script.eval(&ground));
+        assert_eq!(parity.eval(&ground), parity_script.eval(&ground));
+    }
+
+    // only jumping at the edge of a hole walks into the second hull's double hole
+    let failure = match spring(&Script::parse("NOT A J").unwrap(), Mode::Walk) {
+        Ok(Outcome::Fell(failure)) => failure,
+        other => panic!("{:?}", other),
+    };
+    assert!(failure.text.contains("\nWalking...\n"));
+    assert_eq!(9, failure.frames.len());
+    assert_eq!(Some(8), failure.hole());
+    assert_eq!(&HULLS[1].bytes().map(|c| c == b'#').collect::<Vec<_>>(), &failure.frames[0].hull);
+    assert_eq!((6, 0), failure.frames[6].droid);
+    let decision = failure.last_decision().unwrap();
+    assert_eq!((4, 2), decision.droid);
+    assert_eq!(vec![false, false, true, false, true, true, true, true, true], decision.sensors());
+
+    assert_eq!(Ok(Outcome::Across { damage: 19352638 }), spring(&walk_script, Mode::Walk));
+    assert!(matches!(spring(&walk_script, Mode::Run), Ok(Outcome::Fell(_))));
+    assert_eq!(Ok(Outcome::Across { damage: 1141251258 }), spring(&run_script, Mode::Run));
+    assert_eq!(
+        Err(RunError::Invalid(ScriptError::OutOfRange { line: 1, reg: Reg::Sensor(5), mode: Mode::Walk })),
+        spring(&far, Mode::Walk),
+    );
+    let mut done = Chat::new(Springdroid::default());
+    springscript::run(&mut done, &walk_script, Mode::Walk).unwrap();
+    assert!(matches!(springscript::run(&mut done, &walk_script, Mode::Walk), Err(RunError::Stopped { state: State::Halted, .. })));
+}
+
+// the scaffold from the example for waking the robot up
+const SCAFFOLD: &str = "\
+#######...#####
+#.....#...#...#
+#.....#...#...#
+......#...#...#
+......#...###.#
+......#.....#.#
+^########...#.#
+......#.#...#.#
+......#########
+........#...#..
+....#########..
+....#...#......
+....#...#......
+....#...#......
+....#####......
+";
+
+// A woken-up vacuum robot that shows `SCAFFOLD`, takes its routines, and reports a hundred
+// dust for every cell it went over, unless it falls off
+#[derive(Default)]
+struct Vacuum {
+    lines: Vec<String>,
+}
+
+impl Vacuum {
+    fn sweep(&self) -> Option<i64> {
+        let rows: Vec<&[u8]> = SCAFFOLD.lines().map(str::as_bytes).collect();
+        let mut pos = (0i64, 6i64);
+        let mut dir = Dir::North;
+        let mut visited = vec![pos];
+        for call in self.lines[0].split(',') {
+            let function = &self.lines[1 + (call.as_bytes()[0] - b'A') as usize];
+            for step in function.split(',') {
+                match step {
+                    "L" => dir = dir.left(),
+                    "R" => dir = dir.right(),
+                    n => for _ in 0..n.parse().unwrap() {
+                        pos = dir.step(pos);
+                        if rows.get(pos.1 as usize).and_then(|row| row.get(pos.0 as usize)) != Some(&b'#') {
+                            return None;
+                        }
+                        visited.push(pos);
+                    },
+                }
+            }
+        }
+        visited.sort();
+        visited.dedup();
+        Some(visited.len() as i64 * 100)
+    }
+}
+
+impl Talk for Vacuum {
+    fn greet(&mut self) -> Vec<i64> {
+        chars(&format!("{}\nMain:\n", SCAFFOLD))
+    }
+
+    fn answer(&mut self, line: &str) -> Option<(Vec<i64>, bool)> {
+        assert!(line.len() <= scaffold::MAX_LEN);
+        self.lines.push(line.to_string());
+        let prompt = match self.lines.len() {
+            1 => "Function A:\n",
+            2 => "Function B:\n",
+            3 => "Function C:\n",
+            4 => "Continuous video feed?\n",
+            _ => return match self.sweep() {
+                Some(dust) => Some((vec![dust], true)),
+                None => Some((chars("\nThe robot fell off the scaffold!\n"), true)),
+            },
+        };
+        Some((chars(prompt), false))
+    }
+}
+
+#[test]
+fn scaffold_cleaning() {
+    let camera = Camera::parse("..#..........\n..#..........\n#######...###\n#.#...#...#.#\n\
+        #############\n..#...#...#..\n..#####...^..\n\n");
+    assert_eq!((13, 7), (camera.width(), camera.height()));
+    assert_eq!(Some(((10, 6), Dir::North)), camera.robot());
+    assert_eq!(vec![(2, 2), (2, 4), (6, 4), (10, 4)], camera.intersections());
+    assert_eq!(76, camera.alignment());
+
+    let mut comp = Chat::new(Vacuum::default());
+    assert_eq!(State::Waiting, comp.run());
+    let camera = Camera::read(comp.out());
+    assert_eq!(15, camera.height());
+    let path = camera.path().unwrap();
+    assert_eq!("R,8,R,8,R,4,R,4,R,8,L,6,L,2,R,4,R,4,R,8,R,8,R,8,L,6,L,2", scaffold::render(&path));
+    let routines = scaffold::compress(&path).unwrap();
+    assert_eq!(path, routines.expand());
+    let lines = routines.lines();
+    assert_eq!(4, lines.len());
+    assert!(lines.iter().all(|line| line.len() <= scaffold::MAX_LEN));
+
+    // every cell of scaffold, the robot's own included, gets swept once
+    let cells = SCAFFOLD.bytes().filter(|c| matches!(c, b'#' | b'^')).count() as i64;
+    assert_eq!(Ok(cells * 100), scaffold::feed(&mut comp, &routines, false));
+    assert_eq!(Ok(cells * 100), scaffold::collect_dust(&mut Chat::new(Vacuum::default())));
+
+    let mut comp = Chat::new(Vacuum::default());
+    comp.run();
+    let wrong = Routines { main: vec![0], functions: vec![vec![Step::Forward(2)]] };
+    match scaffold::feed(&mut comp, &wrong, false) {
+        Err(ScaffoldError::NoDust { text }) => assert!(text.ends_with("The robot fell off the scaffold!\n")),
+        other => panic!("{:?}", other),
+    }
+    // three 20 character functions can't hold ten different five digit moves
+    let distinct: Vec<Step> = (0..10).map(|i| Step::Forward(10000 + i)).collect();
+    assert_eq!(None, scaffold::compress(&distinct));
+}
+
+// a beam fanning out between slopes 0.7 and 0.9, missing a few rows near the origin
+const BEAM: &str = "
+    fn main() {
+        var x = input();
+        var y = input();
+        if (10 * x >= 7 * y && 10 * x <= 9 * y) { output(1); } else { output(0); }
+    }
+";
+
+fn pulled(x: i64, y: i64) -> bool {
+    10 * x >= 7 * y && 10 * x <= 9 * y
+}
+
+#[test]
+fn tractor_beam() {
+    let image = lang::compile(BEAM).unwrap();
+    let beam = Beam::new(image.clone()).with_threads(4);
+    assert_eq!(Ok(true), beam.probe((0, 0)));
+    assert_eq!(Ok(false), beam.probe((1, 1)));
+    assert_eq!(Ok(true), beam.probe((7, 9)));
+
+    let grid = beam.scan(50, 40).unwrap();
+    assert_eq!(40, grid.len());
+    for (y, row) in grid.iter().enumerate() {
+        assert_eq!(50, row.len());
+        for (x, cell) in row.iter().enumerate() {
+            assert_eq!(pulled(x as i64, y as i64), *cell, "({}, {})", x, y);
+        }
+    }
+    let expected = (0..50).flat_map(|x| (0..40).map(move |y| (x, y))).filter(|(x, y)| pulled(*x, *y)).count();
+    assert_eq!(Ok(expected), beam.count(50, 40));
+    // everything was asked twice, but run once
+    assert_eq!(2000, beam.probes());
+    assert_eq!(beam.scan(50, 40), Beam::new(image.clone()).with_threads(1).scan(50, 40));
+
+    // tracing only probes around the edges of each row
+    let traced = Beam::new(image.clone());
+    let spans = traced.trace(300).unwrap();
+    for (y, span) in spans.iter().enumerate() {
+        let y = y as i64;
+        let cells: Vec<i64> = (0..=y).filter(|x| pulled(*x, y)).collect();
+        assert_eq!(cells.first().map(|start| (*start, *cells.last().unwrap())), *span, "row {}", y);
+    }
+    assert_eq!(Some((0, 0)), spans[0]);
+    assert_eq!(None, spans[1]);
+    assert!(traced.probes() < 1500);
+
+    // the first 10x10 square by its bottom row, checked against every cell, and no empty ones
+    let (x, y) = traced.fit_square(10, 1000).unwrap().unwrap();
+    assert!((0..10).all(|dy| (0..10).all(|dx| pulled(x + dx, y + dy))));
+    let fits = |x: i64, y: i64| pulled(x, y + 9) && pulled(x + 9, y);
+    assert!((0..y).all(|top| (0..=top).all(|left| !fits(left, top))));
+    assert_eq!(Ok(None), traced.fit_square(10, 50));
+    assert_eq!(Ok(None), traced.fit_square(0, 1000));
+    assert_eq!(Ok(None), traced.fit_square(-3, 1000));
+
+    // in [x]; in [y]; out 2; hlt
+    let broken = Beam::new(vec![3, 0, 3, 0, 104, 2, 99]);
+    assert_eq!(Err(ProbeError::BadAnswer { pos: (3, 4), outputs: vec![2] }), broken.probe((3, 4)));
+    // in [x]; in [y]; out 1; hlt
+    let everywhere = Beam::new(vec![3, 0, 3, 0, 104, 1, 99]);
+    assert_eq!(Err(ProbeError::Unbounded { y: 0 }), everywhere.span(3));
+
+    // threads tracing the same beam at once agree with one tracing it alone
+    let shared = Beam::new(image.clone());
+    let traces: Vec<_> = std::thread::scope(|scope| {
+        let workers: Vec<_> = (0..4).map(|_| scope.spawn(|| shared.trace(300))).collect();
+        workers.into_iter().map(|worker| worker.join().unwrap()).collect()
+    });
+    assert!(traces.iter().all(|trace| *trace == Ok(spans.clone())));
+}